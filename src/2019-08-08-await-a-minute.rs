@@ -2,6 +2,14 @@
 
 <sup>*by [David Tolnay]&#8202;,&ensp;2019.08.08*</sup>
 
+<nav class="contents">
+
+- [Comprehensible error handling](#comprehensible-error-handling)
+- [Native control flow](#native-control-flow)
+- [Borrowing](#borrowing)
+
+</nav>
+
 [David Tolnay]: https://github.com/dtolnay
 
 <br>
@@ -2,6 +2,16 @@
 
 <sup>*by [David Tolnay]&#8202;,&ensp;2019.10.01*</sup>
 
+<nav class="contents">
+
+- [The beginner's understanding](#the-beginners-understanding)
+- [It falls apart](#it-falls-apart)
+- [Better names](#better-names)
+- [Pedagogy](#pedagogy)
+- [Addendum: interior mutability](#addendum-interior-mutability)
+
+</nav>
+
 [David Tolnay]: https://github.com/dtolnay
 
 <br>
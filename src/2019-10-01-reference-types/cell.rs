@@ -0,0 +1,109 @@
+//! [`Cell<T>`] &mdash; we can perform mutation even when other references to
+//! the same `Cell<T>` may exist.
+//!
+//! ```
+//! use dtolnay::reference_types::cell::Cell;
+//!
+//! let cell = Cell::new(1);
+//! let a = &cell;
+//! let b = &cell;
+//! a.set(b.get() + 1);
+//! assert_eq!(b.get(), 2);
+//! ```
+//!
+//! It's safe because the API enforces:
+//!
+//! - it's impossible for more than one thread to hold references to the same
+//!   `Cell<T>` at a time because `Cell<T>` does not implement the `Sync`
+//!   trait, i.e. `Cell<T>` is single threaded;
+//!
+//!   ```compile_fail,E0277
+//!   use dtolnay::reference_types::cell::Cell;
+//!   use std::thread;
+//!
+//!   let cell = Cell::new(1);
+//!   thread::scope(|scope| {
+//!       scope.spawn(|| cell.set(2));
+//!       scope.spawn(|| cell.set(3));
+//!   });
+//!   ```
+//!
+//! - and it's impossible to obtain a reference to the contents within the
+//!   `Cell<T>`, as such references could be invalidated by a mutation;
+//!   instead all access is done by copying data out of the cell.
+//!
+//!   ```compile_fail,E0599
+//!   use dtolnay::reference_types::cell::Cell;
+//!
+//!   let cell = Cell::new(String::from("no copy"));
+//!   let string = cell.get();
+//!   ```
+//!
+//!   Types that are not `Copy` can still be moved in and out whole.
+//!
+//!   ```
+//!   use dtolnay::reference_types::cell::Cell;
+//!
+//!   let cell = Cell::new(String::from("before"));
+//!   let before = cell.replace(String::from("after"));
+//!   assert_eq!(before, "before");
+//!   assert_eq!(cell.into_inner(), "after");
+//!   ```
+
+use std::cell::UnsafeCell;
+
+/// A mutable memory location, like [`std::cell::Cell`].
+pub struct Cell<T> {
+    value: UnsafeCell<T>,
+}
+
+// Not Sync because UnsafeCell<T> is not Sync. Send whenever T is Send.
+
+impl<T> Cell<T> {
+    pub const fn new(value: T) -> Self {
+        Cell {
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    pub fn replace(&self, value: T) -> T {
+        // SAFETY: no reference into the cell's contents can exist, and no
+        // other thread can be accessing it because Cell is not Sync. The old
+        // value is moved out before anything that might run user code, such
+        // as its destructor.
+        unsafe { self.value.get().replace(value) }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Exclusive access to the contents, which is fine to hand out as a
+    /// reference because nobody else can call `set` while it exists.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Copy> Cell<T> {
+    pub fn get(&self) -> T {
+        // SAFETY: as in `replace`; copying out cannot run user code.
+        unsafe { *self.value.get() }
+    }
+}
+
+impl<T: Default> Cell<T> {
+    pub fn take(&self) -> T {
+        self.replace(T::default())
+    }
+}
+
+impl<T: Default> Default for Cell<T> {
+    fn default() -> Self {
+        Cell::new(T::default())
+    }
+}
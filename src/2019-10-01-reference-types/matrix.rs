@@ -0,0 +1,230 @@
+//! Which operations through a shared reference `&T` or an exclusive reference
+//! `&mut T` the compiler accepts, one doctest per cell of the matrix.
+//!
+//! | | mutate | share with another thread |
+//! |---|---|---|
+//! | `&T` | E0594, E0596 | ok if `T: Sync` |
+//! | `&mut T` | ok | ok if `T: Send` |
+//! | `&Cell<T>` | ok | E0277 |
+//! | `&RefCell<T>` | ok | E0277 |
+//! | `&AtomicU32` | ok | ok |
+//! | `&Mutex<T>` | ok | ok if `T: Send` |
+//!
+//! Exclusive really means exclusive: two `&mut` to the same value cannot
+//! coexist (E0499), nor can a `&mut` coexist with a `&` (E0502).
+//!
+//! The doctests are carried by items of this module that are hidden from the
+//! documentation, one per cell, such as `MutateThroughShared`. A
+//! `compile_fail` test passes only if compilation fails, and on a nightly
+//! toolchain only if it fails with the listed error code.
+
+macro_rules! matrix {
+    ($($(#[doc = $doc:literal])* $name:ident = $lang:literal $code:literal;)*) => {
+        $(
+            $(#[doc = $doc])*
+            #[doc = ""]
+            #[doc = concat!("```", $lang, $code, "```")]
+            #[doc(hidden)]
+            pub struct $name;
+        )*
+    };
+}
+
+matrix! {
+    /// Assigning to a field through `&T` does not compile.
+    MutateThroughShared = "compile_fail,E0594" r"
+# struct Point {
+#     x: u32,
+#     y: u32,
+# }
+#
+fn embiggen_x(pt: &Point) {
+    pt.x = pt.x * 2;
+}
+";
+
+    /// Calling a `&mut self` method through `&T` does not compile either.
+    MutateVecThroughShared = "compile_fail,E0596" r"
+fn append(vec: &Vec<u32>) {
+    vec.push(1);
+}
+";
+
+    /// Anything goes through `&mut T`.
+    MutateThroughExclusive = "" r"
+# struct Point {
+#     x: u32,
+#     y: u32,
+# }
+#
+fn embiggen_x(pt: &mut Point) {
+    pt.x = pt.x * 2;
+}
+";
+
+    /// `Cell` allows mutation through a shared reference by copying values in
+    /// and out.
+    MutateThroughCell = "" r"
+use std::cell::Cell;
+
+fn embiggen(x: &Cell<u32>) {
+    x.set(x.get() * 2);
+}
+";
+
+    /// `RefCell` allows mutation through a shared reference with dynamically
+    /// checked borrows.
+    MutateThroughRefCell = "" r"
+use std::cell::RefCell;
+
+fn append(vec: &RefCell<Vec<u32>>) {
+    vec.borrow_mut().push(1);
+}
+";
+
+    /// `AtomicU32` allows mutation through a shared reference, which is the
+    /// signature that motivates the essay.
+    MutateThroughAtomic = "" r"
+use std::sync::atomic::{AtomicU32, Ordering};
+
+fn reset(counter: &AtomicU32) {
+    counter.store(0, Ordering::Release);
+}
+";
+
+    /// `Mutex` allows mutation through a shared reference by taking turns.
+    MutateThroughMutex = "" r"
+use std::sync::Mutex;
+
+fn append(vec: &Mutex<Vec<u32>>) {
+    vec.lock().unwrap().push(1);
+}
+";
+
+    /// Two exclusive references to the same value cannot coexist.
+    AliasTwoExclusive = "compile_fail,E0499" r"
+let mut x = 0;
+let a = &mut x;
+let b = &mut x;
+*a += 1;
+*b += 1;
+";
+
+    /// An exclusive reference cannot coexist with a shared one.
+    AliasSharedAndExclusive = "compile_fail,E0502" r#"
+let mut x = 0;
+let a = &x;
+let b = &mut x;
+*b += 1;
+println!("{}", a);
+"#;
+
+    /// A shared reference to a `Sync` type can be used from several threads
+    /// at once.
+    ShareU32AcrossThreads = "" r"
+use std::thread;
+
+let x = 1u32;
+thread::scope(|scope| {
+    scope.spawn(|| x + 1);
+    scope.spawn(|| x + 2);
+});
+";
+
+    /// An exclusive reference can be sent to another thread if the referent
+    /// is `Send`, even if it is not `Sync`, because no other thread can be
+    /// looking at it.
+    SendExclusiveCellAcrossThreads = "" r"
+use std::cell::Cell;
+use std::thread;
+
+let mut x = Cell::new(1);
+let exclusive = &mut x;
+thread::scope(|scope| {
+    scope.spawn(move || exclusive.set(2));
+});
+assert_eq!(x.get(), 2);
+";
+
+    /// But not if the referent is not `Send`: an `Rc` must stay on the thread
+    /// that owns its reference count, exclusive access or not.
+    SendExclusiveRcAcrossThreads = "compile_fail,E0277" r"
+use std::rc::Rc;
+use std::thread;
+
+let mut x = Rc::new(1);
+let exclusive = &mut x;
+thread::scope(|scope| {
+    scope.spawn(move || {
+        *Rc::make_mut(exclusive) = 2;
+    });
+});
+";
+
+    /// `Cell` is not `Sync`, so a shared reference to one cannot be used from
+    /// another thread.
+    ShareCellAcrossThreads = "compile_fail,E0277" r"
+use std::cell::Cell;
+use std::thread;
+
+let x = Cell::new(1);
+thread::scope(|scope| {
+    scope.spawn(|| x.set(2));
+    scope.spawn(|| x.set(3));
+});
+";
+
+    /// `RefCell` is not `Sync` either.
+    ShareRefCellAcrossThreads = "compile_fail,E0277" r"
+use std::cell::RefCell;
+use std::thread;
+
+let vec = RefCell::new(Vec::new());
+thread::scope(|scope| {
+    scope.spawn(|| vec.borrow_mut().push(1));
+    scope.spawn(|| vec.borrow_mut().push(2));
+});
+";
+
+    /// `AtomicU32` is `Sync`: concurrent mutation through shared references
+    /// is the whole point of atomics.
+    ShareAtomicAcrossThreads = "" r"
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+let counter = AtomicU32::new(0);
+thread::scope(|scope| {
+    scope.spawn(|| counter.fetch_add(1, Ordering::Relaxed));
+    scope.spawn(|| counter.fetch_add(1, Ordering::Relaxed));
+});
+assert_eq!(counter.into_inner(), 2);
+";
+
+    /// `Mutex<T>` is `Sync` whenever `T` is `Send`.
+    ShareMutexAcrossThreads = "" r"
+use std::sync::Mutex;
+use std::thread;
+
+let vec = Mutex::new(Vec::new());
+thread::scope(|scope| {
+    scope.spawn(|| vec.lock().unwrap().push(1));
+    scope.spawn(|| vec.lock().unwrap().push(2));
+});
+assert_eq!(vec.into_inner().unwrap().len(), 2);
+";
+
+    /// A `Mutex` around something that is not `Send` is not `Sync`, since
+    /// whichever thread takes the lock gets to touch the contents.
+    ShareMutexRcAcrossThreads = "compile_fail,E0277" r"
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::thread;
+
+let rc = Mutex::new(Rc::new(1));
+thread::scope(|scope| {
+    scope.spawn(|| {
+        let _ = rc.lock().unwrap();
+    });
+});
+";
+}
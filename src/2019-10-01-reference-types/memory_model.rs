@@ -0,0 +1,355 @@
+//! What the `Ordering` parameter of `AtomicU32::store` means, explored by
+//! exhaustively running small programs over simulated atomics.
+//!
+//! ```
+//! # use std::sync::atomic::{AtomicU32, Ordering};
+//! #
+//! static COUNTER: AtomicU32 = AtomicU32::new(0);
+//!
+//! fn reset() {
+//!     COUNTER.store(0, Ordering::Release);
+//! }
+//! ```
+//!
+//! The essay sets this parameter aside because it has nothing to do with
+//! whether the store is allowed through a shared reference: every ordering
+//! makes the store itself atomic. What the ordering controls is what *other*
+//! memory a thread is guaranteed to see once it observes the store.
+//!
+//! A [`Program`] is a handful of threads, each a straight-line list of
+//! [`Instr`] over a few simulated atomics. [`Program::outcomes`] enumerates
+//! every interleaving of the threads and, at each load, every value the load
+//! is permitted to return, and collects the distinct results.
+//!
+//! # Message passing
+//!
+//! One thread writes some data, then resets `COUNTER` to signal that the data
+//! is ready. Another thread observes the reset, then reads the data.
+//!
+//! ```
+//! use dtolnay::reference_types::memory_model::{Instr, Program};
+//! use std::sync::atomic::Ordering;
+//!
+//! fn message_passing(store: Ordering, load: Ordering) -> Program {
+//!     let mut program = Program::new();
+//!     let data = program.atomic(0);
+//!     let counter = program.atomic(1);
+//!     program.thread([
+//!         Instr::Store(data, 42, Ordering::Relaxed),
+//!         Instr::Store(counter, 0, store),
+//!     ]);
+//!     program.thread([
+//!         Instr::Load(counter, load),
+//!         Instr::Load(data, Ordering::Relaxed),
+//!     ]);
+//!     program
+//! }
+//!
+//! // Observed the reset but not the data.
+//! let stale = |reads: &[Vec<u32>]| reads[1] == [0, 0];
+//!
+//! let relaxed = message_passing(Ordering::Relaxed, Ordering::Relaxed);
+//! assert!(relaxed.outcomes().iter().any(|outcome| stale(&outcome.reads)));
+//!
+//! let release_acquire = message_passing(Ordering::Release, Ordering::Acquire);
+//! assert!(!release_acquire.outcomes().iter().any(|outcome| stale(&outcome.reads)));
+//!
+//! // Release on its own is not enough; the reader has to acquire.
+//! let release_relaxed = message_passing(Ordering::Release, Ordering::Relaxed);
+//! assert!(release_relaxed.outcomes().iter().any(|outcome| stale(&outcome.reads)));
+//! ```
+//!
+//! # Store buffering
+//!
+//! Each thread stores to one atomic and then loads the other. Release and
+//! acquire only order a thread's own earlier accesses before its later store,
+//! and its later accesses after its earlier load, so both threads may miss
+//! each other's store. Only `SeqCst` puts all four accesses in one total order.
+//!
+//! ```
+//! use dtolnay::reference_types::memory_model::{Instr, Program};
+//! use std::sync::atomic::Ordering;
+//!
+//! fn store_buffering(store: Ordering, load: Ordering) -> Program {
+//!     let mut program = Program::new();
+//!     let x = program.atomic(0);
+//!     let y = program.atomic(0);
+//!     program.thread([Instr::Store(x, 1, store), Instr::Load(y, load)]);
+//!     program.thread([Instr::Store(y, 1, store), Instr::Load(x, load)]);
+//!     program
+//! }
+//!
+//! let both_missed = |reads: &[Vec<u32>]| reads[0] == [0] && reads[1] == [0];
+//!
+//! let release_acquire = store_buffering(Ordering::Release, Ordering::Acquire);
+//! assert!(release_acquire.outcomes().iter().any(|outcome| both_missed(&outcome.reads)));
+//!
+//! let seq_cst = store_buffering(Ordering::SeqCst, Ordering::SeqCst);
+//! assert!(!seq_cst.outcomes().iter().any(|outcome| both_missed(&outcome.reads)));
+//! ```
+//!
+//! # The model
+//!
+//! Each atomic keeps the history of every value stored to it, in modification
+//! order. Each thread keeps a view of how far along each history it is known
+//! to have seen. A load may return any value at or after the thread's view of
+//! that atomic, not just the latest one; this is where weak behaviors come
+//! from. A release store records the storing thread's view alongside the
+//! value, and an acquire load that reads such a value merges that view into
+//! its own. Read-modify-write operations always act on the latest value.
+//! `SeqCst` accesses additionally synchronize with a single global view shared
+//! by all `SeqCst` accesses.
+//!
+//! This is a simplification of the C++20 memory model that Rust uses. Every
+//! outcome it produces is one that the real model allows, but it does not
+//! produce every outcome the real model allows. In particular a store is
+//! always placed last in modification order, and loads are never satisfied
+//! by stores that come later in the same thread's program order, so outcomes
+//! such as "load buffering" are not explored. Fences are not modeled.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
+
+/// A simulated `AtomicU32`, created by [`Program::atomic`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Atomic(usize);
+
+/// One operation on a simulated atomic.
+#[derive(Copy, Clone, Debug)]
+pub enum Instr {
+    /// Like `AtomicU32::load`. The value read is recorded in the outcome.
+    Load(Atomic, Ordering),
+    /// Like `AtomicU32::store`.
+    Store(Atomic, u32, Ordering),
+    /// Like `AtomicU32::fetch_add`. The previous value is recorded in the
+    /// outcome.
+    FetchAdd(Atomic, u32, Ordering),
+}
+
+/// A fixed set of threads operating on a fixed set of atomics.
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    init: Vec<u32>,
+    threads: Vec<Vec<Instr>>,
+}
+
+/// One possible result of running a program.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Outcome {
+    /// For each thread, the values returned by its loads and read-modify-write
+    /// operations in program order.
+    pub reads: Vec<Vec<u32>>,
+    /// Final value of each atomic, in order of creation.
+    pub memory: Vec<u32>,
+}
+
+// Per atomic, an index into its history.
+type View = Vec<usize>;
+
+#[derive(Clone)]
+struct Message {
+    value: u32,
+    // What a thread that acquires this message is guaranteed to have seen.
+    view: View,
+}
+
+#[derive(Clone)]
+struct State {
+    pcs: Vec<usize>,
+    views: Vec<View>,
+    sc: View,
+    history: Vec<Vec<Message>>,
+    reads: Vec<Vec<u32>>,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Program::default()
+    }
+
+    /// Adds an atomic with the given initial value.
+    pub fn atomic(&mut self, init: u32) -> Atomic {
+        self.init.push(init);
+        Atomic(self.init.len() - 1)
+    }
+
+    /// Adds a thread that runs the given instructions in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the same invalid orderings as the corresponding methods of
+    /// `AtomicU32`, such as a `Release` load.
+    pub fn thread(&mut self, instrs: impl IntoIterator<Item = Instr>) {
+        let instrs: Vec<Instr> = instrs.into_iter().collect();
+        for instr in &instrs {
+            match *instr {
+                Instr::Load(atomic, ordering) => {
+                    assert!(
+                        !matches!(ordering, Ordering::Release | Ordering::AcqRel),
+                        "there is no such thing as a release load",
+                    );
+                    self.check(atomic);
+                }
+                Instr::Store(atomic, _, ordering) => {
+                    assert!(
+                        !matches!(ordering, Ordering::Acquire | Ordering::AcqRel),
+                        "there is no such thing as an acquire store",
+                    );
+                    self.check(atomic);
+                }
+                Instr::FetchAdd(atomic, _, _) => self.check(atomic),
+            }
+        }
+        self.threads.push(instrs);
+    }
+
+    fn check(&self, atomic: Atomic) {
+        assert!(
+            atomic.0 < self.init.len(),
+            "atomic from a different program"
+        );
+    }
+
+    /// Every distinct outcome of every execution permitted by the model.
+    pub fn outcomes(&self) -> BTreeSet<Outcome> {
+        let atomics = self.init.len();
+        let state = State {
+            pcs: vec![0; self.threads.len()],
+            views: vec![vec![0; atomics]; self.threads.len()],
+            sc: vec![0; atomics],
+            history: self
+                .init
+                .iter()
+                .map(|&value| {
+                    vec![Message {
+                        value,
+                        view: vec![0; atomics],
+                    }]
+                })
+                .collect(),
+            reads: vec![Vec::new(); self.threads.len()],
+        };
+        let mut outcomes = BTreeSet::new();
+        self.explore(&state, &mut outcomes);
+        outcomes
+    }
+
+    fn explore(&self, state: &State, outcomes: &mut BTreeSet<Outcome>) {
+        let mut finished = true;
+        for (thread, instrs) in self.threads.iter().enumerate() {
+            let Some(&instr) = instrs.get(state.pcs[thread]) else {
+                continue;
+            };
+            finished = false;
+            for mut next in step(state, thread, instr) {
+                next.pcs[thread] += 1;
+                self.explore(&next, outcomes);
+            }
+        }
+        if finished {
+            outcomes.insert(Outcome {
+                reads: state.reads.clone(),
+                memory: state
+                    .history
+                    .iter()
+                    .map(|history| history.last().unwrap().value)
+                    .collect(),
+            });
+        }
+    }
+}
+
+// Every state that can result from the given thread executing one instruction.
+fn step(state: &State, thread: usize, instr: Instr) -> Vec<State> {
+    let mut state = state.clone();
+    let ordering = match instr {
+        Instr::Load(_, ordering)
+        | Instr::Store(_, _, ordering)
+        | Instr::FetchAdd(_, _, ordering) => ordering,
+    };
+    let seq_cst = ordering == Ordering::SeqCst;
+    if seq_cst {
+        join(&mut state.views[thread], &state.sc);
+    }
+
+    let mut successors = Vec::new();
+    match instr {
+        Instr::Load(Atomic(atomic), _) => {
+            for index in state.views[thread][atomic]..state.history[atomic].len() {
+                let mut next = state.clone();
+                let message = &state.history[atomic][index];
+                next.views[thread][atomic] = index;
+                if is_acquire(ordering) {
+                    join(&mut next.views[thread], &message.view);
+                }
+                next.reads[thread].push(message.value);
+                successors.push(next);
+            }
+        }
+        Instr::Store(Atomic(atomic), value, _) => {
+            let index = state.history[atomic].len();
+            state.views[thread][atomic] = index;
+            let view = if is_release(ordering) {
+                state.views[thread].clone()
+            } else {
+                singleton(state.sc.len(), atomic, index)
+            };
+            state.history[atomic].push(Message { value, view });
+            successors.push(state);
+        }
+        Instr::FetchAdd(Atomic(atomic), addend, _) => {
+            let index = state.history[atomic].len();
+            let prev = state.history[atomic][index - 1].clone();
+            state.views[thread][atomic] = index;
+            if is_acquire(ordering) {
+                join(&mut state.views[thread], &prev.view);
+            }
+            // Continues the release sequence of the value it replaces.
+            let mut view = if is_release(ordering) {
+                state.views[thread].clone()
+            } else {
+                singleton(state.sc.len(), atomic, index)
+            };
+            join(&mut view, &prev.view);
+            state.history[atomic].push(Message {
+                value: prev.value.wrapping_add(addend),
+                view,
+            });
+            state.reads[thread].push(prev.value);
+            successors.push(state);
+        }
+    }
+
+    if seq_cst {
+        for next in &mut successors {
+            join(&mut next.sc, &next.views[thread]);
+        }
+    }
+    successors
+}
+
+fn is_acquire(ordering: Ordering) -> bool {
+    matches!(
+        ordering,
+        Ordering::Acquire | Ordering::AcqRel | Ordering::SeqCst
+    )
+}
+
+fn is_release(ordering: Ordering) -> bool {
+    matches!(
+        ordering,
+        Ordering::Release | Ordering::AcqRel | Ordering::SeqCst
+    )
+}
+
+fn singleton(atomics: usize, atomic: usize, index: usize) -> View {
+    let mut view = vec![0; atomics];
+    view[atomic] = index;
+    view
+}
+
+fn join(view: &mut View, other: &View) {
+    for (mine, theirs) in view.iter_mut().zip(other) {
+        *mine = (*mine).max(*theirs);
+    }
+}
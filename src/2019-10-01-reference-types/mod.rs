@@ -0,0 +1,25 @@
+//! Companion code for *Accurate mental model for Rust's reference types*.
+//!
+//! The essay's addendum lists the interior mutability types of the standard
+//! library and the invariant through which each one makes mutation through a
+//! shared reference safe. The modules [`cell`], [`refcell`], [`mutex`] and
+//! [`rwlock`] are minimal from-scratch implementations of those four types,
+//! each built directly on [`UnsafeCell`] and, for the thread safe ones,
+//! atomics. They leave out most of the standard library's API in order to
+//! keep the unsafe code small enough to audit at a glance.
+//!
+//! The [`matrix`] module checks the essay's mental model exhaustively with
+//! the compiler: for each kind of reference, whether mutating through it and
+//! sharing it across threads compiles.
+//!
+//! The [`memory_model`] module follows up on the `Ordering` argument of
+//! `AtomicU32::store`, which the essay sets aside.
+//!
+//! [`UnsafeCell`]: core::cell::UnsafeCell
+
+pub mod cell;
+pub mod matrix;
+pub mod memory_model;
+pub mod mutex;
+pub mod refcell;
+pub mod rwlock;
@@ -0,0 +1,146 @@
+//! [`Mutex<T>`] &mdash; we can perform mutation even when other references to
+//! the same `Mutex<T>` may exist.
+//!
+//! ```
+//! use dtolnay::reference_types::mutex::Mutex;
+//! use std::thread;
+//!
+//! let mutex = Mutex::new(Vec::new());
+//! thread::scope(|scope| {
+//!     scope.spawn(|| mutex.lock().push(1));
+//!     scope.spawn(|| mutex.lock().push(2));
+//! });
+//! let mut vec = mutex.into_inner();
+//! vec.sort();
+//! assert_eq!(vec, [1, 2]);
+//! ```
+//!
+//! It's safe because the API enforces:
+//!
+//! - only one of the references may operate on the inner `T` at a time,
+//!   whether reading or writing; other accesses will block until the current
+//!   one has released its lock.
+//!
+//!   ```
+//!   use dtolnay::reference_types::mutex::Mutex;
+//!
+//!   let mutex = Mutex::new(0);
+//!   let guard = mutex.lock();
+//!   assert!(mutex.try_lock().is_none()); // lock() would block forever
+//!   drop(guard);
+//!   assert!(mutex.try_lock().is_some());
+//!   ```
+//!
+//!   No reference into the contents can outlive the lock being released:
+//!
+//!   ```compile_fail,E0597
+//!   use dtolnay::reference_types::mutex::Mutex;
+//!
+//!   let mutex = Mutex::new(0);
+//!   let escaped: &mut i32 = {
+//!       let mut guard = mutex.lock();
+//!       &mut *guard
+//!   };
+//!   *escaped += 1;
+//!   ```
+//!
+//!   Because only one thread at a time has access, the contents only need to
+//!   be `Send`, not `Sync`, for the mutex to be shared between threads. A
+//!   `Mutex<Cell<T>>` is `Sync` but a `Mutex<Rc<T>>` is not, since an `Rc`
+//!   cannot be touched from any thread but the one that created it.
+//!
+//!   ```compile_fail,E0277
+//!   use dtolnay::reference_types::mutex::Mutex;
+//!   use std::rc::Rc;
+//!   use std::thread;
+//!
+//!   let mutex = Mutex::new(Rc::new(0));
+//!   thread::scope(|scope| {
+//!       scope.spawn(|| {
+//!           let _ = mutex.lock();
+//!       });
+//!   });
+//!   ```
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// A mutual exclusion lock, like [`std::sync::Mutex`] but without poisoning
+/// and spinning instead of parking while contended.
+pub struct Mutex<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the lock ensures that at most one thread at a time accesses the
+// contents, so moving T between threads is all that can happen.
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+/// Access to the contents of a locked `Mutex`, which is unlocked on drop.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    // Only Sync if T is Sync, since &MutexGuard hands out &T.
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<T> Mutex<T> {
+    pub const fn new(value: T) -> Self {
+        Mutex {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Blocks until the lock is acquired.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        Some(MutexGuard {
+            mutex: self,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: this guard holds the lock.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: this guard holds the lock.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.locked.store(false, Ordering::Release);
+    }
+}
@@ -0,0 +1,167 @@
+//! [`RefCell<T>`] &mdash; we can perform mutation even when other references
+//! to the same `RefCell<T>` may exist.
+//!
+//! ```
+//! use dtolnay::reference_types::refcell::RefCell;
+//!
+//! let cell = RefCell::new(Vec::new());
+//! let a = &cell;
+//! let b = &cell;
+//! a.borrow_mut().push(1);
+//! b.borrow_mut().push(2);
+//! assert_eq!(*cell.borrow(), [1, 2]);
+//! ```
+//!
+//! It's safe because the API enforces:
+//!
+//! - `RefCell<T>` is single threaded so it's impossible for multiple threads
+//!   to refer to the same one, similar to `Cell<T>`;
+//!
+//!   ```compile_fail,E0277
+//!   use dtolnay::reference_types::refcell::RefCell;
+//!   use std::thread;
+//!
+//!   let cell = RefCell::new(Vec::new());
+//!   thread::scope(|scope| {
+//!       scope.spawn(|| cell.borrow_mut().push(1));
+//!       scope.spawn(|| cell.borrow_mut().push(2));
+//!   });
+//!   ```
+//!
+//! - and within the one thread, dynamically checked borrow rules will detect
+//!   and prevent attempts to mutate while a reader is holding a reference
+//!   into the content of the `RefCell`.
+//!
+//!   ```should_panic
+//!   use dtolnay::reference_types::refcell::RefCell;
+//!
+//!   let cell = RefCell::new(vec![1, 2, 3]);
+//!   let first = &cell.borrow()[0];
+//!   cell.borrow_mut().clear(); // panics: already borrowed
+//!   println!("{}", first);
+//!   ```
+//!
+//!   Without panicking, the same check is available as `try_borrow_mut`.
+//!
+//!   ```
+//!   use dtolnay::reference_types::refcell::RefCell;
+//!
+//!   let cell = RefCell::new(vec![1, 2, 3]);
+//!   let reader = cell.borrow();
+//!   assert!(cell.try_borrow_mut().is_none());
+//!   drop(reader);
+//!   assert!(cell.try_borrow_mut().is_some());
+//!   ```
+
+use super::cell::Cell;
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+
+/// A mutable memory location with dynamically checked borrow rules, like
+/// [`std::cell::RefCell`].
+pub struct RefCell<T> {
+    // Number of live Ref, or -1 while a RefMut is live.
+    borrow: Cell<isize>,
+    value: UnsafeCell<T>,
+}
+
+// Not Sync because Cell and UnsafeCell are not Sync. Send whenever T is Send.
+
+const WRITING: isize = -1;
+
+/// Shared borrow of the contents of a `RefCell`.
+pub struct Ref<'a, T> {
+    cell: &'a RefCell<T>,
+}
+
+/// Exclusive borrow of the contents of a `RefCell`.
+pub struct RefMut<'a, T> {
+    cell: &'a RefCell<T>,
+}
+
+impl<T> RefCell<T> {
+    pub const fn new(value: T) -> Self {
+        RefCell {
+            borrow: Cell::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.try_borrow().expect("already mutably borrowed")
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.try_borrow_mut().expect("already borrowed")
+    }
+
+    pub fn try_borrow(&self) -> Option<Ref<'_, T>> {
+        let borrow = self.borrow.get();
+        if borrow == WRITING || borrow == isize::MAX {
+            return None;
+        }
+        self.borrow.set(borrow + 1);
+        Some(Ref { cell: self })
+    }
+
+    pub fn try_borrow_mut(&self) -> Option<RefMut<'_, T>> {
+        if self.borrow.get() != 0 {
+            return None;
+        }
+        self.borrow.set(WRITING);
+        Some(RefMut { cell: self })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the borrow count is positive for as long as this Ref is
+        // alive, which rules out any RefMut.
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for Ref<'_, T> {
+    fn drop(&mut self) {
+        self.cell.borrow.set(self.cell.borrow.get() - 1);
+    }
+}
+
+impl<T> Deref for RefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the borrow state is WRITING for as long as this RefMut is
+        // alive, which rules out any other Ref or RefMut.
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as above.
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for RefMut<'_, T> {
+    fn drop(&mut self) {
+        self.cell.borrow.set(0);
+    }
+}
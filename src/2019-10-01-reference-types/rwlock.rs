@@ -0,0 +1,194 @@
+//! [`RwLock<T>`] &mdash; we can perform mutation even when other references
+//! to the same `RwLock<T>` may exist.
+//!
+//! ```
+//! use dtolnay::reference_types::rwlock::RwLock;
+//! use std::thread;
+//!
+//! let lock = RwLock::new(0);
+//! thread::scope(|scope| {
+//!     scope.spawn(|| *lock.write() += 1);
+//!     scope.spawn(|| *lock.write() += 1);
+//!     scope.spawn(|| assert!(*lock.read() <= 2));
+//! });
+//! assert_eq!(*lock.read(), 2);
+//! ```
+//!
+//! It's safe because the API enforces:
+//!
+//! - only one of the references may be used to mutate the `T` at a time, and
+//!   only while no other references are being used for reading; accesses will
+//!   block to meet these requirements.
+//!
+//!   ```
+//!   use dtolnay::reference_types::rwlock::RwLock;
+//!
+//!   let lock = RwLock::new(0);
+//!   let reader1 = lock.read();
+//!   let reader2 = lock.read(); // any number of readers at once
+//!   assert!(lock.try_write().is_none()); // write() would block
+//!   drop((reader1, reader2));
+//!
+//!   let writer = lock.write();
+//!   assert!(lock.try_read().is_none()); // read() would block
+//!   drop(writer);
+//!   ```
+//!
+//!   Since readers on different threads share the contents at the same time,
+//!   the contents must be `Sync` as well as `Send` for the lock to be shared
+//!   between threads. An `RwLock<Cell<T>>` would let two readers mutate the
+//!   same `Cell` concurrently, so it is not `Sync`.
+//!
+//!   ```compile_fail,E0277
+//!   use dtolnay::reference_types::rwlock::RwLock;
+//!   use std::cell::Cell;
+//!   use std::thread;
+//!
+//!   let lock = RwLock::new(Cell::new(0));
+//!   thread::scope(|scope| {
+//!       scope.spawn(|| lock.read().set(1));
+//!       scope.spawn(|| lock.read().set(2));
+//!   });
+//!   ```
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// A reader-writer lock, like [`std::sync::RwLock`] but without poisoning
+/// and spinning instead of parking while contended.
+pub struct RwLock<T> {
+    // Number of readers, or WRITING while locked for writing.
+    state: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+const WRITING: usize = usize::MAX;
+
+// SAFETY: writers get exclusive access, which requires T: Send, and readers on
+// different threads get shared access at the same time, which requires
+// T: Sync.
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+/// Shared access to the contents of an `RwLock` locked for reading.
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    marker: PhantomData<&'a T>,
+}
+
+/// Exclusive access to the contents of an `RwLock` locked for writing.
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<T> RwLock<T> {
+    pub const fn new(value: T) -> Self {
+        RwLock {
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Blocks until there is no writer.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Blocks until there are no readers or writer.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            // One short of WRITING is the most readers representable.
+            if state >= WRITING - 1 {
+                return None;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Some(RwLockReadGuard {
+                        lock: self,
+                        marker: PhantomData,
+                    });
+                }
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.state
+            .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        Some(RwLockWriteGuard {
+            lock: self,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: this guard is counted among the readers, so there is no
+        // writer.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: this guard holds the lock for writing.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: this guard holds the lock for writing.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.store(0, Ordering::Release);
+    }
+}
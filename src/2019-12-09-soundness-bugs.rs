@@ -2,6 +2,17 @@
 
 <sup>*by [David Tolnay]&#8202;,&ensp;2019.12.09*</sup>
 
+<nav class="contents">
+
+- [An appropriate mindset for this discussion](#an-appropriate-mindset-for-this-discussion)
+- [Soundness](#soundness)
+- [Simple case study](#simple-case-study)
+- [Unsoundness as a reflection of priorities](#unsoundness-as-a-reflection-of-priorities)
+- [Where things stand](#where-things-stand)
+- [Findings](#findings)
+
+</nav>
+
 [David Tolnay]: https://github.com/dtolnay
 
 <br>
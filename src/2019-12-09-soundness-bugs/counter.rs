@@ -0,0 +1,171 @@
+//! A per-thread counter, the other kind of library in which the essay found a
+//! soundness bug.
+//!
+//! Each thread that adds to a [`Counter`] gets its own shard, so that hot
+//! increments from many threads do not contend on one cache line. Reading the
+//! counter sums the shards. When a thread exits, its shards are folded into
+//! their counters so that nothing it counted is lost.
+//!
+//! This implementation contains no unsafe code. The compiler rather than an
+//! audit is what guarantees that no sequence of calls through the safe API,
+//! including calls made from inside of thread-local destructors while a thread
+//! is exiting, can produce a data race.
+//!
+//! ```
+//! use dtolnay::soundness_bugs::counter::Counter;
+//! use std::thread;
+//!
+//! let counter = Counter::new();
+//! thread::scope(|scope| {
+//!     for _ in 0..4 {
+//!         scope.spawn(|| {
+//!             for _ in 0..10 {
+//!                 counter.increment();
+//!             }
+//!         });
+//!     }
+//! });
+//! assert_eq!(counter.get(), 40);
+//! ```
+
+#![forbid(unsafe_code)]
+
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+/// Counter whose increments are sharded by thread.
+pub struct Counter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    // Everything counted by threads that have since exited.
+    retired: AtomicU64,
+    // Shards of live threads that have added to this counter.
+    shards: Mutex<Vec<Arc<AtomicU64>>>,
+}
+
+// This thread's shard of every counter it has added to.
+struct LocalShards {
+    shards: Vec<(Weak<Inner>, Arc<AtomicU64>)>,
+    // Index of the shard used most recently, so that repeated adds to the same
+    // counter do not scan the others.
+    last: usize,
+}
+
+thread_local! {
+    static LOCAL: RefCell<LocalShards> = const {
+        RefCell::new(LocalShards {
+            shards: Vec::new(),
+            last: 0,
+        })
+    };
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Counter {
+            inner: Arc::new(Inner {
+                retired: AtomicU64::new(0),
+                shards: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        let added = LOCAL.try_with(|local| {
+            // Not borrowed already because nothing below calls out to user
+            // code while holding the borrow.
+            let mut local = local.borrow_mut();
+            local.shard(&self.inner).fetch_add(n, Ordering::Relaxed);
+        });
+        if added.is_err() {
+            // This thread's shards are already being torn down, as happens
+            // when adding from another thread-local's destructor.
+            self.inner.retired.fetch_add(n, Ordering::Relaxed);
+        }
+    }
+
+    /// Sum of every thread's shard.
+    ///
+    /// Increments that happen concurrently with the call may or may not be
+    /// included. Everything added by threads that have been joined is.
+    pub fn get(&self) -> u64 {
+        // Wrapping, like the shards' own fetch_add.
+        let shards = self.inner.lock();
+        shards
+            .iter()
+            .map(|shard| shard.load(Ordering::Relaxed))
+            .fold(
+                self.inner.retired.load(Ordering::Relaxed),
+                u64::wrapping_add,
+            )
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, Vec<Arc<AtomicU64>>> {
+        // Nothing panics while holding the lock, but even if it did the list
+        // of shards would be left consistent.
+        self.shards.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl LocalShards {
+    fn shard(&mut self, inner: &Arc<Inner>) -> &AtomicU64 {
+        // Counters that have been dropped still hold their allocation until
+        // their Weak is removed from here, so a new counter can never be
+        // mistaken for a dropped one at the same address.
+        let is_inner = |(counter, _): &(Weak<Inner>, _)| counter.as_ptr() == Arc::as_ptr(inner);
+        if !self.shards.get(self.last).is_some_and(is_inner) {
+            self.last = if let Some(index) = self.shards.iter().position(is_inner) {
+                index
+            } else {
+                // Registering is the only time the list grows, so it is also
+                // where the shards of dropped counters are pruned.
+                self.shards
+                    .retain(|(counter, _)| counter.strong_count() > 0);
+                let shard = Arc::new(AtomicU64::new(0));
+                inner.lock().push(Arc::clone(&shard));
+                self.shards.push((Arc::downgrade(inner), shard));
+                self.shards.len() - 1
+            };
+        }
+        &self.shards[self.last].1
+    }
+}
+
+impl Drop for LocalShards {
+    fn drop(&mut self) {
+        for (counter, shard) in self.shards.drain(..) {
+            let Some(inner) = counter.upgrade() else {
+                continue;
+            };
+            // Under the lock so that a concurrent `get` sees this thread's
+            // count either in its shard or in retired, never both or neither.
+            let mut shards = inner.lock();
+            inner
+                .retired
+                .fetch_add(shard.load(Ordering::Relaxed), Ordering::Relaxed);
+            shards.retain(|other| !Arc::ptr_eq(other, &shard));
+        }
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl Debug for Counter {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_tuple("Counter").field(&self.get()).finish()
+    }
+}
@@ -0,0 +1,110 @@
+//! The `frob` bindings from the essay, wrapped around a Rust stand-in for the C
+//! function so that the difference between them can be observed under Miri.
+//!
+//! ```c
+//! // Requires arg != 10.
+//! // If arg is 10, we stomp on yer memery.
+//! void frob(int32_t arg);
+//! ```
+//!
+//! Every binding documented here is sound: no safe caller can make
+//! [`ffi::frob`] write out of bounds. The essay's unsound binding exists too,
+//! as the hidden `frob_unsound`, only so that `tests/test_frob.rs` can show
+//! what goes wrong with it. The test that calls `frob_unsound(10)` from
+//! entirely safe code is ignored by default because it is undefined behavior;
+//! run it under Miri to watch it get caught.
+//!
+//! ```console
+//! $ MIRIFLAGS=-Zmiri-strict-provenance cargo miri test --test test_frob -- --ignored
+//! error: Undefined Behavior: memory access failed: ... out-of-bounds
+//! ```
+
+pub mod ffi {
+    //! Stand-in for the C library.
+
+    use std::hint;
+
+    /// Behaves correctly for every argument except 10, for which it writes one
+    /// byte past the end of its buffer.
+    ///
+    /// # Safety
+    ///
+    /// `arg` must not be 10.
+    pub unsafe fn frob(arg: i32) {
+        let mut memery = [0u8; 10];
+        let len = if arg == 10 { 11 } else { memery.len() };
+        let ptr = memery.as_mut_ptr();
+        for i in 0..len {
+            // SAFETY: in bounds as long as the caller upheld arg != 10.
+            unsafe { ptr.add(i).write(0xAA) }
+        }
+        hint::black_box(&mut memery);
+    }
+}
+
+/// Sound by runtime validation of the argument.
+///
+/// # Panics
+///
+/// Panics if `arg` is 10.
+///
+/// ```
+/// dtolnay::soundness_bugs::frob::frob(9);
+/// ```
+///
+/// ```should_panic
+/// dtolnay::soundness_bugs::frob::frob(10);
+/// ```
+pub fn frob(arg: i32) {
+    assert!(arg != 10);
+    // SAFETY: checked just above.
+    unsafe { ffi::frob(arg) }
+}
+
+/// The values of `arg` that are expected by `frob`, none of which is 10.
+#[derive(Copy, Clone, Debug)]
+pub enum FrobLevel {
+    Low = 0,
+    Medium = 1,
+    High = 2,
+    Critical = 3,
+}
+
+/// Sound at zero runtime cost because `FrobLevel` has no variant equal to 10.
+///
+/// ```
+/// use dtolnay::soundness_bugs::frob::{frob_level, FrobLevel};
+///
+/// frob_level(FrobLevel::Critical);
+/// ```
+pub fn frob_level(level: FrobLevel) {
+    let arg = level as i32;
+    // SAFETY: every FrobLevel discriminant is in 0..=3.
+    unsafe { ffi::frob(arg) }
+}
+
+/// Passes responsibility for the invariant on to the caller.
+///
+/// # Safety
+///
+/// Caller must ensure `arg != 10`.
+pub unsafe fn frob_unchecked(arg: i32) {
+    // SAFETY: forwarded to the caller.
+    unsafe { ffi::frob(arg) }
+}
+
+/// Deliberately unsound: safe to call according to its signature, yet
+/// `frob_unsound(10)` is undefined behavior. Never call this outside of tests.
+///
+/// Note that nothing goes wrong as long as no caller ever passes 10, which is
+/// the essay's point about unsoundness being distinct from the presence of a
+/// bug.
+///
+/// ```
+/// dtolnay::soundness_bugs::frob::frob_unsound(9);
+/// ```
+#[doc(hidden)]
+pub fn frob_unsound(arg: i32) {
+    // UNSOUND
+    unsafe { ffi::frob(arg) }
+}
@@ -0,0 +1,6 @@
+//! Companion code for *Soundness bugs in Rust libraries: can't live with 'em,
+//! can't live without 'em*.
+
+pub mod counter;
+pub mod frob;
+pub mod string_view;
@@ -0,0 +1,230 @@
+//! A sound binding for C++ `std::string_view`, the kind of interop library in
+//! which the essay's first two soundness bugs were found.
+//!
+//! A `string_view` is a pointer and a length borrowed from memory owned by
+//! someone else, usually a C++ `std::string`. Nothing in C++ stops the view
+//! from outliving that string or from being read while the string is being
+//! modified. On the Rust side both of those are ruled out by tying the view to
+//! a lifetime `'a` for which the underlying bytes are borrowed immutably.
+//!
+//! The view outliving its string does not compile:
+//!
+//! ```compile_fail,E0597
+//! use dtolnay::soundness_bugs::string_view::CxxString;
+//!
+//! let view;
+//! {
+//!     let string = CxxString::from("dangling");
+//!     view = string.as_view();
+//! }
+//! println!("{:?}", view);
+//! ```
+//!
+//! Nor does modifying the string while a view of it is alive:
+//!
+//! ```compile_fail,E0502
+//! use dtolnay::soundness_bugs::string_view::CxxString;
+//!
+//! let mut string = CxxString::from("before");
+//! let view = string.as_view();
+//! string.push_bytes(b" and after");
+//! println!("{:?}", view);
+//! ```
+//!
+//! Once the view is no longer used, the string is free to change again:
+//!
+//! ```
+//! use dtolnay::soundness_bugs::string_view::CxxString;
+//!
+//! let mut string = CxxString::from("before");
+//! let view = string.as_view();
+//! assert_eq!(view.to_str(), Ok("before"));
+//! string.push_bytes(b" and after");
+//! assert_eq!(string.as_view().to_str(), Ok("before and after"));
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::ptr;
+use std::slice;
+use std::str::{self, Utf8Error};
+
+/// Non-owning view of a contiguous run of bytes, like C++ `std::string_view`.
+///
+/// The bytes need not be UTF-8, since nothing in C++ guarantees that they
+/// are.
+#[derive(Copy, Clone)]
+pub struct StringView<'a> {
+    ptr: *const u8,
+    len: usize,
+    marker: PhantomData<&'a [u8]>,
+}
+
+// SAFETY: a StringView is equivalent to &'a [u8], which is Send and Sync.
+unsafe impl Send for StringView<'_> {}
+
+// SAFETY: as above.
+unsafe impl Sync for StringView<'_> {}
+
+impl<'a> StringView<'a> {
+    /// The empty view. Like a default constructed `std::string_view`, its
+    /// pointer is null.
+    pub const fn empty() -> Self {
+        StringView {
+            ptr: ptr::null(),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// View of the UTF-8 bytes of a Rust string.
+    pub const fn new(string: &'a str) -> Self {
+        Self::from_bytes(string.as_bytes())
+    }
+
+    /// View of arbitrary bytes.
+    pub const fn from_bytes(bytes: &'a [u8]) -> Self {
+        StringView {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+            marker: PhantomData,
+        }
+    }
+
+    /// View of the `data()` and `size()` of a `string_view` received from C++.
+    ///
+    /// # Safety
+    ///
+    /// Unless `len` is 0, `ptr` must point to `len` initialized bytes which
+    /// remain valid and unmodified for the whole of `'a`. If `len` is 0, `ptr`
+    /// may be anything including null.
+    pub const unsafe fn from_raw_parts(ptr: *const u8, len: usize) -> Self {
+        StringView {
+            ptr,
+            len,
+            marker: PhantomData,
+        }
+    }
+
+    /// The underlying bytes, borrowed for the full lifetime of the view rather
+    /// than the lifetime of `&self`.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        if self.len == 0 {
+            // C++ allows a null data() for an empty view, which
+            // slice::from_raw_parts does not.
+            return &[];
+        }
+        // SAFETY: guaranteed by the constructors.
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// The underlying bytes as a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not valid UTF-8.
+    pub fn to_str(&self) -> Result<&'a str, Utf8Error> {
+        str::from_utf8(self.as_bytes())
+    }
+
+    /// The underlying bytes as a string, with invalid UTF-8 replaced by
+    /// U+FFFD.
+    pub fn to_string_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
+
+    /// Pointer to pass back to C++ as the view's `data()`.
+    pub const fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for StringView<'_> {
+    fn default() -> Self {
+        StringView::empty()
+    }
+}
+
+impl<'a> From<&'a str> for StringView<'a> {
+    fn from(string: &'a str) -> Self {
+        StringView::new(string)
+    }
+}
+
+impl<'a> From<&'a [u8]> for StringView<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        StringView::from_bytes(bytes)
+    }
+}
+
+impl PartialEq for StringView<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for StringView<'_> {}
+
+impl Debug for StringView<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&self.to_string_lossy(), formatter)
+    }
+}
+
+/// Stand-in for a C++ `std::string` whose buffer lives on the C++ side.
+///
+/// ```
+/// use dtolnay::soundness_bugs::string_view::CxxString;
+///
+/// let mut string = CxxString::from("caf");
+/// string.push_bytes(b"\xE9");
+/// assert!(string.as_view().to_str().is_err());
+/// assert_eq!(string.as_view().to_string_lossy(), "caf\u{FFFD}");
+/// ```
+#[derive(Default)]
+pub struct CxxString {
+    buf: Vec<u8>,
+}
+
+impl CxxString {
+    pub fn new() -> Self {
+        CxxString::default()
+    }
+
+    /// Appends to the string, possibly reallocating its buffer. This is what
+    /// would leave a C++ `string_view` of the same string dangling.
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// View of the string's current contents, which keeps the string borrowed
+    /// for as long as the view is in use.
+    pub fn as_view(&self) -> StringView<'_> {
+        // SAFETY: the buffer is neither modified nor freed for the lifetime
+        // of the shared borrow of self.
+        unsafe { StringView::from_raw_parts(self.buf.as_ptr(), self.buf.len()) }
+    }
+}
+
+impl From<&str> for CxxString {
+    fn from(string: &str) -> Self {
+        CxxString {
+            buf: string.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl Debug for CxxString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&self.as_view(), formatter)
+    }
+}
@@ -2,6 +2,16 @@
 
 <sup>*by [David Tolnay]&#8202;,&ensp;2020.02.20*</sup>
 
+<nav class="contents">
+
+- [Design summary](#design-summary)
+- [Target audience 1: the Rust community](#target-audience-1-the-rust-community)
+- [Target audience 2: triage working group](#target-audience-2-triage-working-group)
+- [Target audience 3: contributors](#target-audience-3-contributors)
+- [Target audience 4: Rust team members](#target-audience-4-rust-team-members)
+
+</nav>
+
 [David Tolnay]: https://github.com/dtolnay
 
 <br>
@@ -0,0 +1,234 @@
+//! Load-balanced reviewer assignment.
+//!
+//! Previously, if a PR sat for a while without review, the state of the art
+//! was to reassign it to a random other member of the reviewing team. With
+//! every member's todo list public, the choice can instead take into account
+//! who is familiar with the code being changed and who has the least already
+//! queued.
+//!
+//! Each candidate's score is the sum of two parts, both shown in the
+//! explanation so that anyone can check why a reviewer was picked:
+//!
+//! - **expertise**: [`Weights::expertise`] scaled by the fraction of the PR's
+//!   files that fall within one of the member's areas, rounded down;
+//! - **load**: minus [`Weights::per_item`] for every item on the member's
+//!   list.
+//!
+//! Ties go to the shorter queue, then to the name that sorts first.
+//!
+//! ```
+//! use dtolnay::triage_scale::assign::{self, Member, PullRequest, Team, Weights};
+//! use dtolnay::triage_scale::notifications::{Change, Edit, Notifications};
+//!
+//! let team = Team {
+//!     name: "libs".to_owned(),
+//!     members: vec![
+//!         Member::new("alex", ["library/std/src/sync"]),
+//!         Member::new("dtolnay", ["library/proc_macro", "library/core/src/fmt"]),
+//!     ],
+//! };
+//!
+//! let mut notifications = Notifications::new();
+//! for i in 0..3 {
+//!     let url = format!("https://github.com/rust-lang/rust/pull/{}", 69000 + i);
+//!     notifications.apply(Edit {
+//!         at: 1_582_156_800,
+//!         editor: "triage".to_owned(),
+//!         user: "dtolnay".to_owned(),
+//!         change: Change::Add { index: None, url, description: String::new() },
+//!     })?;
+//! }
+//!
+//! let pr = PullRequest {
+//!     author: "jane".to_owned(),
+//!     files: vec![
+//!         "library/proc_macro/src/lib.rs".to_owned(),
+//!         "library/proc_macro/src/bridge/mod.rs".to_owned(),
+//!         "tests/ui/proc-macro/span-api.rs".to_owned(),
+//!     ],
+//!     exclude: Vec::new(),
+//! };
+//!
+//! let recommendation = assign::recommend(&team, &notifications, &pr, &Weights::default()).unwrap();
+//! assert_eq!(recommendation.reviewer, "dtolnay");
+//! assert_eq!(
+//!     recommendation.to_string(),
+//!     "\
+//! libs: recommending dtolnay
+//! - dtolnay: +66 expertise (2 of 3 files), -30 load (3 queued) = +36
+//! - alex: +0 expertise (0 of 3 files), +0 load (0 queued) = +0
+//! ",
+//! );
+//! # Ok::<(), dtolnay::triage_scale::notifications::Error>(())
+//! ```
+
+use super::notifications::Notifications;
+use std::cmp::Reverse;
+use std::fmt::{self, Display};
+
+/// A reviewing team.
+#[derive(Clone, Debug)]
+pub struct Team {
+    pub name: String,
+    pub members: Vec<Member>,
+}
+
+/// One member of a team and the parts of the repository they know well.
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    /// Path prefixes such as `library/core/src/fmt`. A prefix matches whole
+    /// path components only.
+    pub areas: Vec<String>,
+}
+
+/// The PR in need of a reviewer.
+#[derive(Clone, Debug)]
+pub struct PullRequest {
+    /// Never recommended to review their own PR.
+    pub author: String,
+    /// Paths of the files the PR touches.
+    pub files: Vec<String>,
+    /// Members not to recommend, such as the reviewer who let it go stale.
+    pub exclude: Vec<String>,
+}
+
+/// How much each part of the score counts.
+#[derive(Copy, Clone, Debug)]
+pub struct Weights {
+    /// Score for owning every file the PR touches.
+    pub expertise: i64,
+    /// Penalty for each item already on the member's list.
+    pub per_item: i64,
+}
+
+/// One member's score and how it was arrived at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub name: String,
+    /// Number of the PR's files within one of the member's areas.
+    pub matched: usize,
+    /// Number of files the PR touches.
+    pub touched: usize,
+    /// Length of the member's list.
+    pub queued: usize,
+    pub expertise: i64,
+    pub load: i64,
+    pub total: i64,
+}
+
+/// The chosen reviewer, followed by every candidate from best to worst.
+#[derive(Clone, Debug)]
+pub struct Recommendation {
+    pub team: String,
+    pub reviewer: String,
+    pub ranking: Vec<Candidate>,
+}
+
+impl Member {
+    pub fn new<'a>(name: &str, areas: impl IntoIterator<Item = &'a str>) -> Self {
+        Member {
+            name: name.to_owned(),
+            areas: areas.into_iter().map(str::to_owned).collect(),
+        }
+    }
+
+    fn owns(&self, path: &str) -> bool {
+        self.areas.iter().any(|area| {
+            let area = area.trim_end_matches('/');
+            path.strip_prefix(area)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            expertise: 100,
+            per_item: 10,
+        }
+    }
+}
+
+/// Ranks every eligible member of the team, or returns None if nobody is
+/// eligible.
+pub fn recommend(
+    team: &Team,
+    notifications: &Notifications,
+    pr: &PullRequest,
+    weights: &Weights,
+) -> Option<Recommendation> {
+    let touched = pr.files.len();
+    let mut ranking: Vec<Candidate> = team
+        .members
+        .iter()
+        .filter(|member| member.name != pr.author && !pr.exclude.contains(&member.name))
+        .map(|member| {
+            let matched = pr.files.iter().filter(|path| member.owns(path)).count();
+            let queued = notifications.list(&member.name).len();
+            let expertise = if touched == 0 {
+                0
+            } else {
+                // Exact in i128, and no larger in magnitude than the weight
+                // because matched <= touched.
+                let scaled = i128::from(weights.expertise) * i128::from(to_i64(matched))
+                    / i128::from(to_i64(touched));
+                i64::try_from(scaled).unwrap_or(weights.expertise)
+            };
+            let load = weights
+                .per_item
+                .saturating_mul(to_i64(queued))
+                .saturating_neg();
+            Candidate {
+                name: member.name.clone(),
+                matched,
+                touched,
+                queued,
+                expertise,
+                load,
+                total: expertise.saturating_add(load),
+            }
+        })
+        .collect();
+    ranking.sort_by(|a, b| {
+        let key = |candidate: &Candidate| (Reverse(candidate.total), candidate.queued);
+        key(a).cmp(&key(b)).then_with(|| a.name.cmp(&b.name))
+    });
+    Some(Recommendation {
+        team: team.name.clone(),
+        reviewer: ranking.first()?.name.clone(),
+        ranking,
+    })
+}
+
+fn to_i64(n: usize) -> i64 {
+    i64::try_from(n).unwrap_or(i64::MAX)
+}
+
+impl Display for Candidate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}: {:+} expertise ({} of {} {}), {:+} load ({} queued) = {:+}",
+            self.name,
+            self.expertise,
+            self.matched,
+            self.touched,
+            if self.touched == 1 { "file" } else { "files" },
+            self.load,
+            self.queued,
+            self.total,
+        )
+    }
+}
+
+impl Display for Recommendation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(formatter, "{}: recommending {}", self.team, self.reviewer)?;
+        for candidate in &self.ranking {
+            writeln!(formatter, "- {candidate}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+//! Chat commands for editing anyone's list.
+//!
+//! Every command begins with the user whose list to edit. Positions are
+//! 1-based, as shown to people.
+//!
+//! ```text
+//! @dtolnay add https://github.com/dtolnay/syn/issues/700 Question about attributes
+//! @dtolnay add 1 https://github.com/rust-lang/rust/pull/69000 Review span API
+//! @dtolnay move 3 1
+//! @dtolnay remove 2
+//! @dtolnay tag 1 beta-nominated
+//! @dtolnay untag 1 beta-nominated
+//! ```
+//!
+//! `add` with no position appends to the end of the list. The description is
+//! everything after the URL.
+//!
+//! ```
+//! use dtolnay::triage_scale::command::Command;
+//! use dtolnay::triage_scale::notifications::Notifications;
+//!
+//! let mut notifications = Notifications::new();
+//! for input in [
+//!     "@dtolnay add https://github.com/dtolnay/syn/issues/700 Question about attributes",
+//!     "@dtolnay add 1 https://github.com/rust-lang/rust/pull/69000 Review span API",
+//!     "@dtolnay tag 2 question",
+//! ] {
+//!     let command: Command = input.parse()?;
+//!     notifications.apply(command.into_edit("jane", 1_582_156_800))?;
+//! }
+//!
+//! let list = notifications.list("dtolnay");
+//! assert_eq!(list[0].description, "Review span API");
+//! assert_eq!(list[1].tags, ["question"]);
+//!
+//! let err = "@dtolnay move 3".parse::<Command>().unwrap_err();
+//! assert_eq!(err.to_string(), "`move` needs a position to move to");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::notifications::{Change, Edit};
+use std::error;
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// A parsed command: whose list to edit, and how.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub user: String,
+    /// The requested change. Indices are 0-based.
+    pub change: Change,
+}
+
+/// Malformed command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// Byte range of the input that the message is about.
+    pub span: Range<usize>,
+    msg: String,
+}
+
+struct Words<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+struct Word<'a> {
+    text: &'a str,
+    span: Range<usize>,
+}
+
+impl Command {
+    /// The edit made by running this command as `editor` at the given time.
+    pub fn into_edit(self, editor: &str, at: u64) -> Edit {
+        Edit {
+            at,
+            editor: editor.to_owned(),
+            user: self.user,
+            change: self.change,
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Error> {
+        let mut words = Words { input, offset: 0 };
+        let user = parse_user(&mut words)?;
+        let Some(verb) = words.next() else {
+            return Err(Error::new(
+                words.offset..input.len(),
+                format!("expected a command after `@{user}`"),
+            ));
+        };
+        let change = parse_change(&verb, &mut words)?;
+        if let Some(extra) = words.next() {
+            return Err(Error::new(
+                extra.span.start..input.trim_end().len(),
+                format!("unexpected `{}` after `{}` command", extra.text, verb.text),
+            ));
+        }
+        Ok(Command { user, change })
+    }
+}
+
+fn parse_user(words: &mut Words) -> Result<String, Error> {
+    let Some(user) = words.next() else {
+        return Err(Error::new(0..words.input.len(), "empty command"));
+    };
+    let Some(name) = user.text.strip_prefix('@') else {
+        return Err(Error::new(
+            user.span,
+            format!("expected `@user` whose list to edit, found `{}`", user.text),
+        ));
+    };
+    let valid = |ch: char| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_';
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(Error::new(
+            user.span,
+            format!("invalid user name `{}`", user.text),
+        ));
+    }
+    Ok(name.to_owned())
+}
+
+fn parse_change(verb: &Word, words: &mut Words) -> Result<Change, Error> {
+    match verb.text {
+        "add" => {
+            let first = words.expect("`add` needs a URL")?;
+            let (index, url) = if first.text.starts_with(|ch: char| ch.is_ascii_digit()) {
+                let index = parse_position(&first)?;
+                (
+                    Some(index),
+                    words.expect("`add` needs a URL after the position")?,
+                )
+            } else {
+                (None, first)
+            };
+            if !url.text.starts_with("https://") && !url.text.starts_with("http://") {
+                return Err(Error::new(
+                    url.span,
+                    format!(
+                        "expected a URL starting with https:// or http://, found `{}`",
+                        url.text
+                    ),
+                ));
+            }
+            Ok(Change::Add {
+                index,
+                url: url.text.to_owned(),
+                description: words.rest().to_owned(),
+            })
+        }
+        "move" => {
+            let from = parse_position(&words.expect("`move` needs a position to move from")?)?;
+            let to = parse_position(&words.expect("`move` needs a position to move to")?)?;
+            Ok(Change::Move { from, to })
+        }
+        "remove" => {
+            let index = parse_position(&words.expect("`remove` needs a position")?)?;
+            Ok(Change::Remove { index })
+        }
+        "tag" | "untag" => {
+            let msg = format!("`{}` needs a position", verb.text);
+            let index = parse_position(&words.expect(&msg)?)?;
+            let tag = parse_tag(&words.expect("expected a tag after the position")?)?;
+            Ok(if verb.text == "tag" {
+                Change::Tag { index, tag }
+            } else {
+                Change::Untag { index, tag }
+            })
+        }
+        _ => Err(Error::new(
+            verb.span.clone(),
+            format!(
+                "unknown command `{}`, expected add, move, remove, tag or untag",
+                verb.text,
+            ),
+        )),
+    }
+}
+
+fn parse_tag(word: &Word) -> Result<String, Error> {
+    let valid = |ch: char| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-';
+    if word.text.chars().all(valid) {
+        Ok(word.text.to_owned())
+    } else {
+        Err(Error::new(
+            word.span.clone(),
+            format!(
+                "invalid tag `{}`, tags are lowercase letters, digits and dashes",
+                word.text,
+            ),
+        ))
+    }
+}
+
+// A 1-based position, converted to a 0-based index.
+fn parse_position(word: &Word) -> Result<usize, Error> {
+    match word.text.parse::<usize>() {
+        Ok(0) => Err(Error::new(word.span.clone(), "positions start at 1")),
+        Ok(position) => Ok(position - 1),
+        Err(_) => Err(Error::new(
+            word.span.clone(),
+            format!("expected a position, found `{}`", word.text),
+        )),
+    }
+}
+
+impl<'a> Words<'a> {
+    fn next(&mut self) -> Option<Word<'a>> {
+        let rest = &self.input[self.offset..];
+        let start = self.offset + (rest.len() - rest.trim_start().len());
+        let rest = &self.input[start..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.offset = start + len;
+        Some(Word {
+            text: &rest[..len],
+            span: start..start + len,
+        })
+    }
+
+    fn expect(&mut self, msg: &str) -> Result<Word<'a>, Error> {
+        let end = self.input.len();
+        self.next().ok_or_else(|| Error::new(end..end, msg))
+    }
+
+    fn rest(&mut self) -> &'a str {
+        let rest = self.input[self.offset..].trim();
+        self.offset = self.input.len();
+        rest
+    }
+}
+
+impl Error {
+    fn new(span: Range<usize>, msg: impl Into<String>) -> Self {
+        Error {
+            span,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.msg)
+    }
+}
+
+impl error::Error for Error {}
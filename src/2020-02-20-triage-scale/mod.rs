@@ -0,0 +1,15 @@
+//! Companion code for *Triage at scale for the Rust team*.
+//!
+//! A model of Triagebot's notifications: a publicly readable, publicly
+//! editable, roughly ordered todo list for every team member. The lists and
+//! every edit made to them are in [`notifications`], and [`store`] persists
+//! them to a local file. Edits are written as chat commands, parsed by
+//! [`command`]. With everyone's list visible, [`assign`] can pick a reviewer
+//! for a PR by how much is already on their plate, and [`render`] produces
+//! the page a PR author can be linked to while they wait.
+
+pub mod assign;
+pub mod command;
+pub mod notifications;
+pub mod render;
+pub mod store;
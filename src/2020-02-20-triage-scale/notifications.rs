@@ -0,0 +1,226 @@
+//! Every team member's todo list, and the log of who edited which list.
+//!
+//! Anyone may edit anyone's list. Rather than restricting who can make an
+//! edit, every edit is recorded along with who made it, so that the owner of
+//! a list can always see how it came to be in its current order.
+//!
+//! ```
+//! use dtolnay::triage_scale::notifications::{Change, Edit, Notifications};
+//!
+//! let mut notifications = Notifications::new();
+//! notifications.apply(Edit {
+//!     at: 1582156800,
+//!     editor: "Mark-Simulacrum".to_owned(),
+//!     user: "dtolnay".to_owned(),
+//!     change: Change::Add {
+//!         index: None,
+//!         url: "https://github.com/rust-lang/rust/pull/69000".to_owned(),
+//!         description: "Review proc_macro span API".to_owned(),
+//!     },
+//! })?;
+//! notifications.apply(Edit {
+//!     at: 1582160400,
+//!     editor: "jane".to_owned(),
+//!     user: "dtolnay".to_owned(),
+//!     change: Change::Add {
+//!         index: Some(0),
+//!         url: "https://github.com/dtolnay/syn/issues/700".to_owned(),
+//!         description: "Question about parsing attributes".to_owned(),
+//!     },
+//! })?;
+//!
+//! let list = notifications.list("dtolnay");
+//! assert_eq!(list[0].added_by, "jane");
+//! assert_eq!(list[1].added_by, "Mark-Simulacrum");
+//! assert_eq!(notifications.log().len(), 2);
+//! # Ok::<(), dtolnay::triage_scale::notifications::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One entry in a team member's list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub url: String,
+    pub description: String,
+    pub added_by: String,
+    /// Seconds since the Unix epoch.
+    pub added_at: u64,
+    /// Free-form labels such as `beta-nominated`, in the order added.
+    pub tags: Vec<String>,
+}
+
+/// One modification to one list. Indices are 0-based positions in the list
+/// as it is at the time the edit is applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// Inserts a new item at `index`, or at the end of the list if `None`.
+    Add {
+        index: Option<usize>,
+        url: String,
+        description: String,
+    },
+    /// Moves the item at `from` so that it ends up at position `to`.
+    Move {
+        from: usize,
+        to: usize,
+    },
+    Remove {
+        index: usize,
+    },
+    /// Adds a tag to the item at `index` unless it already has it.
+    Tag {
+        index: usize,
+        tag: String,
+    },
+    /// Removes a tag from the item at `index` if it has it.
+    Untag {
+        index: usize,
+        tag: String,
+    },
+}
+
+/// A change together with who made it, to whose list, and when.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+    pub editor: String,
+    pub user: String,
+    pub change: Change,
+}
+
+/// Every team member's list and the log of edits that produced them.
+#[derive(Clone, Debug, Default)]
+pub struct Notifications {
+    lists: BTreeMap<String, Vec<Item>>,
+    log: Vec<Edit>,
+}
+
+/// An edit that does not apply to the list as it currently stands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub user: String,
+    pub index: usize,
+    pub len: usize,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Notifications::default()
+    }
+
+    /// The user's list in order, which is empty for anyone nobody has ever
+    /// added anything for.
+    pub fn list(&self, user: &str) -> &[Item] {
+        self.lists.get(user).map_or(&[], Vec::as_slice)
+    }
+
+    /// Everyone whose list has ever been edited, in alphabetical order.
+    pub fn users(&self) -> impl Iterator<Item = &str> {
+        self.lists.keys().map(String::as_str)
+    }
+
+    /// Every edit ever applied, oldest first.
+    pub fn log(&self) -> &[Edit] {
+        &self.log
+    }
+
+    /// Applies an edit and appends it to the log, or leaves everything
+    /// unchanged if the edit refers to a position the list does not have.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an index is past the end of the list.
+    pub fn apply(&mut self, edit: Edit) -> Result<(), Error> {
+        let len = self.list(&edit.user).len();
+        let out_of_range = match edit.change {
+            Change::Add { index, .. } => index.filter(|&index| index > len),
+            Change::Move { from, to } => [from, to].into_iter().find(|&index| index >= len),
+            Change::Remove { index } | Change::Tag { index, .. } | Change::Untag { index, .. } => {
+                Some(index).filter(|&index| index >= len)
+            }
+        };
+        if let Some(index) = out_of_range {
+            return Err(Error {
+                user: edit.user,
+                index,
+                len,
+            });
+        }
+
+        let list = self.lists.entry(edit.user.clone()).or_default();
+        match &edit.change {
+            Change::Add {
+                index,
+                url,
+                description,
+            } => {
+                let index = index.unwrap_or(list.len());
+                let item = Item {
+                    url: url.clone(),
+                    description: description.clone(),
+                    added_by: edit.editor.clone(),
+                    added_at: edit.at,
+                    tags: Vec::new(),
+                };
+                list.insert(index, item);
+            }
+            Change::Move { from, to } => {
+                let item = list.remove(*from);
+                list.insert(*to, item);
+            }
+            Change::Remove { index } => {
+                list.remove(*index);
+            }
+            Change::Tag { index, tag } => {
+                let tags = &mut list[*index].tags;
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            Change::Untag { index, tag } => {
+                list[*index].tags.retain(|existing| existing != tag);
+            }
+        }
+        self.log.push(edit);
+        Ok(())
+    }
+}
+
+impl Edit {
+    /// An edit made right now.
+    pub fn now(editor: &str, user: &str, change: Change) -> Self {
+        Edit {
+            at: now(),
+            editor: editor.to_owned(),
+            user: user.to_owned(),
+            change,
+        }
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}'s list has {} {}, there is no position {}",
+            self.user,
+            self.len,
+            if self.len == 1 { "item" } else { "items" },
+            self.index + 1,
+        )
+    }
+}
+
+impl error::Error for Error {}
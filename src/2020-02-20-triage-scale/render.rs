@@ -0,0 +1,233 @@
+//! A team member's list as a web page, and as markdown for a PR comment.
+//!
+//! Imagine if the comment that @rust-highfive leaves on new PRs included a
+//! link to the reviewer's triagebot page. The [`html`] page is that link's
+//! destination: a self-contained document with no external stylesheets,
+//! scripts or images. The [`markdown`] rendering fits in the comment itself.
+//!
+//! ```
+//! use dtolnay::triage_scale::notifications::Item;
+//! use dtolnay::triage_scale::render;
+//!
+//! let list = [Item {
+//!     url: "https://github.com/dtolnay/syn/issues/700".to_owned(),
+//!     description: "Question about <attributes>".to_owned(),
+//!     added_by: "jane".to_owned(),
+//!     added_at: 1_582_156_800,
+//!     tags: vec!["question".to_owned()],
+//! }];
+//!
+//! assert_eq!(
+//!     render::markdown("dtolnay", &list, None),
+//!     "\
+//! **@dtolnay's queue** has 1 item:
+//!
+//! 1. [Question about \\<attributes\\>](https://github.com/dtolnay/syn/issues/700) `question` &mdash; added by @jane on 2020-02-20
+//! ",
+//! );
+//! ```
+
+use super::notifications::Item;
+use std::fmt::Write;
+
+const STYLE: &str = "\
+body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Helvetica, Arial, sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; color: #24292e; }
+h1 { font-size: 1.6em; }
+ol { padding-left: 2em; }
+li { margin-bottom: 0.8em; }
+.meta { color: #6a737d; font-size: 0.85em; }
+.tag { display: inline-block; background: #e1ecf4; color: #39739d; border-radius: 3px; padding: 0 0.4em; font-size: 0.85em; margin-left: 0.3em; }
+code { background: #f6f8fa; padding: 0.1em 0.3em; border-radius: 3px; }
+";
+
+/// Standalone HTML page listing the user's items in order.
+pub fn html(user: &str, list: &[Item]) -> String {
+    let user = escape_html(user);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    out.push_str("<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    let _ = writeln!(out, "<title>{user}'s notifications</title>");
+    let _ = write!(out, "<style>\n{STYLE}</style>\n");
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{user}'s notifications</h1>");
+    let _ = writeln!(
+        out,
+        "<p>{}. This list is publicly editable: if something deserves {user}'s attention, add it or move it up, for example <code>@{user} add 1 URL description</code>.</p>",
+        count(list.len()),
+    );
+    if !list.is_empty() {
+        out.push_str("<ol>\n");
+        for item in list {
+            let url = escape_html(&item.url);
+            let text = if item.description.is_empty() {
+                url.clone()
+            } else {
+                escape_html(&item.description)
+            };
+            if is_web(&item.url) {
+                let _ = write!(out, "<li><a href=\"{url}\">{text}</a>");
+            } else {
+                let _ = write!(out, "<li>{text}");
+            }
+            for tag in &item.tags {
+                let _ = write!(out, "<span class=\"tag\">{}</span>", escape_html(tag));
+            }
+            let _ = writeln!(
+                out,
+                "<br><span class=\"meta\">added by {} on <time datetime=\"{}\">{}</time></span></li>",
+                escape_html(&item.added_by),
+                datetime(item.added_at),
+                timestamp(item.added_at),
+            );
+        }
+        out.push_str("</ol>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Markdown list of the user's items, optionally followed by a link to their
+/// full page.
+pub fn markdown(user: &str, list: &[Item], page: Option<&str>) -> String {
+    let mut out = String::new();
+    if list.is_empty() {
+        let _ = writeln!(out, "**{}'s queue** is empty.", mention(user));
+    } else {
+        let _ = writeln!(
+            out,
+            "**{}'s queue** has {}:",
+            mention(user),
+            count(list.len()),
+        );
+        out.push('\n');
+        for (i, item) in list.iter().enumerate() {
+            let text = if item.description.is_empty() {
+                &item.url
+            } else {
+                &item.description
+            };
+            if is_web(&item.url) {
+                let _ = write!(
+                    out,
+                    "{}. [{}]({})",
+                    i + 1,
+                    escape_markdown(text),
+                    link_destination(&item.url),
+                );
+            } else {
+                let _ = write!(out, "{}. {}", i + 1, escape_markdown(text));
+            }
+            for tag in &item.tags {
+                let _ = write!(out, " `{}`", tag.replace('`', ""));
+            }
+            let _ = writeln!(
+                out,
+                " &mdash; added by {} on {}",
+                mention(&item.added_by),
+                date(item.added_at),
+            );
+        }
+    }
+    if let Some(page) = page {
+        out.push('\n');
+        let _ = writeln!(
+            out,
+            "[Full list and how to edit it]({})",
+            link_destination(page),
+        );
+    }
+    out
+}
+
+fn count(len: usize) -> String {
+    format!("{len} {}", if len == 1 { "item" } else { "items" })
+}
+
+// Only web URLs become links. Anything else on the publicly editable list,
+// like a `javascript:` URL, is shown as text.
+fn is_web(url: &str) -> bool {
+    let scheme = url.get(..8).unwrap_or(url).to_ascii_lowercase();
+    scheme.starts_with("https://") || scheme.starts_with("http://")
+}
+
+// An @-mention of a GitHub user, or for anything that is not a valid username
+// the name in a code span, so that it can neither ping nor inject markup.
+fn mention(user: &str) -> String {
+    if !user.is_empty() && user.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+        format!("@{user}")
+    } else {
+        format!("`{}`", user.replace('`', "").replace('\n', " "))
+    }
+}
+
+/// Escapes text for use in HTML element content or a quoted attribute.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if "\\`*_[]<>|~&".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(if ch == '\n' { ' ' } else { ch });
+    }
+    escaped
+}
+
+// Characters that would end a markdown link destination early.
+fn link_destination(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+// Calendar date and time in UTC of a Unix timestamp.
+fn civil(secs: u64) -> (u64, u64, u64, u64, u64) {
+    let days = secs / 86400;
+    let rem = secs % 86400;
+    // Howard Hinnant's civil_from_days, shifted so that the era begins on
+    // 0000-03-01 and all quantities are nonnegative.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day, rem / 3600, rem % 3600 / 60)
+}
+
+/// Calendar date in UTC of a Unix timestamp, as in "2020-02-20".
+pub fn date(secs: u64) -> String {
+    let (year, month, day, _, _) = civil(secs);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn timestamp(secs: u64) -> String {
+    let (year, month, day, hour, minute) = civil(secs);
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02} UTC")
+}
+
+fn datetime(secs: u64) -> String {
+    let (year, month, day, hour, minute) = civil(secs);
+    let second = secs % 60;
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
@@ -0,0 +1,271 @@
+//! Persistence of [`Notifications`] to a local file.
+//!
+//! The file holds the edit log, one edit per line, and nothing else. The
+//! current lists are recovered by replaying the log from the beginning. Edits
+//! are only ever appended, under an exclusive lock on the file, so any number
+//! of threads or processes can safely edit through their own `Store` at once:
+//! each edit is checked against, and applied on top of, every edit that was
+//! appended before it.
+//!
+//! ```
+//! use dtolnay::triage_scale::notifications::{Change, Edit};
+//! use dtolnay::triage_scale::store::Store;
+//! # use std::env;
+//! #
+//! # if cfg!(miri) {
+//! #     return Ok(());
+//! # }
+//! # let path = env::temp_dir().join(format!("store-doctest-{}", std::process::id()));
+//! # let _ = std::fs::remove_file(&path);
+//!
+//! let store = Store::open(&path)?;
+//! store.apply(Edit::now(
+//!     "jane",
+//!     "dtolnay",
+//!     Change::Add {
+//!         index: None,
+//!         url: "https://github.com/dtolnay/syn/issues/700".to_owned(),
+//!         description: "Question about parsing attributes".to_owned(),
+//!     },
+//! ))?;
+//!
+//! let notifications = Store::open(&path)?.load()?;
+//! assert_eq!(notifications.list("dtolnay")[0].added_by, "jane");
+//! # std::fs::remove_file(&path)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::notifications::{self, Change, Edit, Notifications};
+use std::error;
+use std::fmt::{self, Display};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Handle to an edit log file.
+#[derive(Clone, Debug)]
+pub struct Store {
+    path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A line of the file that is not an edit, or is an edit that does not
+    /// apply on top of the lines before it.
+    Corrupt {
+        line: usize,
+        msg: String,
+    },
+    /// The edit being applied does not apply to the current lists.
+    Edit(notifications::Error),
+}
+
+impl Store {
+    /// Opens the edit log at the given path, creating an empty one if it does
+    /// not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Store { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replays the whole log.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is corrupt.
+    pub fn load(&self) -> Result<Notifications, Error> {
+        let mut file = File::open(&self.path)?;
+        file.lock_shared()?;
+        let (notifications, _len) = replay(&mut file)?;
+        Ok(notifications)
+    }
+
+    /// Appends an edit to the log if it applies on top of every edit already
+    /// in the log, and returns the resulting lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or written or is corrupt,
+    /// or if the edit refers to a position the list does not have.
+    pub fn apply(&self, edit: Edit) -> Result<Notifications, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        file.lock()?;
+        let (mut notifications, len) = replay(&mut file)?;
+        let line = serialize(&edit);
+        notifications.apply(edit).map_err(Error::Edit)?;
+        // Discard the remains of an append that was interrupted partway.
+        file.set_len(len)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(notifications)
+    }
+}
+
+// The lists as of the last complete line, and the length of the file up to
+// that line.
+fn replay(file: &mut File) -> Result<(Notifications, u64), Error> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let complete = contents.rfind('\n').map_or(0, |newline| newline + 1);
+
+    let mut notifications = Notifications::new();
+    for (i, line) in contents[..complete].lines().enumerate() {
+        let corrupt = |msg: String| Error::Corrupt { line: i + 1, msg };
+        let edit = parse(line).ok_or_else(|| corrupt("not an edit".to_owned()))?;
+        notifications
+            .apply(edit)
+            .map_err(|err| corrupt(err.to_string()))?;
+    }
+    Ok((notifications, complete as u64))
+}
+
+fn serialize(edit: &Edit) -> String {
+    let mut fields = vec![
+        edit.at.to_string(),
+        escape(&edit.editor),
+        escape(&edit.user),
+    ];
+    match &edit.change {
+        Change::Add {
+            index,
+            url,
+            description,
+        } => {
+            fields.push("add".to_owned());
+            fields.push(index.map_or_else(String::new, |index| index.to_string()));
+            fields.push(escape(url));
+            fields.push(escape(description));
+        }
+        Change::Move { from, to } => {
+            fields.push("move".to_owned());
+            fields.push(from.to_string());
+            fields.push(to.to_string());
+        }
+        Change::Remove { index } => {
+            fields.push("remove".to_owned());
+            fields.push(index.to_string());
+        }
+        Change::Tag { index, tag } => {
+            fields.push("tag".to_owned());
+            fields.push(index.to_string());
+            fields.push(escape(tag));
+        }
+        Change::Untag { index, tag } => {
+            fields.push("untag".to_owned());
+            fields.push(index.to_string());
+            fields.push(escape(tag));
+        }
+    }
+    let mut line = fields.join("\t");
+    line.push('\n');
+    line
+}
+
+fn parse(line: &str) -> Option<Edit> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let change = match fields.get(3..)? {
+        ["add", index, url, description] => Change::Add {
+            index: if index.is_empty() {
+                None
+            } else {
+                Some(index.parse().ok()?)
+            },
+            url: unescape(url)?,
+            description: unescape(description)?,
+        },
+        ["move", from, to] => Change::Move {
+            from: from.parse().ok()?,
+            to: to.parse().ok()?,
+        },
+        ["remove", index] => Change::Remove {
+            index: index.parse().ok()?,
+        },
+        ["tag", index, tag] => Change::Tag {
+            index: index.parse().ok()?,
+            tag: unescape(tag)?,
+        },
+        ["untag", index, tag] => Change::Untag {
+            index: index.parse().ok()?,
+            tag: unescape(tag)?,
+        },
+        _ => return None,
+    };
+    Some(Edit {
+        at: fields[0].parse().ok()?,
+        editor: unescape(fields[1])?,
+        user: unescape(fields[2])?,
+        change,
+    })
+}
+
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for ch in field.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => Display::fmt(err, formatter),
+            Error::Corrupt { line, msg } => write!(formatter, "line {line}: {msg}"),
+            Error::Edit(err) => Display::fmt(err, formatter),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Corrupt { .. } => None,
+            Error::Edit(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
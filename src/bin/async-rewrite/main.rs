@@ -0,0 +1,81 @@
+//! Suggests async/await equivalents for functions built out of futures 0.1
+//! combinators, in the style of the before and after snippets in *Await a
+//! minute, why bother?*
+//!
+//! ```console
+//! $ cargo run --bin async-rewrite -- src/server.rs
+//! ```
+//!
+//! Every function that returns `impl Future<Item = T, Error = E>` or whose
+//! body chains `.map`, `.then`, `.and_then`, `.into_future().flatten()` or
+//! `future::result` is printed back as an `async fn` returning `Result<T, E>`
+//! in which each asynchronous step is an `.await` and each fallible step is a
+//! `?`. Which calls are asynchronous is inferred from the signatures declared
+//! in the same file. The output is a suggestion to start from, not a
+//! guaranteed drop-in replacement; anything the tool had to assume is called
+//! out in a `note:` comment above the function. A function returning a future
+//! whose body cannot be followed, such as one built on `future::join_all` or
+//! with its combinators inside an `if`, is printed unchanged with a note.
+
+#[path = "../common/lexer.rs"]
+mod lexer;
+mod rewrite;
+mod syntax;
+
+use crate::syntax::Parser;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::process;
+
+fn main() {
+    let paths: Vec<OsString> = env::args_os().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: async-rewrite <FILE>...");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    let mut first = true;
+    for path in paths {
+        let display = path.to_string_lossy();
+        let src = match fs::read_to_string(&path) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: {display}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+        let tokens = match lexer::tokenize(&src).and_then(|tokens| lexer::trees(&tokens)) {
+            Ok(trees) => trees,
+            Err(err) => {
+                eprintln!("error: {display}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+        let parser = Parser { src: &src };
+        let mut fns = Vec::new();
+        rewrite::collect_fns(&tokens, None, &mut fns);
+        fns.sort_by_key(|item| item.line);
+        for item in &fns {
+            let Some(suggestion) = rewrite::rewrite(&parser, &fns, item) else {
+                continue;
+            };
+            if !first {
+                println!();
+            }
+            first = false;
+            println!("// {}:{}: {}", display, suggestion.line, suggestion.name);
+            for note in &suggestion.notes {
+                println!("// note: {note}");
+            }
+            println!("{}", suggestion.code);
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,983 @@
+// Symbolic evaluation of a function body in which every futures 0.1
+// combinator is replaced by the sequential code it stands for: the closure
+// passed to `and_then` runs after a `?`, the closure passed to `then` runs
+// after an `.await`, and so on. Whether a call produces a future or a Result
+// is decided from the signatures declared in the same file; calls that cannot
+// be classified are left alone.
+
+use crate::lexer::{self, Kind, TokenTree};
+use crate::syntax::{self, angle_end, split_top_level, Expr, Parser, Stmt};
+use std::collections::VecDeque;
+use std::mem;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Ret {
+    Future,
+    Result,
+}
+
+pub struct FnItem<'t, 'a> {
+    pub name: &'a str,
+    pub line: usize,
+    pub self_ty: Option<String>,
+    pub has_self: bool,
+    pub ret: Option<Ret>,
+    pub header: &'t [TokenTree<'a>],
+    pub generics: &'t [TokenTree<'a>],
+    pub params: &'t TokenTree<'a>,
+    pub ret_ty: &'t [TokenTree<'a>],
+    pub where_clause: &'t [TokenTree<'a>],
+    pub body: &'t TokenTree<'a>,
+    // The whole item from its visibility through the closing brace.
+    pub source: &'t [TokenTree<'a>],
+}
+
+pub struct Suggestion {
+    pub line: usize,
+    pub name: String,
+    pub notes: Vec<String>,
+    pub code: String,
+}
+
+// A partially evaluated expression.
+enum Value {
+    // Nothing is known about the type of this expression.
+    Unknown(Expr),
+    // An expression that has already been awaited and/or unwrapped with `?`.
+    Plain(Expr),
+    // A futures 0.1 future; `.await` produces a Result.
+    Future(Expr),
+    // A Result, or a future that is already resolved.
+    Result(Expr),
+    // The success case of a Result whose error case has been dealt with.
+    Ok(Box<Value>),
+}
+
+struct Rewriter<'s, 't, 'a> {
+    fns: &'s [FnItem<'t, 'a>],
+    env: Vec<(String, Value)>,
+    notes: Vec<String>,
+    changes: usize,
+    futures: usize,
+}
+
+pub fn collect_fns<'t, 'a>(
+    trees: &'t [TokenTree<'a>],
+    self_ty: Option<&str>,
+    fns: &mut Vec<FnItem<'t, 'a>>,
+) {
+    let mut i = 0;
+    while i < trees.len() {
+        let tree = &trees[i];
+        if tree.is("impl") {
+            if let Some(brace) = trees[i..].iter().position(|tree| tree.group("{").is_some()) {
+                let brace = i + brace;
+                let header = &trees[i + 1..brace];
+                let ty = impl_self_ty(header);
+                if let TokenTree::Group(group) = &trees[brace] {
+                    collect_fns(&group.trees, ty, fns);
+                }
+                i = brace + 1;
+                continue;
+            }
+        } else if tree.is("fn") {
+            if let Some((item, end)) = parse_fn(trees, i, self_ty) {
+                if let TokenTree::Group(group) = item.body {
+                    collect_fns(&group.trees, None, fns);
+                }
+                fns.push(item);
+                i = end;
+                continue;
+            }
+        } else if let TokenTree::Group(group) = tree {
+            collect_fns(&group.trees, self_ty, fns);
+        }
+        i += 1;
+    }
+}
+
+fn impl_self_ty<'a>(header: &[TokenTree<'a>]) -> Option<&'a str> {
+    let mut start = 0;
+    if header.first().is_some_and(|tree| tree.is("<")) {
+        start = angle_end(header, 0)?;
+    }
+    if let Some(for_token) = syntax::find_top_level(header, start, "for") {
+        start = for_token + 1;
+    }
+    let end = syntax::find_top_level(header, start, "where").unwrap_or(header.len());
+    let ty = &header[start..end];
+    let generics = ty.iter().position(|tree| tree.is("<")).unwrap_or(ty.len());
+    ty[..generics].iter().rev().find_map(TokenTree::ident)
+}
+
+fn parse_fn<'t, 'a>(
+    trees: &'t [TokenTree<'a>],
+    i: usize,
+    self_ty: Option<&str>,
+) -> Option<(FnItem<'t, 'a>, usize)> {
+    let name = trees.get(i + 1)?.ident()?;
+    let mut start = i;
+    let mut is_async = false;
+    while start > 0 {
+        let prev = &trees[start - 1];
+        if prev.is("async") {
+            is_async = true;
+        } else if !(prev.is("unsafe") || prev.is("const") || prev.is("extern"))
+            && prev.first().kind != Kind::Literal
+        {
+            break;
+        }
+        start -= 1;
+    }
+    if start > 0 && trees[start - 1].is("pub") {
+        start -= 1;
+    } else if start > 1 && trees[start - 1].group("(").is_some() && trees[start - 2].is("pub") {
+        start -= 2;
+    }
+
+    let mut j = i + 2;
+    let generics_start = j;
+    if trees.get(j)?.is("<") {
+        j = angle_end(trees, j)?;
+    }
+    let generics = &trees[generics_start..j];
+    let params = trees.get(j)?;
+    let params_group = params.group("(")?;
+    j += 1;
+    let mut ret_ty: &[TokenTree] = &[];
+    if trees.get(j)?.is("->") {
+        let ret_start = j + 1;
+        j = ret_start;
+        let mut angle = 0;
+        while let Some(tree) = trees.get(j) {
+            if tree.is("<") {
+                angle += 1;
+            } else if tree.is(">") {
+                angle -= 1;
+            } else if angle == 0 && (tree.is("where") || tree.is(";") || tree.group("{").is_some())
+            {
+                break;
+            }
+            j += 1;
+        }
+        ret_ty = &trees[ret_start..j];
+    }
+    let where_start = j;
+    while trees
+        .get(j)
+        .is_some_and(|tree| !tree.is(";") && tree.group("{").is_none())
+    {
+        j += 1;
+    }
+    let where_clause = &trees[where_start..j];
+    let body = trees.get(j).filter(|tree| tree.group("{").is_some())?;
+
+    let has_self = split_top_level(&params_group.trees, ",")[0]
+        .iter()
+        .any(|tree| tree.is("self"));
+    let ret = if is_async || is_future_ty(ret_ty) {
+        Some(Ret::Future)
+    } else if is_result_ty(ret_ty) {
+        Some(Ret::Result)
+    } else {
+        None
+    };
+
+    let item = FnItem {
+        name,
+        line: trees[i].first().line,
+        self_ty: self_ty.map(str::to_owned),
+        has_self,
+        ret,
+        header: &trees[start..i],
+        generics,
+        params,
+        ret_ty,
+        where_clause,
+        body,
+        source: &trees[start..=j],
+    };
+    Some((item, j + 1))
+}
+
+fn is_future_ty(ty: &[TokenTree]) -> bool {
+    ty.iter()
+        .filter_map(TokenTree::ident)
+        .any(|ident| ident.ends_with("Future") || ident == "FutureResult")
+}
+
+fn is_result_ty(ty: &[TokenTree]) -> bool {
+    let generics = ty.iter().position(|tree| tree.is("<")).unwrap_or(ty.len());
+    ty[..generics]
+        .iter()
+        .rev()
+        .find_map(TokenTree::ident)
+        .is_some_and(|ident| ident == "Result")
+}
+
+// `impl Future<Item = T, Error = E> + Send` becomes `Result<T, E>`.
+fn future_to_result(parser: &Parser, ty: &[TokenTree]) -> Option<String> {
+    let future = ty.iter().position(|tree| tree.ident() == Some("Future"))?;
+    let end = angle_end(ty, future + 1)?;
+    let mut item = "()".to_owned();
+    let mut error = "()".to_owned();
+    for binding in split_top_level(&ty[future + 2..end - 1], ",") {
+        if binding.len() > 2 && binding[1].is("=") {
+            let rhs = parser.snippet(&binding[2..]);
+            match binding[0].ident() {
+                Some("Item") => item = rhs,
+                Some("Error") => error = rhs,
+                _ => {}
+            }
+        }
+    }
+    Some(format!("Result<{item}, {error}>"))
+}
+
+pub fn rewrite(parser: &Parser, fns: &[FnItem], item: &FnItem) -> Option<Suggestion> {
+    let Some(body) = parser.parse_block(item.body) else {
+        return unchanged(parser, item);
+    };
+    let mut rewriter = Rewriter {
+        fns,
+        env: Vec::new(),
+        notes: Vec::new(),
+        changes: 0,
+        futures: 0,
+    };
+    let mut stmts = Vec::new();
+    let tail = rewriter.lower_stmts(body.stmts, &mut stmts);
+    let returns_future = item.ret == Some(Ret::Future);
+    if rewriter.changes == 0 || rewriter.futures == 0 && !returns_future {
+        return unchanged(parser, item);
+    }
+
+    let mut header = String::new();
+    let vis = parser.snippet(item.header).replace("async ", "");
+    if !vis.is_empty() {
+        header.push_str(&vis);
+        header.push(' ');
+    }
+    let result_ty = future_to_result(parser, item.ret_ty).filter(|_| returns_future);
+    if result_ty.is_some() {
+        header.push_str("async ");
+    }
+    header.push_str("fn ");
+    header.push_str(item.name);
+    header.push_str(&parser.snippet(item.generics));
+    header.push_str(&parser.snippet(std::slice::from_ref(item.params)));
+    if let Some(result_ty) = &result_ty {
+        header.push_str(" -> ");
+        header.push_str(result_ty);
+    } else if !item.ret_ty.is_empty() {
+        header.push_str(" -> ");
+        header.push_str(&parser.snippet(item.ret_ty));
+    }
+    if item.where_clause.is_empty() {
+        header.push_str(" {\n");
+    } else {
+        header.push('\n');
+        header.push_str(&parser.snippet(item.where_clause));
+        header.push_str("\n{\n");
+    }
+
+    match tail {
+        Some(tail) if result_ty.is_some() => rewriter.push_return(tail, &mut stmts),
+        None if result_ty.is_some() => stmts.push(Stmt::Expr {
+            expr: ok(Expr::Lit("()".to_owned())),
+            semi: false,
+        }),
+        Some(tail) => stmts.push(Stmt::Expr {
+            expr: tail.into_expr(),
+            semi: false,
+        }),
+        None => {}
+    }
+    if result_ty.is_none() {
+        rewriter.notes.push(format!(
+            "`{}` does not return a future; the rewritten body needs to move into an async fn returning Result",
+            item.name,
+        ));
+    }
+
+    let mut code = header;
+    syntax::print_stmts(&stmts, 4, &mut code);
+    code.push('}');
+    Some(Suggestion {
+        line: item.line,
+        name: item.name.to_owned(),
+        notes: rewriter.notes,
+        code,
+    })
+}
+
+// A function returning a future whose body the tool could not lower, printed
+// as is so that it does not silently go missing from the output.
+fn unchanged(parser: &Parser, item: &FnItem) -> Option<Suggestion> {
+    if item.ret != Some(Ret::Future) || item.header.iter().any(|tree| tree.is("async")) {
+        return None;
+    }
+    Some(Suggestion {
+        line: item.line,
+        name: item.name.to_owned(),
+        notes: vec![format!(
+            "left `{}` unchanged; its body does not chain combinators in a form this tool can rewrite",
+            item.name,
+        )],
+        code: parser.snippet(item.source),
+    })
+}
+
+impl Rewriter<'_, '_, '_> {
+    fn lower_stmts(&mut self, stmts: Vec<Stmt>, out: &mut Vec<Stmt>) -> Option<Value> {
+        let mut stmts = VecDeque::from(stmts);
+        while let Some(stmt) = stmts.pop_front() {
+            self.flush_verbatim_uses(|name| count_verbatim_stmt(name, &stmt), out);
+            match stmt {
+                Stmt::Let {
+                    pat,
+                    ty,
+                    init: Some(init),
+                } => {
+                    if is_clone_rebind(&pat, &init) {
+                        self.notes.push(format!(
+                            "dropped `let {pat} = {pat}.clone();`, the async fn can borrow `{pat}` instead",
+                        ));
+                        self.changes += 1;
+                        continue;
+                    }
+                    let before = self.changes;
+                    let value = self.lower(init, out);
+                    if self.changes == before {
+                        let kind = value.kind();
+                        let init = Some(value.into_expr());
+                        out.push(Stmt::Let {
+                            pat: pat.clone(),
+                            ty,
+                            init,
+                        });
+                        if let Some(kind) = kind.filter(|_| is_ident(&pat)) {
+                            self.env.push((pat.clone(), kind(Expr::Path(pat))));
+                        }
+                    } else {
+                        let uses = count_in_rest(&pat, stmts.make_contiguous());
+                        self.bind(pat, value, uses, out);
+                    }
+                }
+                Stmt::Expr { expr, semi } if semi || !stmts.is_empty() => {
+                    let before = self.changes;
+                    let value = self.lower(expr, out);
+                    if self.changes == before || !semi {
+                        out.push(Stmt::Expr {
+                            expr: value.into_expr(),
+                            semi,
+                        });
+                    } else {
+                        self.push_effect(value, out);
+                    }
+                }
+                Stmt::Expr { expr, .. } => return Some(self.lower(expr, out)),
+                stmt @ (Stmt::Let { init: None, .. } | Stmt::Verbatim(_)) => out.push(stmt),
+            }
+        }
+        None
+    }
+
+    fn lower(&mut self, expr: Expr, out: &mut Vec<Stmt>) -> Value {
+        match expr {
+            Expr::Path(path) => match self.take(&path) {
+                Some(value) => value,
+                None => Value::Unknown(Expr::Path(path)),
+            },
+            Expr::Paren(inner) => match self.lower(*inner, out) {
+                Value::Unknown(expr) => Value::Unknown(Expr::Paren(Box::new(expr))),
+                Value::Plain(expr) => Value::Plain(Expr::Paren(Box::new(expr))),
+                value => value,
+            },
+            Expr::Call { func, args } => self.lower_call(*func, args, out),
+            Expr::MethodCall {
+                receiver,
+                method,
+                turbofish,
+                args,
+            } => {
+                let receiver = self.lower(*receiver, out);
+                self.lower_method(receiver, method, turbofish, args, out)
+            }
+            expr => Value::Unknown(self.subst(expr)),
+        }
+    }
+
+    fn lower_call(&mut self, func: Expr, mut args: Vec<Expr>, out: &mut Vec<Stmt>) -> Value {
+        let func = match func {
+            Expr::Paren(inner) if args.is_empty() => match *inner {
+                Expr::Closure { params, body, .. } if params.is_empty() => {
+                    self.notes
+                        .push("inlined an immediately invoked closure; `?` inside it now returns from the async fn".to_owned());
+                    self.changes += 1;
+                    return self.lower_body(*body, out);
+                }
+                inner => Expr::Paren(Box::new(inner)),
+            },
+            func => func,
+        };
+        let Expr::Path(path) = &func else {
+            let func = Box::new(self.subst(func));
+            let args = self.subst_all(args);
+            return Value::Unknown(Expr::Call { func, args });
+        };
+
+        let segments: Vec<&str> = path.split("::").map(str::trim).collect();
+        let last_two = segments[segments.len().saturating_sub(2)..].join("::");
+        if args.len() == 1 {
+            match (path.as_str(), last_two.as_str()) {
+                ("Ok", _) => {
+                    let value = self.lower(args.remove(0), out);
+                    return Value::Ok(Box::new(value));
+                }
+                (_, "future::result" | "future::done") => {
+                    self.changes += 1;
+                    self.futures += 1;
+                    return match self.lower(args.remove(0), out) {
+                        Value::Unknown(expr) | Value::Plain(expr) => Value::Result(expr),
+                        value => value,
+                    };
+                }
+                (_, "future::ok") => {
+                    self.changes += 1;
+                    self.futures += 1;
+                    let value = self.lower(args.remove(0), out);
+                    return Value::Ok(Box::new(value));
+                }
+                (_, "future::err") => {
+                    self.changes += 1;
+                    self.futures += 1;
+                    let args = self.subst_all(args);
+                    let func = Box::new(Expr::Path("Err".to_owned()));
+                    return Value::Result(Expr::Call { func, args });
+                }
+                _ => {}
+            }
+        }
+
+        let ret = self.lookup_path(&segments);
+        let args = self.subst_all(args);
+        let call = Expr::Call {
+            func: Box::new(func),
+            args,
+        };
+        match ret {
+            Some(Ret::Future) => Value::Future(call),
+            Some(Ret::Result) => Value::Result(call),
+            None => Value::Unknown(call),
+        }
+    }
+
+    fn lower_method(
+        &mut self,
+        receiver: Value,
+        method: String,
+        turbofish: String,
+        mut args: Vec<Expr>,
+        out: &mut Vec<Stmt>,
+    ) -> Value {
+        let receiver = match receiver {
+            Value::Unknown(expr) | Value::Plain(expr) => {
+                let ret = self.lookup_method(&method);
+                let call = Expr::MethodCall {
+                    receiver: Box::new(expr),
+                    method,
+                    turbofish,
+                    args: self.subst_all(args),
+                };
+                return match ret {
+                    Some(Ret::Future) => Value::Future(call),
+                    Some(Ret::Result) => Value::Result(call),
+                    None => Value::Unknown(call),
+                };
+            }
+            receiver => receiver,
+        };
+
+        match (method.as_str(), args.len()) {
+            ("into_future", 0) => {
+                self.changes += 1;
+                self.futures += 1;
+                return receiver;
+            }
+            ("flatten", 0) => {
+                self.changes += 1;
+                self.futures += 1;
+                return match self.unwrap(receiver) {
+                    Value::Unknown(expr) | Value::Plain(expr) => Value::Future(expr),
+                    value => value,
+                };
+            }
+            ("unwrap", 0) if !matches!(receiver, Value::Future(_)) => {
+                self.changes += 1;
+                return self.unwrap(receiver);
+            }
+            ("wait", 0) if matches!(receiver, Value::Future(_)) => {
+                self.changes += 1;
+                self.futures += 1;
+                return self.resolve(receiver);
+            }
+            ("map_err", 1) => {
+                self.changes += 1;
+                let receiver = self.resolve(receiver).into_expr();
+                return Value::Result(Expr::MethodCall {
+                    receiver: Box::new(receiver),
+                    method,
+                    turbofish,
+                    args: self.subst_all(args),
+                });
+            }
+            ("map" | "and_then" | "then", 1) => {
+                if let Expr::Closure { params, .. } = &args[0] {
+                    if params.len() <= 1 {
+                        let Expr::Closure { params, body, .. } = args.remove(0) else {
+                            unreachable!();
+                        };
+                        let param = params.into_iter().next().unwrap_or_else(|| "_".to_owned());
+                        return self.lower_combinator(&method, receiver, param, *body, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Value::Unknown(Expr::MethodCall {
+            receiver: Box::new(receiver.into_expr()),
+            method,
+            turbofish,
+            args: self.subst_all(args),
+        })
+    }
+
+    fn lower_combinator(
+        &mut self,
+        method: &str,
+        receiver: Value,
+        param: String,
+        body: Expr,
+        out: &mut Vec<Stmt>,
+    ) -> Value {
+        self.changes += 1;
+        if matches!(receiver, Value::Future(_)) {
+            self.futures += 1;
+        }
+        let uses = count_expr(&param, &body);
+        let arg = if method == "then" {
+            self.resolve(receiver)
+        } else {
+            self.unwrap(receiver)
+        };
+        self.bind(param, arg, uses, out);
+        let value = self.lower_body(body, out);
+        if method == "map" {
+            return Value::Ok(Box::new(value));
+        }
+        match value {
+            Value::Unknown(expr) | Value::Plain(expr) => {
+                let mut text = String::new();
+                syntax::print_expr(&expr, 0, &mut text);
+                self.notes.push(format!(
+                    "assumed `{text}` returns a Result; add `.await` if it is a future",
+                ));
+                Value::Result(expr)
+            }
+            value => value,
+        }
+    }
+
+    fn lower_body(&mut self, body: Expr, out: &mut Vec<Stmt>) -> Value {
+        match body {
+            Expr::Block(block) => self
+                .lower_stmts(block.stmts, out)
+                .unwrap_or_else(|| Value::Plain(Expr::Lit("()".to_owned()))),
+            body => {
+                self.flush_verbatim_uses(|name| count_verbatim(name, &body), out);
+                self.lower(body, out)
+            }
+        }
+    }
+
+    // The value inside of a Result or future, propagating its error with `?`.
+    fn unwrap(&mut self, value: Value) -> Value {
+        match value {
+            Value::Ok(value) => *value,
+            Value::Future(expr) => {
+                self.futures += 1;
+                Value::Plain(Expr::Try(Box::new(Expr::Await(Box::new(expr)))))
+            }
+            Value::Result(expr) | Value::Unknown(expr) | Value::Plain(expr) => {
+                Value::Plain(Expr::Try(Box::new(expr)))
+            }
+        }
+    }
+
+    // The Result of a future, which is the argument to a `then` closure.
+    fn resolve(&mut self, value: Value) -> Value {
+        match value {
+            Value::Future(expr) => {
+                self.futures += 1;
+                Value::Result(Expr::Await(Box::new(expr)))
+            }
+            value => Value::Result(value.into_expr()),
+        }
+    }
+
+    fn bind(&mut self, pat: String, value: Value, uses: usize, out: &mut Vec<Stmt>) {
+        if pat == "_" {
+            self.push_effect(value, out);
+            return;
+        }
+        if let Value::Plain(Expr::Path(path)) = &value {
+            if *path == pat {
+                return;
+            }
+        }
+        let kind = value.kind();
+        if kind.is_some() && uses <= 1 && is_ident(&pat) {
+            self.env.push((pat, value));
+            return;
+        }
+        out.push(Stmt::Let {
+            pat: pat.clone(),
+            ty: None,
+            init: Some(value.into_expr()),
+        });
+        if let Some(kind) = kind.filter(|_| is_ident(&pat)) {
+            self.env.push((pat.clone(), kind(Expr::Path(pat))));
+        }
+    }
+
+    // A statement evaluated for its side effects only.
+    fn push_effect(&mut self, value: Value, out: &mut Vec<Stmt>) {
+        let expr = match value {
+            Value::Future(expr) => {
+                self.futures += 1;
+                Expr::Try(Box::new(Expr::Await(Box::new(expr))))
+            }
+            Value::Result(expr) => Expr::Try(Box::new(expr)),
+            Value::Ok(value) => return self.push_effect(*value, out),
+            Value::Unknown(expr) | Value::Plain(expr) => {
+                if matches!(&expr, Expr::Path(_) | Expr::Lit(_)) {
+                    return;
+                }
+                expr
+            }
+        };
+        out.push(Stmt::Expr { expr, semi: true });
+    }
+
+    // The final value of an async fn that returns Result.
+    fn push_return(&mut self, value: Value, out: &mut Vec<Stmt>) {
+        let expr = match value {
+            Value::Ok(value) => ok(value.into_expr()),
+            Value::Plain(expr) => ok(expr),
+            value => {
+                let value = match value {
+                    Value::Unknown(expr) => Value::Future(expr),
+                    value => value,
+                };
+                let init = self.unwrap(value).into_expr();
+                out.push(Stmt::Let {
+                    pat: "ret".to_owned(),
+                    ty: None,
+                    init: Some(init),
+                });
+                ok(Expr::Path("ret".to_owned()))
+            }
+        };
+        out.push(Stmt::Expr { expr, semi: false });
+    }
+
+    // Deferred values cannot be substituted into code that was not parsed, so
+    // bind them to a variable of the same name before such a statement or
+    // tail expression.
+    fn flush_verbatim_uses(&mut self, uses: impl Fn(&str) -> usize, out: &mut Vec<Stmt>) {
+        let mut i = 0;
+        while i < self.env.len() {
+            let name = &self.env[i].0;
+            let deferred = !matches!(&self.env[i].1.expr(), Expr::Path(path) if path == name);
+            if deferred && uses(name) > 0 {
+                let (name, value) = self.env.remove(i);
+                let kind = value.kind().unwrap();
+                out.push(Stmt::Let {
+                    pat: name.clone(),
+                    ty: None,
+                    init: Some(value.into_expr()),
+                });
+                self.env.insert(i, (name.clone(), kind(Expr::Path(name))));
+            }
+            i += 1;
+        }
+    }
+
+    fn take(&mut self, name: &str) -> Option<Value> {
+        let i = self.env.iter().rposition(|(n, _)| n == name)?;
+        Some(self.env.remove(i).1)
+    }
+
+    fn subst(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Path(path) => match self.take(&path) {
+                Some(value) => value.into_expr(),
+                None => Expr::Path(path),
+            },
+            Expr::Call { func, args } => Expr::Call {
+                func: Box::new(self.subst(*func)),
+                args: self.subst_all(args),
+            },
+            Expr::MethodCall {
+                receiver,
+                method,
+                turbofish,
+                args,
+            } => Expr::MethodCall {
+                receiver: Box::new(self.subst(*receiver)),
+                method,
+                turbofish,
+                args: self.subst_all(args),
+            },
+            Expr::Field { base, member } => Expr::Field {
+                base: Box::new(self.subst(*base)),
+                member,
+            },
+            Expr::Closure {
+                is_move,
+                params,
+                body,
+            } => {
+                let env = mem::take(&mut self.env);
+                let (shadowed, visible) =
+                    env.into_iter().partition(|(name, _)| params.contains(name));
+                self.env = visible;
+                let body = Box::new(self.subst(*body));
+                self.env.extend::<Vec<_>>(shadowed);
+                Expr::Closure {
+                    is_move,
+                    params,
+                    body,
+                }
+            }
+            Expr::Block(mut block) => {
+                for stmt in &mut block.stmts {
+                    match stmt {
+                        Stmt::Let {
+                            init: Some(init), ..
+                        }
+                        | Stmt::Expr { expr: init, .. } => {
+                            let expr = mem::replace(init, Expr::Lit(String::new()));
+                            *init = self.subst(expr);
+                        }
+                        Stmt::Let { init: None, .. } | Stmt::Verbatim(_) => {}
+                    }
+                }
+                Expr::Block(block)
+            }
+            Expr::Try(expr) => Expr::Try(Box::new(self.subst(*expr))),
+            Expr::Await(expr) => Expr::Await(Box::new(self.subst(*expr))),
+            Expr::Unary { op, expr } => Expr::Unary {
+                op,
+                expr: Box::new(self.subst(*expr)),
+            },
+            Expr::Paren(expr) => Expr::Paren(Box::new(self.subst(*expr))),
+            expr @ (Expr::Lit(_) | Expr::Verbatim(_)) => expr,
+        }
+    }
+
+    fn subst_all(&mut self, exprs: Vec<Expr>) -> Vec<Expr> {
+        exprs.into_iter().map(|expr| self.subst(expr)).collect()
+    }
+
+    fn lookup_path(&self, segments: &[&str]) -> Option<Ret> {
+        let (name, ty) = match segments {
+            [name] => (*name, None),
+            [.., ty, name] => (*name, Some(*ty)),
+            [] => return None,
+        };
+        self.lookup(|item| {
+            item.name == name
+                && match ty {
+                    Some(ty) => item.self_ty.as_deref() == Some(ty),
+                    None => item.self_ty.is_none() && !item.has_self,
+                }
+        })
+    }
+
+    fn lookup_method(&self, name: &str) -> Option<Ret> {
+        self.lookup(|item| item.name == name && item.has_self)
+    }
+
+    // The return kind shared by every matching signature, if any.
+    fn lookup(&self, matches: impl Fn(&FnItem) -> bool) -> Option<Ret> {
+        let mut candidates = self.fns.iter().filter(|item| matches(item));
+        let ret = candidates.next()?.ret?;
+        candidates.all(|item| item.ret == Some(ret)).then_some(ret)
+    }
+}
+
+impl Value {
+    fn expr(&self) -> &Expr {
+        match self {
+            Value::Unknown(expr)
+            | Value::Plain(expr)
+            | Value::Future(expr)
+            | Value::Result(expr) => expr,
+            Value::Ok(value) => value.expr(),
+        }
+    }
+
+    fn kind(&self) -> Option<fn(Expr) -> Value> {
+        match self {
+            Value::Future(_) => Some(Value::Future),
+            Value::Result(_) | Value::Ok(_) => Some(Value::Result),
+            Value::Unknown(_) | Value::Plain(_) => None,
+        }
+    }
+
+    fn into_expr(self) -> Expr {
+        match self {
+            Value::Unknown(expr)
+            | Value::Plain(expr)
+            | Value::Future(expr)
+            | Value::Result(expr) => expr,
+            Value::Ok(value) => ok(value.into_expr()),
+        }
+    }
+}
+
+fn ok(expr: Expr) -> Expr {
+    Expr::Call {
+        func: Box::new(Expr::Path("Ok".to_owned())),
+        args: vec![expr],
+    }
+}
+
+fn is_ident(pat: &str) -> bool {
+    let mut chars = pat.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+        && pat != "_"
+        && pat != "mut"
+        && pat != "ref"
+}
+
+fn is_clone_rebind(pat: &str, init: &Expr) -> bool {
+    match init {
+        Expr::MethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        } => {
+            method == "clone"
+                && args.is_empty()
+                && matches!(&**receiver, Expr::Path(path) if path == pat)
+        }
+        _ => false,
+    }
+}
+
+// Uses of `name` in the statements following its binding, up to the point
+// where it is shadowed.
+fn count_in_rest(name: &str, stmts: &[Stmt]) -> usize {
+    let mut count = 0;
+    for stmt in stmts {
+        match stmt {
+            Stmt::Let { pat, init, .. } => {
+                count += init.as_ref().map_or(0, |init| count_expr(name, init));
+                if pat == name {
+                    break;
+                }
+            }
+            Stmt::Expr { expr, .. } => count += count_expr(name, expr),
+            Stmt::Verbatim(text) => count += count_text(name, text),
+        }
+    }
+    count
+}
+
+fn count_expr(name: &str, expr: &Expr) -> usize {
+    match expr {
+        Expr::Path(path) => usize::from(path == name),
+        Expr::Lit(_) => 0,
+        Expr::Verbatim(text) => count_text(name, text),
+        Expr::Call { func, args } => {
+            count_expr(name, func) + args.iter().map(|arg| count_expr(name, arg)).sum::<usize>()
+        }
+        Expr::MethodCall { receiver, args, .. } => {
+            count_expr(name, receiver) + args.iter().map(|arg| count_expr(name, arg)).sum::<usize>()
+        }
+        Expr::Closure { params, body, .. } => {
+            if params.iter().any(|param| param == name) {
+                0
+            } else {
+                count_expr(name, body)
+            }
+        }
+        Expr::Block(block) => count_in_rest(name, &block.stmts),
+        Expr::Field { base: expr, .. }
+        | Expr::Try(expr)
+        | Expr::Await(expr)
+        | Expr::Unary { expr, .. }
+        | Expr::Paren(expr) => count_expr(name, expr),
+    }
+}
+
+fn count_verbatim(name: &str, expr: &Expr) -> usize {
+    match expr {
+        Expr::Verbatim(text) => count_text(name, text),
+        Expr::Path(_) | Expr::Lit(_) => 0,
+        Expr::Call { func, args } => {
+            count_verbatim(name, func)
+                + args
+                    .iter()
+                    .map(|arg| count_verbatim(name, arg))
+                    .sum::<usize>()
+        }
+        Expr::MethodCall { receiver, args, .. } => {
+            count_verbatim(name, receiver)
+                + args
+                    .iter()
+                    .map(|arg| count_verbatim(name, arg))
+                    .sum::<usize>()
+        }
+        Expr::Block(block) => block
+            .stmts
+            .iter()
+            .map(|stmt| count_verbatim_stmt(name, stmt))
+            .sum(),
+        Expr::Closure { body: expr, .. }
+        | Expr::Field { base: expr, .. }
+        | Expr::Try(expr)
+        | Expr::Await(expr)
+        | Expr::Unary { expr, .. }
+        | Expr::Paren(expr) => count_verbatim(name, expr),
+    }
+}
+
+fn count_verbatim_stmt(name: &str, stmt: &Stmt) -> usize {
+    match stmt {
+        Stmt::Let { init, .. } => init.as_ref().map_or(0, |init| count_verbatim(name, init)),
+        Stmt::Expr { expr, .. } => count_verbatim(name, expr),
+        Stmt::Verbatim(text) => count_text(name, text),
+    }
+}
+
+fn count_text(name: &str, text: &str) -> usize {
+    let Ok(tokens) = lexer::tokenize(text) else {
+        return 0;
+    };
+    let mut count = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == Kind::Ident && token.text == name && !(i > 0 && tokens[i - 1].is(".")) {
+            count += 1;
+        }
+    }
+    count
+}
@@ -0,0 +1,578 @@
+// Just enough of a Rust expression syntax tree to talk about futures 0.1
+// combinator chains. Anything not recognized is carried along as verbatim
+// source text so that rewriting never loses code it does not understand.
+
+use crate::lexer::{Group, Kind, TokenTree};
+use std::fmt::Write as _;
+
+pub enum Expr {
+    Path(String),
+    Lit(String),
+    Verbatim(String),
+    Call {
+        func: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    MethodCall {
+        receiver: Box<Expr>,
+        method: String,
+        turbofish: String,
+        args: Vec<Expr>,
+    },
+    Field {
+        base: Box<Expr>,
+        member: String,
+    },
+    Closure {
+        is_move: bool,
+        params: Vec<String>,
+        body: Box<Expr>,
+    },
+    Block(Block),
+    Try(Box<Expr>),
+    Await(Box<Expr>),
+    Unary {
+        op: String,
+        expr: Box<Expr>,
+    },
+    Paren(Box<Expr>),
+}
+
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub verbatim: String,
+}
+
+pub enum Stmt {
+    Let {
+        pat: String,
+        ty: Option<String>,
+        init: Option<Expr>,
+    },
+    Expr {
+        expr: Expr,
+        semi: bool,
+    },
+    Verbatim(String),
+}
+
+pub struct Parser<'a> {
+    pub src: &'a str,
+}
+
+const EXPR_KEYWORDS: &[&str] = &[
+    "as", "async", "break", "const", "continue", "else", "for", "if", "let", "loop", "match",
+    "return", "static", "unsafe", "while", "yield",
+];
+
+const ITEM_KEYWORDS: &[&str] = &[
+    "enum",
+    "extern",
+    "fn",
+    "impl",
+    "macro_rules",
+    "mod",
+    "pub",
+    "struct",
+    "trait",
+    "type",
+    "union",
+    "use",
+];
+
+impl Parser<'_> {
+    // Source text of a run of token trees, with the indentation of any
+    // continuation lines made relative to the line of the first token.
+    pub fn snippet(&self, trees: &[TokenTree]) -> String {
+        let (Some(first), Some(last)) = (trees.first(), trees.last()) else {
+            return String::new();
+        };
+        let start = first.first().offset;
+        let end = last.last().offset + last.last().text.len();
+        let line_start = self.src[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.src[line_start..start];
+        let column = line.len() - line.trim_start().len();
+        let mut snippet = String::new();
+        for (i, line) in self.src[start..end].split('\n').enumerate() {
+            if i > 0 {
+                snippet.push('\n');
+                let indent = line.len() - line.trim_start().len();
+                snippet.push_str(&line[indent.min(column)..]);
+            } else {
+                snippet.push_str(line);
+            }
+        }
+        snippet
+    }
+
+    pub fn parse_expr(&self, trees: &[TokenTree]) -> Expr {
+        match self.parse_prefix(trees) {
+            Some((expr, consumed)) if consumed == trees.len() => expr,
+            _ => Expr::Verbatim(self.snippet(trees)),
+        }
+    }
+
+    fn parse_prefix(&self, trees: &[TokenTree]) -> Option<(Expr, usize)> {
+        let first = trees.first()?;
+        if first.is("move") || first.is("|") || first.is("||") {
+            return self
+                .parse_closure(trees)
+                .map(|closure| (closure, trees.len()));
+        }
+        for op in ["&", "*", "!", "-"] {
+            if first.is(op) {
+                let mut op = op.to_owned();
+                let mut i = 1;
+                if op == "&" && trees.get(1).is_some_and(|tree| tree.is("mut")) {
+                    op.push_str("mut ");
+                    i += 1;
+                }
+                let (expr, consumed) = self.parse_prefix(&trees[i..])?;
+                let expr = Box::new(expr);
+                return Some((Expr::Unary { op, expr }, i + consumed));
+            }
+        }
+        let (mut expr, mut i) = self.parse_primary(trees)?;
+        loop {
+            let Some(tree) = trees.get(i) else {
+                return Some((expr, i));
+            };
+            if tree.is("?") {
+                expr = Expr::Try(Box::new(expr));
+                i += 1;
+            } else if tree.is(".") {
+                let member = trees.get(i + 1)?;
+                if member.is("await") {
+                    expr = Expr::Await(Box::new(expr));
+                    i += 2;
+                    continue;
+                }
+                let member_token = member.first();
+                if !matches!(member_token.kind, Kind::Ident | Kind::Literal) {
+                    return None;
+                }
+                let name = member_token.text.to_owned();
+                i += 2;
+                let mut turbofish = String::new();
+                if trees.get(i).is_some_and(|tree| tree.is("::")) {
+                    let end = angle_end(trees, i + 1)?;
+                    turbofish = self.snippet(&trees[i..end]);
+                    i = end;
+                }
+                if let Some(group) = trees.get(i).and_then(|tree| tree.group("(")) {
+                    expr = Expr::MethodCall {
+                        receiver: Box::new(expr),
+                        method: name,
+                        turbofish,
+                        args: self.parse_args(group),
+                    };
+                    i += 1;
+                } else if turbofish.is_empty() {
+                    expr = Expr::Field {
+                        base: Box::new(expr),
+                        member: name,
+                    };
+                } else {
+                    return None;
+                }
+            } else if let Some(group) = tree.group("(") {
+                expr = Expr::Call {
+                    func: Box::new(expr),
+                    args: self.parse_args(group),
+                };
+                i += 1;
+            } else if tree.group("[").is_some() {
+                expr = Expr::Verbatim(self.snippet(&trees[..=i]));
+                i += 1;
+            } else {
+                return Some((expr, i));
+            }
+        }
+    }
+
+    fn parse_primary(&self, trees: &[TokenTree]) -> Option<(Expr, usize)> {
+        let first = &trees[0];
+        if let Some(group) = first.group("(") {
+            let expr = if group.trees.is_empty() || split_top_level(&group.trees, ",").len() > 1 {
+                Expr::Verbatim(self.snippet(&trees[..1]))
+            } else {
+                Expr::Paren(Box::new(self.parse_expr(&group.trees)))
+            };
+            return Some((expr, 1));
+        }
+        if let Some(group) = first.group("{") {
+            let verbatim = self.snippet(&trees[..1]);
+            let stmts = self.parse_stmts(&group.trees);
+            return Some((Expr::Block(Block { stmts, verbatim }), 1));
+        }
+        if first.group("[").is_some() {
+            return Some((Expr::Verbatim(self.snippet(&trees[..1])), 1));
+        }
+        let token = first.first();
+        match token.kind {
+            Kind::Literal => return Some((Expr::Lit(token.text.to_owned()), 1)),
+            Kind::Ident if !EXPR_KEYWORDS.contains(&token.text) => {}
+            _ => return None,
+        }
+        let mut i = 1;
+        while trees.get(i).is_some_and(|tree| tree.is("::")) {
+            let next = trees.get(i + 1)?;
+            if next.is("<") {
+                i = angle_end(trees, i + 1)?;
+            } else {
+                next.ident()?;
+                i += 2;
+            }
+        }
+        if trees.get(i).is_some_and(|tree| tree.is("!")) {
+            trees
+                .get(i + 1)?
+                .first()
+                .text
+                .starts_with(['(', '[', '{'])
+                .then_some(())?;
+            return Some((Expr::Verbatim(self.snippet(&trees[..i + 2])), i + 2));
+        }
+        if trees.get(i).and_then(|tree| tree.group("{")).is_some() {
+            return Some((Expr::Verbatim(self.snippet(&trees[..=i])), i + 1));
+        }
+        Some((Expr::Path(self.snippet(&trees[..i])), i))
+    }
+
+    fn parse_closure(&self, trees: &[TokenTree]) -> Option<Expr> {
+        let is_move = trees[0].is("move");
+        let mut i = usize::from(is_move);
+        let mut params = Vec::new();
+        if trees.get(i)?.is("||") {
+            i += 1;
+        } else if trees.get(i)?.is("|") {
+            let close = i + 1 + trees[i + 1..].iter().position(|tree| tree.is("|"))?;
+            for param in split_top_level(&trees[i + 1..close], ",") {
+                if !param.is_empty() {
+                    params.push(self.snippet(param));
+                }
+            }
+            i = close + 1;
+        } else {
+            return None;
+        }
+        let body = Box::new(self.parse_expr(trees.get(i..).filter(|rest| !rest.is_empty())?));
+        Some(Expr::Closure {
+            is_move,
+            params,
+            body,
+        })
+    }
+
+    fn parse_args(&self, group: &Group) -> Vec<Expr> {
+        split_top_level(&group.trees, ",")
+            .into_iter()
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| self.parse_expr(arg))
+            .collect()
+    }
+
+    pub fn parse_block(&self, tree: &TokenTree) -> Option<Block> {
+        let group = tree.group("{")?;
+        Some(Block {
+            stmts: self.parse_stmts(&group.trees),
+            verbatim: self.snippet(std::slice::from_ref(tree)),
+        })
+    }
+
+    pub fn parse_stmts(&self, trees: &[TokenTree]) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        let mut i = 0;
+        while i < trees.len() {
+            if trees[i].is(";") {
+                i += 1;
+            } else if is_item_start(&trees[i..]) {
+                let end = item_end(trees, i);
+                stmts.push(Stmt::Verbatim(self.snippet(&trees[i..end])));
+                i = end;
+            } else if trees[i].is("let") {
+                let end = find_top_level(trees, i, ";").unwrap_or(trees.len());
+                stmts.push(self.parse_let(&trees[i..end]));
+                i = end + 1;
+            } else {
+                let end = expr_stmt_end(trees, i);
+                let semi = trees.get(end).is_some_and(|tree| tree.is(";"));
+                let expr = self.parse_expr(&trees[i..end]);
+                stmts.push(Stmt::Expr { expr, semi });
+                i = end + usize::from(semi);
+            }
+        }
+        stmts
+    }
+
+    fn parse_let(&self, trees: &[TokenTree]) -> Stmt {
+        let verbatim = || Stmt::Verbatim(format!("{};", self.snippet(trees)));
+        let decl = &trees[1..];
+        let eq = find_top_level(decl, 0, "=");
+        let (lhs, init) = match eq {
+            Some(eq) => (&decl[..eq], Some(&decl[eq + 1..])),
+            None => (decl, None),
+        };
+        if let Some(init) = init {
+            if init.is_empty() || find_top_level(init, 0, "else").is_some() {
+                return verbatim();
+            }
+        }
+        let (pat, ty) = match find_top_level(lhs, 0, ":") {
+            Some(colon) => (&lhs[..colon], Some(&lhs[colon + 1..])),
+            None => (lhs, None),
+        };
+        if pat.is_empty() {
+            return verbatim();
+        }
+        Stmt::Let {
+            pat: self.snippet(pat),
+            ty: ty.map(|ty| self.snippet(ty)),
+            init: init.map(|init| self.parse_expr(init)),
+        }
+    }
+}
+
+pub fn split_top_level<'t, 'a>(trees: &'t [TokenTree<'a>], sep: &str) -> Vec<&'t [TokenTree<'a>]> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut angle = 0;
+    for (i, tree) in trees.iter().enumerate() {
+        if tree.is("<") {
+            angle += 1;
+        } else if tree.is(">") && angle > 0 {
+            angle -= 1;
+        } else if tree.is(sep) && angle == 0 {
+            pieces.push(&trees[start..i]);
+            start = i + 1;
+        }
+    }
+    pieces.push(&trees[start..]);
+    pieces
+}
+
+// Index of the first occurrence of `text` at or after `i`, not nested inside
+// angle brackets.
+pub fn find_top_level(trees: &[TokenTree], i: usize, text: &str) -> Option<usize> {
+    let mut angle = 0;
+    for (j, tree) in trees.iter().enumerate().skip(i) {
+        if tree.is(text) && angle == 0 {
+            return Some(j);
+        } else if tree.is("<") {
+            angle += 1;
+        } else if tree.is(">") && angle > 0 {
+            angle -= 1;
+        }
+    }
+    None
+}
+
+// Given the index of a `<`, returns the index one past its matching `>`.
+pub fn angle_end(trees: &[TokenTree], i: usize) -> Option<usize> {
+    if !trees.get(i)?.is("<") {
+        return None;
+    }
+    let mut depth = 0;
+    for (j, tree) in trees.iter().enumerate().skip(i) {
+        if tree.is("<") {
+            depth += 1;
+        } else if tree.is(">") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(j + 1);
+            }
+        }
+    }
+    None
+}
+
+fn is_item_start(trees: &[TokenTree]) -> bool {
+    let mut i = 0;
+    while trees.get(i).is_some_and(|tree| tree.is("#")) {
+        i += 2;
+    }
+    let Some(ident) = trees.get(i).and_then(TokenTree::ident) else {
+        return i > 0;
+    };
+    let next = trees.get(i + 1);
+    ITEM_KEYWORDS.contains(&ident)
+        || (ident == "const" || ident == "static")
+            && next.is_some_and(|tree| tree.ident().is_some())
+        || (ident == "async" || ident == "unsafe") && next.is_some_and(|tree| tree.is("fn"))
+}
+
+fn item_end(trees: &[TokenTree], i: usize) -> usize {
+    let mut start = i;
+    while trees
+        .get(start)
+        .is_some_and(|tree| tree.is("#") || tree.is("pub"))
+    {
+        start += if trees[start].is("pub") { 1 } else { 2 };
+    }
+    let braced = !["use", "type", "const", "static"]
+        .iter()
+        .any(|keyword| trees.get(start).is_some_and(|tree| tree.is(keyword)));
+    for (j, tree) in trees.iter().enumerate().skip(i) {
+        if tree.is(";") || braced && tree.group("{").is_some() {
+            return j + 1;
+        }
+    }
+    trees.len()
+}
+
+fn expr_stmt_end(trees: &[TokenTree], i: usize) -> usize {
+    let first = &trees[i];
+    let block_end = if first.group("{").is_some() {
+        Some(i + 1)
+    } else if first.is("if") {
+        let mut end = next_brace(trees, i);
+        while let Some(j) = end.filter(|&j| trees.get(j).is_some_and(|tree| tree.is("else"))) {
+            end = next_brace(trees, j);
+        }
+        end
+    } else if ["match", "while", "for", "loop", "unsafe"]
+        .iter()
+        .any(|keyword| first.is(keyword))
+    {
+        next_brace(trees, i)
+    } else {
+        None
+    };
+    if let Some(end) = block_end {
+        if !trees
+            .get(end)
+            .is_some_and(|tree| tree.is(".") || tree.is("?"))
+        {
+            return end;
+        }
+    }
+    find_top_level(trees, i, ";").unwrap_or(trees.len())
+}
+
+fn next_brace(trees: &[TokenTree], i: usize) -> Option<usize> {
+    trees
+        .iter()
+        .skip(i)
+        .position(|tree| tree.group("{").is_some())
+        .map(|j| i + j + 1)
+}
+
+pub fn print_stmts(stmts: &[Stmt], indent: usize, out: &mut String) {
+    for stmt in stmts {
+        out.push_str(&" ".repeat(indent));
+        print_stmt(stmt, indent, out);
+        out.push('\n');
+    }
+}
+
+fn print_stmt(stmt: &Stmt, indent: usize, out: &mut String) {
+    match stmt {
+        Stmt::Let { pat, ty, init } => {
+            out.push_str("let ");
+            out.push_str(pat);
+            if let Some(ty) = ty {
+                out.push_str(": ");
+                out.push_str(ty);
+            }
+            if let Some(init) = init {
+                out.push_str(" = ");
+                print_expr(init, indent, out);
+            }
+            out.push(';');
+        }
+        Stmt::Expr { expr, semi } => {
+            print_expr(expr, indent, out);
+            if *semi {
+                out.push(';');
+            }
+        }
+        Stmt::Verbatim(text) => print_verbatim(text, indent, out),
+    }
+}
+
+pub fn print_expr(expr: &Expr, indent: usize, out: &mut String) {
+    match expr {
+        Expr::Path(text) | Expr::Lit(text) => out.push_str(text),
+        Expr::Verbatim(text) => print_verbatim(text, indent, out),
+        Expr::Call { func, args } => {
+            print_expr(func, indent, out);
+            print_args(args, indent, out);
+        }
+        Expr::MethodCall {
+            receiver,
+            method,
+            turbofish,
+            args,
+        } => {
+            print_expr(receiver, indent, out);
+            let _ = write!(out, ".{method}{turbofish}");
+            print_args(args, indent, out);
+        }
+        Expr::Field { base, member } => {
+            print_expr(base, indent, out);
+            out.push('.');
+            out.push_str(member);
+        }
+        Expr::Closure {
+            is_move,
+            params,
+            body,
+        } => {
+            if *is_move {
+                out.push_str("move ");
+            }
+            let _ = write!(out, "|{}| ", params.join(", "));
+            print_expr(body, indent, out);
+        }
+        Expr::Block(block) => {
+            if block.stmts.is_empty() {
+                print_verbatim(&block.verbatim, indent, out);
+            } else {
+                out.push_str("{\n");
+                print_stmts(&block.stmts, indent + 4, out);
+                out.push_str(&" ".repeat(indent));
+                out.push('}');
+            }
+        }
+        Expr::Try(expr) => {
+            print_expr(expr, indent, out);
+            out.push('?');
+        }
+        Expr::Await(expr) => {
+            print_expr(expr, indent, out);
+            out.push_str(".await");
+        }
+        Expr::Unary { op, expr } => {
+            out.push_str(op);
+            print_expr(expr, indent, out);
+        }
+        Expr::Paren(expr) => {
+            out.push('(');
+            print_expr(expr, indent, out);
+            out.push(')');
+        }
+    }
+}
+
+fn print_args(args: &[Expr], indent: usize, out: &mut String) {
+    out.push('(');
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        print_expr(arg, indent, out);
+    }
+    out.push(')');
+}
+
+pub fn print_verbatim(text: &str, indent: usize, out: &mut String) {
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+            if !line.is_empty() {
+                out.push_str(&" ".repeat(indent));
+            }
+        }
+        out.push_str(line);
+    }
+}
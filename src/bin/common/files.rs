@@ -0,0 +1,30 @@
+// Finding the Rust source files to scan, for the tools in src/bin that take
+// paths on the command line.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Appends `path` if it is a file, or else every .rs file under it in sorted
+// order, skipping hidden directories and build output in `target`.
+pub fn collect(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if entry.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                collect(&entry, files)?;
+            }
+        } else if name.ends_with(".rs") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,325 @@
+// A tokenizer for Rust source that is just precise enough for the tools in
+// src/bin: it understands comments, string and char literals, lifetimes, and
+// groups tokens into delimited trees, but does not attempt to parse anything.
+//
+// Each binary that includes this module uses a different subset of it.
+
+#![allow(dead_code)]
+
+use std::fmt::{self, Display};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Ident,
+    Lifetime,
+    Literal,
+    Punct,
+    LineComment,
+    BlockComment,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Token<'a> {
+    pub kind: Kind,
+    pub text: &'a str,
+    pub offset: usize,
+    pub line: usize,
+}
+
+pub enum TokenTree<'a> {
+    Token(Token<'a>),
+    Group(Group<'a>),
+}
+
+pub struct Group<'a> {
+    pub open: Token<'a>,
+    pub close: Token<'a>,
+    pub trees: Vec<TokenTree<'a>>,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line: usize,
+    pub msg: &'static str,
+}
+
+impl Token<'_> {
+    pub fn is(&self, text: &str) -> bool {
+        self.kind != Kind::Literal && self.text == text
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind, Kind::LineComment | Kind::BlockComment)
+    }
+}
+
+impl<'a> TokenTree<'a> {
+    pub fn first(&self) -> &Token<'a> {
+        match self {
+            TokenTree::Token(token) => token,
+            TokenTree::Group(group) => &group.open,
+        }
+    }
+
+    pub fn last(&self) -> &Token<'a> {
+        match self {
+            TokenTree::Token(token) => token,
+            TokenTree::Group(group) => &group.close,
+        }
+    }
+
+    pub fn is(&self, text: &str) -> bool {
+        match self {
+            TokenTree::Token(token) => token.is(text),
+            TokenTree::Group(_) => false,
+        }
+    }
+
+    pub fn group(&self, delim: &str) -> Option<&Group<'a>> {
+        match self {
+            TokenTree::Group(group) if group.open.text == delim => Some(group),
+            _ => None,
+        }
+    }
+
+    pub fn ident(&self) -> Option<&'a str> {
+        match self {
+            TokenTree::Token(token) if token.kind == Kind::Ident => Some(token.text),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "line {}: {}", self.line, self.msg)
+    }
+}
+
+const PUNCTS: &[&str] = &[
+    "...", "..=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "..", "+=", "-=", "*=",
+    "/=", "%=", "^=", "&=", "|=",
+];
+
+pub fn tokenize(src: &str) -> Result<Vec<Token<'_>>, Error> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    if src.starts_with("#!") && !src.starts_with("#![") {
+        i = src.find('\n').unwrap_or(src.len());
+    }
+
+    while i < bytes.len() {
+        let start = i;
+        let start_line = line;
+        let b = bytes[i];
+        let kind = if b == b'\n' {
+            line += 1;
+            i += 1;
+            continue;
+        } else if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        } else if src[i..].starts_with("//") {
+            i = src[i..].find('\n').map_or(src.len(), |n| i + n);
+            Kind::LineComment
+        } else if src[i..].starts_with("/*") {
+            let mut depth = 0;
+            loop {
+                if i >= bytes.len() {
+                    return Err(error(start_line, "unterminated block comment"));
+                } else if src[i..].starts_with("/*") {
+                    depth += 1;
+                    i += 2;
+                } else if src[i..].starts_with("*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    line += usize::from(bytes[i] == b'\n');
+                    i += 1;
+                }
+            }
+            Kind::BlockComment
+        } else if let Some(end) = raw_string(bytes, i) {
+            line += src[i..end].matches('\n').count();
+            i = end;
+            Kind::Literal
+        } else if b == b'"' || (matches!(b, b'b' | b'c') && bytes.get(i + 1) == Some(&b'"')) {
+            i += if b == b'"' { 1 } else { 2 };
+            loop {
+                match bytes.get(i) {
+                    None => return Err(error(start_line, "unterminated string literal")),
+                    Some(b'\\') => i += 2,
+                    Some(b'"') => break,
+                    Some(b'\n') => {
+                        line += 1;
+                        i += 1;
+                    }
+                    Some(_) => i += 1,
+                }
+            }
+            i += 1;
+            i = suffix(bytes, i);
+            Kind::Literal
+        } else if b == b'\'' || (b == b'b' && bytes.get(i + 1) == Some(&b'\'')) {
+            i += if b == b'\'' { 1 } else { 2 };
+            if let Some(end) = char_literal(src, i) {
+                i = suffix(bytes, end);
+                Kind::Literal
+            } else if b == b'\'' && bytes.get(i).is_some_and(|&b| is_ident_start(b)) {
+                i = ident_end(bytes, i);
+                Kind::Lifetime
+            } else {
+                return Err(error(start_line, "unterminated character literal"));
+            }
+        } else if b.is_ascii_digit() {
+            i = ident_end(bytes, i);
+            if bytes.get(i) == Some(&b'.')
+                && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+                && !src[start..i].starts_with("0x")
+            {
+                i = ident_end(bytes, i + 1);
+            }
+            if matches!(bytes[i - 1], b'e' | b'E') && matches!(bytes.get(i), Some(b'+' | b'-')) {
+                i = ident_end(bytes, i + 1);
+            }
+            Kind::Literal
+        } else if is_ident_start(b) {
+            if src[i..].starts_with("r#") {
+                i += 2;
+            }
+            i = ident_end(bytes, i);
+            Kind::Ident
+        } else {
+            i += PUNCTS
+                .iter()
+                .find(|punct| src[i..].starts_with(**punct))
+                .map_or_else(|| src[i..].chars().next().unwrap().len_utf8(), |p| p.len());
+            Kind::Punct
+        };
+        tokens.push(Token {
+            kind,
+            text: &src[start..i],
+            offset: start,
+            line: start_line,
+        });
+    }
+
+    Ok(tokens)
+}
+
+pub fn trees<'a>(tokens: &[Token<'a>]) -> Result<Vec<TokenTree<'a>>, Error> {
+    let mut stack: Vec<(Token, Vec<TokenTree>)> = Vec::new();
+    let mut current = Vec::new();
+
+    for token in tokens.iter().filter(|token| !token.is_comment()) {
+        if token.kind != Kind::Punct {
+            current.push(TokenTree::Token(*token));
+            continue;
+        }
+        match token.text {
+            "(" | "[" | "{" => stack.push((*token, std::mem::take(&mut current))),
+            ")" | "]" | "}" => {
+                let Some((open, outer)) = stack.pop() else {
+                    return Err(error(token.line, "unmatched closing delimiter"));
+                };
+                let expected = match open.text {
+                    "(" => ")",
+                    "[" => "]",
+                    _ => "}",
+                };
+                if token.text != expected {
+                    return Err(error(token.line, "mismatched closing delimiter"));
+                }
+                let trees = std::mem::replace(&mut current, outer);
+                current.push(TokenTree::Group(Group {
+                    open,
+                    close: *token,
+                    trees,
+                }));
+            }
+            _ => current.push(TokenTree::Token(*token)),
+        }
+    }
+
+    match stack.pop() {
+        Some((open, _)) => Err(error(open.line, "unclosed delimiter")),
+        None => Ok(current),
+    }
+}
+
+fn error(line: usize, msg: &'static str) -> Error {
+    Error { line, msg }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+fn is_ident_continue(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+fn ident_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && is_ident_continue(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+fn suffix(bytes: &[u8], i: usize) -> usize {
+    if i < bytes.len() && is_ident_start(bytes[i]) {
+        ident_end(bytes, i)
+    } else {
+        i
+    }
+}
+
+// Returns the index one past the closing quote, given the index just past the
+// opening quote, if this is a character literal rather than a lifetime.
+fn char_literal(src: &str, i: usize) -> Option<usize> {
+    let rest = &src[i..];
+    let mut chars = rest.char_indices();
+    let len = match chars.next()? {
+        (_, '\\') => {
+            let close = rest.get(2..)?.find('\'')?;
+            close + 2
+        }
+        (_, '\n' | '\'') => return None,
+        (_, ch) => ch.len_utf8(),
+    };
+    rest[len..].starts_with('\'').then_some(i + len + 1)
+}
+
+fn raw_string(bytes: &[u8], i: usize) -> Option<usize> {
+    let mut j = i;
+    if matches!(bytes[j], b'b' | b'c') {
+        j += 1;
+    }
+    if bytes.get(j) != Some(&b'r') {
+        return None;
+    }
+    j += 1;
+    let hashes = bytes[j..].iter().take_while(|&&b| b == b'#').count();
+    j += hashes;
+    if bytes.get(j) != Some(&b'"') {
+        return None;
+    }
+    j += 1;
+    while j < bytes.len() {
+        let close = j + 1 + hashes;
+        if bytes[j] == b'"'
+            && close <= bytes.len()
+            && bytes[j + 1..close].iter().all(|&b| b == b'#')
+        {
+            return Some(suffix(bytes, close));
+        }
+        j += 1;
+    }
+    None
+}
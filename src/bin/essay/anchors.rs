@@ -0,0 +1,48 @@
+// Heading anchors in the same form as rustdoc's, so that links to sections of
+// an essay's docs.rs page keep working on every other rendering of it.
+//
+// The essay tests include this module as well.
+
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+pub struct Anchors {
+    // How many headings have used each anchor so far.
+    ids: BTreeMap<String, usize>,
+}
+
+impl Anchors {
+    pub fn new() -> Self {
+        Anchors::default()
+    }
+
+    // Anchor for the next heading of a page: "comprehensible-error-handling",
+    // or "borrowing-1" for the second heading called "Borrowing".
+    pub fn next(&mut self, text: &str) -> String {
+        let slug = slug(text);
+        let count = self.ids.entry(slug.clone()).or_insert(0);
+        let id = if *count == 0 {
+            slug
+        } else {
+            format!("{slug}-{count}")
+        };
+        *count += 1;
+        id
+    }
+}
+
+// Rustdoc's rule for turning heading text into an anchor. Only ASCII letters
+// are lowercased; rustdoc leaves "Ärger" as "Ärger", not "ärger".
+pub fn slug(text: &str) -> String {
+    text.chars()
+        .filter_map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+                Some(ch.to_ascii_lowercase())
+            } else if ch.is_ascii_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+// Offline checks of the reference-style links of an essay: every reference
+// has a definition, or else is an intra-doc link that rustdoc resolves from
+// the link text, like `[Sync]`; every definition is used, and only once; and
+// every intra-doc target is a path to an item that rustdoc documents in core,
+// alloc, std or futures01.
+
+use crate::doc::{self, Kind};
+use crate::essays::Essay;
+use crate::links;
+use crate::resolve;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct Problem {
+    // Line of the essay's source file.
+    pub line: usize,
+    pub msg: String,
+}
+
+pub fn links(essay: &Essay) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    // The doc comment begins on the first line of the file.
+    let mut defined = BTreeMap::new();
+    for (i, line) in doc::lines(&essay.doc).iter().enumerate() {
+        if line.kind != Kind::Text {
+            continue;
+        }
+        let Some(def) = links::definition(line.text) else {
+            continue;
+        };
+        let line = i + 1;
+        let label = links::normalize(def.label);
+        if let Some((first, _)) = defined.get(&label) {
+            problems.push(Problem {
+                line,
+                msg: format!("`[{}]` is already defined on line {first}", def.label),
+            });
+            continue;
+        }
+        defined.insert(label, (line, def.label));
+        if !resolve::is_path(def.target) {
+            continue;
+        }
+        match resolve::resolve(def.target) {
+            Ok(Some(_)) => {}
+            Ok(None) => problems.push(Problem {
+                line,
+                msg: format!(
+                    "`{}` is not an item documented in core, alloc, std or futures01",
+                    def.target,
+                ),
+            }),
+            Err(err) => problems.push(Problem {
+                line,
+                msg: format!("cannot resolve `{}`: {err}", def.target),
+            }),
+        }
+    }
+
+    let mut used = BTreeSet::new();
+    for reference in links::references(&essay.doc) {
+        let label = links::normalize(&reference.label);
+        if defined.contains_key(&label) {
+            used.insert(label);
+            continue;
+        }
+        match resolve::resolve(&reference.label) {
+            Ok(Some(_)) => {}
+            Ok(None) => problems.push(Problem {
+                line: reference.line,
+                msg: format!("`[{}]` is used but never defined", reference.label),
+            }),
+            Err(err) => problems.push(Problem {
+                line: reference.line,
+                msg: format!("cannot resolve `[{}]`: {err}", reference.label),
+            }),
+        }
+    }
+
+    for (normalized, &(line, label)) in &defined {
+        if !used.contains(normalized) {
+            problems.push(Problem {
+                line,
+                msg: format!("`[{label}]` is defined but never used"),
+            });
+        }
+    }
+
+    problems.sort_by_key(|problem| problem.line);
+    problems
+}
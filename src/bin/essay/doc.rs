@@ -0,0 +1,214 @@
+// The rustdoc-flavored markdown of an essay, and its translation into plain
+// CommonMark that other exporters build on.
+
+use crate::essays::{Error, Essay};
+use crate::links;
+use crate::resolve;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Text,
+    // The ``` line that opens a code block.
+    Open,
+    Code,
+    // The ``` line that closes a code block.
+    Close,
+}
+
+#[derive(Copy, Clone)]
+pub struct Line<'a> {
+    pub kind: Kind,
+    pub text: &'a str,
+    // For code and fences, whether rustdoc treats the block as Rust.
+    pub rust: bool,
+}
+
+// Tags in a code block's info string that rustdoc understands as applying to
+// a Rust code block.
+const RUSTDOC_ATTRS: &[&str] = &[
+    "rust",
+    "ignore",
+    "should_panic",
+    "no_run",
+    "compile_fail",
+    "test_harness",
+    "standalone_crate",
+];
+
+// Classifies every line of the doc comment as prose or part of a code block.
+pub fn lines(doc: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<(&str, bool)> = None;
+    for text in doc.lines() {
+        let trimmed = text.trim_start();
+        let line = match fence {
+            None => match fence_marker(trimmed) {
+                Some(marker) => {
+                    let rust = is_rust(&trimmed[marker.len()..]);
+                    fence = Some((marker, rust));
+                    Line {
+                        kind: Kind::Open,
+                        text,
+                        rust,
+                    }
+                }
+                None => Line {
+                    kind: Kind::Text,
+                    text,
+                    rust: false,
+                },
+            },
+            Some((marker, rust)) => {
+                let close = fence_marker(trimmed).is_some_and(|close| {
+                    close.starts_with(marker) && trimmed[close.len()..].trim().is_empty()
+                });
+                if close {
+                    fence = None;
+                }
+                Line {
+                    kind: if close { Kind::Close } else { Kind::Code },
+                    text,
+                    rust,
+                }
+            }
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+// The run of backticks or tildes at the start of a fence line.
+fn fence_marker(trimmed: &str) -> Option<&str> {
+    let ch = trimmed
+        .chars()
+        .next()
+        .filter(|&ch| ch == '`' || ch == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(ch).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+// Whether rustdoc compiles a code block with this info string as Rust. An
+// empty info string means Rust; so does any combination of rustdoc's own
+// attributes, edition tags and error codes.
+pub fn is_rust(info: &str) -> bool {
+    info.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .all(|tag| {
+            RUSTDOC_ATTRS.contains(&tag)
+                || tag.starts_with("edition")
+                || tag.starts_with("ignore-")
+                || tag.len() == 5
+                    && tag.starts_with('E')
+                    && tag[1..].bytes().all(|byte| byte.is_ascii_digit())
+        })
+}
+
+// How a line of a Rust code block is shown to the reader, or None if it is
+// hidden with a leading `#`. A leading `##` shows as a single `#`.
+pub fn visible(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    if trimmed == "#" || trimmed.starts_with("# ") || trimmed.starts_with("#\t") {
+        None
+    } else if let Some(rest) = trimmed.strip_prefix("##") {
+        Some(format!("{indent}#{rest}"))
+    } else {
+        Some(line.to_owned())
+    }
+}
+
+// The essay as self-contained CommonMark: hidden lines of code blocks are
+// dropped, rustdoc's code block attributes become a plain `rust` tag,
+// intra-doc links point to their URL, and the HTML used only for spacing on
+// docs.rs is removed. Fails rather than leave a path where a URL belongs if
+// the documentation that intra-doc links point into is not available.
+pub fn markdown(essay: &Essay) -> Result<String, Error> {
+    let resolve = |target: &str| {
+        resolve::resolve(target).map_err(|err| Error {
+            path: essay.path(),
+            msg: format!("cannot resolve `{target}`: {err}"),
+        })
+    };
+    let mut out = String::new();
+    let mut byline = true;
+    for line in lines(&essay.doc) {
+        let text = match line.kind {
+            Kind::Open if line.rust => {
+                let trimmed = line.text.trim_start();
+                let indent = &line.text[..line.text.len() - trimmed.len()];
+                let marker = fence_marker(trimmed).unwrap_or("```");
+                format!("{indent}{marker}rust")
+            }
+            Kind::Code if line.rust => match visible(line.text) {
+                Some(text) => text,
+                None => continue,
+            },
+            Kind::Open | Kind::Code | Kind::Close => line.text.to_owned(),
+            Kind::Text => {
+                let trimmed = line.text.trim();
+                if byline && trimmed.starts_with("<sup>") {
+                    byline = false;
+                    clean_byline(trimmed)
+                } else if trimmed == "<br>" {
+                    String::new()
+                } else if trimmed.starts_with("<br><br>") {
+                    // A longer pause, as before a closing section.
+                    if !out.ends_with("\n\n") {
+                        out.push('\n');
+                    }
+                    "---".to_owned()
+                } else if let Some(def) = links::definition(line.text) {
+                    match resolve(def.target)? {
+                        Some(url) => format!("[{}]: {}", def.label, url),
+                        None => line.text.to_owned(),
+                    }
+                } else {
+                    line.text.to_owned()
+                }
+            }
+        };
+        let blank = line.kind == Kind::Text && text.trim().is_empty();
+        if blank && (out.is_empty() || out.ends_with("\n\n")) {
+            continue;
+        }
+        out.push_str(text.trim_end());
+        out.push('\n');
+    }
+
+    // Shortcut links like [Sync] that rustdoc resolves from the link text.
+    let defined: BTreeSet<String> = links::definitions(&essay.doc)
+        .iter()
+        .map(|def| links::normalize(def.label))
+        .collect();
+    let mut implicit = BTreeSet::new();
+    let mut separated = false;
+    for reference in links::references(&essay.doc) {
+        let label = links::normalize(&reference.label);
+        if defined.contains(&label) || !implicit.insert(label) {
+            continue;
+        }
+        if let Some(url) = resolve(&reference.label)? {
+            if !separated && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            separated = true;
+            let _ = writeln!(out, "[{}]: {}", reference.label, url);
+        }
+    }
+
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    Ok(out)
+}
+
+// `<sup>*by [Author]&#8202;,&ensp;2019.08.08*</sup>` into
+// `*by [Author], 2019.08.08*`.
+fn clean_byline(line: &str) -> String {
+    line.trim_start_matches("<sup>")
+        .trim_end_matches("</sup>")
+        .replace("&#8202;", "")
+        .replace("&ensp;", " ")
+}
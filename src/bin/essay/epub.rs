@@ -0,0 +1,172 @@
+// An EPUB 3 book of every essay, one chapter each, for e-readers.
+
+use crate::doc;
+use crate::essays::{Error, Essay};
+use crate::html::{self, Renderer};
+use crate::markdown;
+use crate::zip;
+use std::fmt::Write as _;
+
+pub struct Book {
+    pub title: String,
+    pub authors: Vec<String>,
+    // A URL that uniquely identifies this edition of the book.
+    pub identifier: String,
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+// E-readers apply their own fonts and margins, so this only keeps code
+// readable.
+const STYLE: &str = "\
+pre {
+  font-family: monospace;
+  font-size: 0.8em;
+  white-space: pre-wrap;
+  background: #f5f5f5;
+  padding: 0.5em;
+}
+code {
+  font-family: monospace;
+}
+blockquote {
+  margin-left: 1em;
+  font-style: italic;
+}
+.cover {
+  text-align: center;
+  margin-top: 30%;
+}
+.rust .kw { color: #8959a8; }
+.rust .string { color: #718c00; }
+.rust .number { color: #f5871f; }
+.rust .comment { color: #8e908c; }
+";
+
+impl Book {
+    // The .epub file.
+    pub fn package(&self, essays: &[Essay]) -> Result<Vec<u8>, Error> {
+        let mut zip = zip::Writer::new();
+        // Must come first, and uncompressed, for readers to detect the format.
+        zip.file("mimetype", b"application/epub+zip");
+        zip.file("META-INF/container.xml", CONTAINER.as_bytes());
+        zip.file("OEBPS/content.opf", self.opf(essays).as_bytes());
+        zip.file("OEBPS/nav.xhtml", self.nav(essays).as_bytes());
+        zip.file("OEBPS/cover.xhtml", self.cover().as_bytes());
+        zip.file("OEBPS/style.css", STYLE.as_bytes());
+        for essay in essays {
+            let name = format!("OEBPS/{}.xhtml", essay.stem());
+            zip.file(&name, chapter(essay)?.as_bytes());
+        }
+        Ok(zip.finish())
+    }
+
+    fn opf(&self, essays: &[Essay]) -> String {
+        // The book changes only when an essay is added, so the newest essay's
+        // date doubles as the modification time and keeps the output
+        // reproducible.
+        let date = essays.last().map_or("1970-01-01", |essay| &essay.date);
+        let mut opf = String::new();
+        opf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        opf.push_str("<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\" xml:lang=\"en\">\n");
+        opf.push_str("<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+        let _ = writeln!(
+            opf,
+            "<dc:identifier id=\"id\">{}</dc:identifier>",
+            html::escape(&self.identifier)
+        );
+        let _ = writeln!(opf, "<dc:title>{}</dc:title>", html::escape(&self.title));
+        for (i, author) in self.authors.iter().enumerate() {
+            let _ = writeln!(
+                opf,
+                "<dc:creator id=\"creator{i}\">{}</dc:creator>",
+                html::escape(author)
+            );
+            let _ = writeln!(opf, "<meta refines=\"#creator{i}\" property=\"role\" scheme=\"marc:relators\">aut</meta>");
+        }
+        opf.push_str("<dc:language>en</dc:language>\n");
+        let _ = writeln!(opf, "<dc:date>{date}</dc:date>");
+        let _ = writeln!(
+            opf,
+            "<meta property=\"dcterms:modified\">{date}T00:00:00Z</meta>"
+        );
+        opf.push_str("</metadata>\n<manifest>\n");
+        opf.push_str("<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n");
+        opf.push_str(
+            "<item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+        );
+        opf.push_str("<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n");
+        for (i, essay) in essays.iter().enumerate() {
+            let _ = writeln!(
+                opf,
+                "<item id=\"chapter{}\" href=\"{}.xhtml\" media-type=\"application/xhtml+xml\"/>",
+                i + 1,
+                essay.stem(),
+            );
+        }
+        opf.push_str("</manifest>\n<spine>\n");
+        opf.push_str("<itemref idref=\"cover\"/>\n<itemref idref=\"nav\"/>\n");
+        for i in 1..=essays.len() {
+            let _ = writeln!(opf, "<itemref idref=\"chapter{i}\"/>");
+        }
+        opf.push_str("</spine>\n</package>\n");
+        opf
+    }
+
+    fn nav(&self, essays: &[Essay]) -> String {
+        let mut body =
+            String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+        for essay in essays {
+            let _ = writeln!(
+                body,
+                "<li><a href=\"{}.xhtml\">{}</a></li>",
+                essay.stem(),
+                html::escape(&essay.title),
+            );
+        }
+        body.push_str("</ol>\n</nav>\n");
+        xhtml(&self.title, &body)
+    }
+
+    fn cover(&self) -> String {
+        let body = format!(
+            "<section epub:type=\"cover\" class=\"cover\">\n<h1>{}</h1>\n<p>{}</p>\n</section>\n",
+            html::escape(&self.title),
+            html::escape(&self.authors.join(", ")),
+        );
+        xhtml(&self.title, &body)
+    }
+}
+
+fn chapter(essay: &Essay) -> Result<String, Error> {
+    let blocks = markdown::parse(&doc::markdown(essay)?);
+    let body = format!(
+        "<section epub:type=\"chapter\">\n{}</section>\n",
+        Renderer::new().blocks(&blocks),
+    );
+    Ok(xhtml(&essay.title, &body))
+}
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"en\" xml:lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\"/>\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n\
+         </head>\n\
+         <body>\n\
+         {body}\
+         </body>\n\
+         </html>\n",
+        html::escape(title),
+    )
+}
@@ -0,0 +1,369 @@
+// Finds the essays registered in src/lib.rs and pulls each one apart into its
+// doc comment and the metadata macro that follows it.
+//
+// The essay tests include this module as well and use only part of it.
+
+#![allow(dead_code)]
+
+use dtolnay::triage_scale::{notifications, render};
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct Essay {
+    // The NN of `mod _NN;` in src/lib.rs.
+    pub number: usize,
+    // File name without the date or extension, as in "await-a-minute".
+    pub slug: String,
+    // From the file name, as in "2019-08-08".
+    pub date: String,
+    // From the metadata macro, as in "August 8, 2019".
+    pub published: String,
+    pub author: String,
+    // From the `# ` heading on the first line.
+    pub title: String,
+    // Text of the `/** ... */` comment, as rustdoc sees it.
+    pub doc: String,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub path: PathBuf,
+    pub msg: String,
+}
+
+impl Essay {
+    // File name without extension, as in "2019-08-08-await-a-minute".
+    pub fn stem(&self) -> String {
+        format!("{}-{}", self.date, self.slug)
+    }
+
+    // Source file relative to the crate root.
+    pub fn path(&self) -> PathBuf {
+        Path::new("src").join(format!("{}.rs", self.stem()))
+    }
+
+    // Name of the `macro_rules!` that carries the metadata.
+    pub fn macro_name(&self) -> String {
+        macro_name(self.number, &self.slug)
+    }
+}
+
+pub fn macro_name(number: usize, slug: &str) -> String {
+    format!("_{:02}__{}", number, slug.replace('-', "_"))
+}
+
+// Essays in date order.
+pub fn load(root: &Path) -> Result<Vec<Essay>, Error> {
+    let lib = root.join("src").join("lib.rs");
+    let src = read(&lib)?;
+    let mut essays = Vec::new();
+    for (path, module) in modules(&src) {
+        let Some(number) = module.strip_prefix('_').and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        let path = root.join("src").join(path);
+        let src = read(&path)?;
+        let essay = parse(number, &path, &src).map_err(|msg| Error {
+            path: path.clone(),
+            msg,
+        })?;
+        essays.push(essay);
+    }
+    essays.sort_by(|a, b| (&a.date, a.number).cmp(&(&b.date, b.number)));
+    Ok(essays)
+}
+
+// The crate-level doc comment of src/lib.rs, as in "Essays by David Tolnay".
+pub fn crate_doc(root: &Path) -> Result<String, Error> {
+    let src = read(&root.join("src").join("lib.rs"))?;
+    let lines: Vec<&str> = src
+        .lines()
+        .filter_map(|line| line.strip_prefix("//!"))
+        .map(str::trim)
+        .collect();
+    Ok(lines.join(" ").trim().to_owned())
+}
+
+// The fields of Cargo.toml that describe the crate as a publication.
+pub struct Manifest {
+    // Author names, without their email addresses.
+    pub authors: Vec<String>,
+    pub documentation: String,
+}
+
+pub fn manifest(root: &Path) -> Result<Manifest, Error> {
+    let path = root.join("Cargo.toml");
+    let src = read(&path)?;
+    let field = |name: &str| {
+        src.lines()
+            .find_map(|line| line.strip_prefix(name)?.trim_start().strip_prefix('='))
+            .ok_or_else(|| Error {
+                path: path.clone(),
+                msg: format!("no `{name}` in the manifest"),
+            })
+    };
+    let strings = |value: &str| -> Vec<String> {
+        value
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(str::to_owned)
+            .collect()
+    };
+    Ok(Manifest {
+        authors: strings(field("authors")?)
+            .iter()
+            .map(|author| {
+                author
+                    .split('<')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned()
+            })
+            .collect(),
+        documentation: strings(field("documentation")?).concat(),
+    })
+}
+
+// Every `#[path = "..."] mod name;` in the crate root, in order.
+pub fn modules(lib: &str) -> Vec<(&str, &str)> {
+    let mut modules = Vec::new();
+    let mut path = None;
+    for line in lib.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("#[path = \"") {
+            path = rest.strip_suffix("\"]");
+        } else if let Some(path) = path.take() {
+            let module = line.strip_prefix("pub ").unwrap_or(line);
+            if let Some(name) = module
+                .strip_prefix("mod ")
+                .and_then(|m| m.strip_suffix(';'))
+            {
+                modules.push((path, name));
+            }
+        }
+    }
+    modules
+}
+
+pub fn parse(number: usize, path: &Path, src: &str) -> Result<Essay, String> {
+    let file_name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or("file name is not UTF-8")?;
+    let (date, slug) =
+        split_file_name(file_name).ok_or("file name is not of the form YYYY-MM-DD-slug.rs")?;
+
+    let (doc, rest) = doc_comment(src)?;
+    let title = doc
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# "))
+        .ok_or("essay does not begin with a `# ` title")?
+        .trim()
+        .to_owned();
+
+    let mut essay = Essay {
+        number,
+        slug: slug.to_owned(),
+        date: date.to_owned(),
+        published: String::new(),
+        author: String::new(),
+        title,
+        doc: doc.to_owned(),
+    };
+    let name = essay.macro_name();
+    if !rest.contains(&format!("macro_rules! {name} ")) {
+        return Err(format!("expected `macro_rules! {name}` after the essay"));
+    }
+    essay.published = field(rest, "date")?;
+    essay.author = field(rest, "author")?;
+    Ok(essay)
+}
+
+// Adds an essay's module to the end of the essay modules in src/lib.rs,
+// numbered after the last one. Returns the number and the new source.
+pub fn register(lib: &str, file_name: &str) -> (usize, String) {
+    let number = modules(lib)
+        .iter()
+        .filter_map(|(_, module)| module.strip_prefix('_')?.parse::<usize>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let entry = format!("#[path = \"{file_name}\"]\nmod _{number:02};\n");
+    let lines: Vec<&str> = lib.lines().collect();
+    let last = lines.iter().rposition(|line| {
+        line.trim()
+            .strip_prefix("mod _")
+            .and_then(|rest| rest.strip_suffix(';'))
+            .is_some_and(|n| n.parse::<usize>().is_ok())
+    });
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        out.push_str(line);
+        out.push('\n');
+        if Some(i) == last {
+            out.push('\n');
+            out.push_str(&entry);
+        }
+    }
+    if last.is_none() {
+        out.push('\n');
+        out.push_str(&entry);
+    }
+    (number, out)
+}
+
+// Lowercase ASCII words joined by hyphens, as in "await-a-minute".
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if ch != '\'' && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+// The current date in UTC, as in "2019-08-08".
+pub fn today() -> String {
+    render::date(notifications::now())
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// "2019-08-08" in the form of the metadata macro, "August 8, 2019". Tells
+// apart text that is not a date at all from a date that does not exist.
+pub fn published(date: &str) -> Result<String, String> {
+    let malformed = || format!("date `{date}` is not of the form YYYY-MM-DD");
+    let (year, rest) = date.split_once('-').ok_or_else(malformed)?;
+    let (month, day) = rest.split_once('-').ok_or_else(malformed)?;
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    if !digits(year, 4) || !digits(month, 2) || !digits(day, 2) {
+        return Err(malformed());
+    }
+    let year: u32 = year.parse().map_err(|_| malformed())?;
+    let month: usize = month.parse().map_err(|_| malformed())?;
+    let day: usize = day.parse().map_err(|_| malformed())?;
+    let invalid = || format!("`{date}` is not a valid date");
+    let name = month
+        .checked_sub(1)
+        .and_then(|i| MONTHS.get(i))
+        .ok_or_else(invalid)?;
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if (1..=days).contains(&day) {
+        Ok(format!("{name} {day}, {year}"))
+    } else {
+        Err(invalid())
+    }
+}
+
+// "2019-08-08-await-a-minute" into ("2019-08-08", "await-a-minute").
+pub fn split_file_name(name: &str) -> Option<(&str, &str)> {
+    let date = name.get(..10)?;
+    let slug = name.get(10..)?.strip_prefix('-')?;
+    let digits = date.bytes().enumerate().all(|(i, byte)| match i {
+        4 | 7 => byte == b'-',
+        _ => byte.is_ascii_digit(),
+    });
+    if digits && !slug.is_empty() {
+        Some((date, slug))
+    } else {
+        None
+    }
+}
+
+// The text inside the leading `/** ... */`, and everything after it. Block
+// comments nest, so a `/* snip */` inside a code block does not end the essay.
+pub fn doc_comment(src: &str) -> Result<(&str, &str), String> {
+    let start = src.trim_start();
+    let body = start
+        .strip_prefix("/**")
+        .ok_or("file does not begin with a `/**` doc comment")?;
+    let end = comment_end(body).ok_or("doc comment is never closed by `*/`")?;
+    let doc = &body[..end];
+    let doc = doc.strip_prefix(' ').unwrap_or(doc);
+    Ok((doc, &body[end + 2..]))
+}
+
+// Offset of the `*/` that closes a block comment whose opening `/*` has
+// already been consumed.
+pub fn comment_end(body: &str) -> Option<usize> {
+    let bytes = body.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+// The string value of `name: "..."` in the metadata macro.
+fn field(rest: &str, name: &str) -> Result<String, String> {
+    let missing = || format!("metadata macro has no `{name}`");
+    let start = rest.find(&format!("{name}:")).ok_or_else(missing)?;
+    let value = rest[start + name.len() + 1..].trim_start();
+    let value = value.strip_prefix('"').ok_or_else(missing)?;
+    let end = value.find('"').ok_or_else(missing)?;
+    Ok(value[..end].to_owned())
+}
+
+pub fn read(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|err| Error::io(path, &err))
+}
+
+impl Error {
+    pub fn io(path: &Path, err: &io::Error) -> Self {
+        Error {
+            path: path.to_owned(),
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: {}", self.path.display(), self.msg)
+    }
+}
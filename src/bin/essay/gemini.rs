@@ -0,0 +1,121 @@
+// Gemtext for the Gemini protocol. Clients wrap text themselves, so each
+// paragraph is one line, and links can only appear on `=>` lines of their own,
+// so the links of each block are listed right after it.
+
+use crate::doc;
+use crate::essays::{Error, Essay};
+use crate::markdown::{self, Block, Inline};
+use std::fmt::Write as _;
+
+pub fn gemtext(essay: &Essay) -> Result<String, Error> {
+    let blocks = markdown::parse(&doc::markdown(essay)?);
+    let mut out = String::new();
+    for block in &blocks {
+        let mut targets = Vec::new();
+        let lines = lines(block, &mut targets);
+        if lines.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        for (url, label) in targets {
+            if label == url {
+                let _ = writeln!(out, "=> {url}");
+            } else {
+                let _ = writeln!(out, "=> {url} {label}");
+            }
+        }
+    }
+    Ok(out)
+}
+
+// The lines of a block, collecting its link targets as (url, label) pairs.
+fn lines(block: &Block, targets: &mut Vec<(String, String)>) -> Vec<String> {
+    match block {
+        // Gemtext has three levels of heading.
+        Block::Heading { level, content } => {
+            vec![format!(
+                "{} {}",
+                "#".repeat((*level).min(3)),
+                text(content, targets)
+            )]
+        }
+        Block::Paragraph(content) => vec![text(content, targets)],
+        Block::Code { lang, code } => {
+            let mut lines = vec![format!("```{lang}")];
+            lines.extend(code.lines().map(str::to_owned));
+            lines.push("```".to_owned());
+            lines
+        }
+        Block::Quote(blocks) => {
+            let mut lines = Vec::new();
+            for block in blocks {
+                if !lines.is_empty() {
+                    lines.push(">".to_owned());
+                }
+                for line in self::lines(block, targets) {
+                    lines.push(format!("> {line}"));
+                }
+            }
+            lines
+        }
+        // Gemtext list items are a single line with no nesting, so nested
+        // lists are flattened, and ordered lists keep their numbers as text.
+        Block::List(list) => {
+            let mut lines = Vec::new();
+            for (i, item) in list.items.iter().enumerate() {
+                let marker = match list.start {
+                    Some(start) => format!("{}. ", start + i),
+                    None => "* ".to_owned(),
+                };
+                let mut paragraphs = Vec::new();
+                let mut rest = Vec::new();
+                for block in item {
+                    match block {
+                        Block::Paragraph(content) => paragraphs.push(text(content, targets)),
+                        _ => rest.extend(self::lines(block, targets)),
+                    }
+                }
+                lines.push(marker + &paragraphs.join(" "));
+                lines.extend(rest);
+            }
+            lines
+        }
+        // Only meaningful to renderers that speak HTML.
+        Block::Html(_) => Vec::new(),
+        Block::Rule => vec!["---".to_owned()],
+    }
+}
+
+fn text(inlines: &[Inline], targets: &mut Vec<(String, String)>) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&text.replace('\n', " ")),
+            Inline::Code(code) => {
+                out.push('`');
+                out.push_str(code);
+                out.push('`');
+            }
+            Inline::Emphasis(content) | Inline::Strong(content) => {
+                out.push_str(&text(content, targets));
+            }
+            Inline::Link { url, content } => {
+                let label = text(content, targets);
+                // Sections of the same page, like the table of contents, have
+                // nowhere to go in gemtext.
+                if !url.starts_with('#') && !targets.iter().any(|(existing, _)| existing == url) {
+                    targets.push((url.clone(), label.clone()));
+                }
+                out.push_str(&label);
+            }
+            Inline::Html(_) => {}
+        }
+    }
+    out
+}
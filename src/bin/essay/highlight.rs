@@ -0,0 +1,50 @@
+// Syntax highlighting for Rust code blocks, as spans with a class per kind of
+// token. Code that the lexer cannot make sense of is shown unhighlighted.
+
+use crate::html::escape;
+use crate::lexer::{self, Kind};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+pub fn rust(code: &str) -> String {
+    let Ok(tokens) = lexer::tokenize(code) else {
+        return escape(code);
+    };
+    let mut out = String::new();
+    let mut pos = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        out.push_str(&escape(&code[pos..token.offset]));
+        let next = tokens.get(i + 1);
+        let class = match token.kind {
+            Kind::LineComment | Kind::BlockComment => Some("comment"),
+            Kind::Lifetime => Some("lifetime"),
+            Kind::Literal if token.text.starts_with(|ch: char| ch.is_ascii_digit()) => {
+                Some("number")
+            }
+            Kind::Literal => Some("string"),
+            Kind::Ident if KEYWORDS.contains(&token.text) => Some("kw"),
+            Kind::Ident if next.is_some_and(|next| next.is("!")) => Some("macro"),
+            Kind::Ident if token.text.starts_with(char::is_uppercase) => Some("type"),
+            Kind::Ident | Kind::Punct => None,
+        };
+        let text = escape(token.text);
+        match class {
+            Some(class) => {
+                out.push_str("<span class=\"");
+                out.push_str(class);
+                out.push_str("\">");
+                out.push_str(&text);
+                out.push_str("</span>");
+            }
+            None => out.push_str(&text),
+        }
+        pos = token.offset + token.text.len();
+    }
+    out.push_str(&escape(&code[pos..]));
+    out
+}
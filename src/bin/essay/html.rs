@@ -0,0 +1,149 @@
+// Renders parsed Markdown to HTML. The output is also well-formed XHTML, so
+// that the EPUB export can use it unchanged.
+
+use crate::anchors::Anchors;
+use crate::highlight;
+use crate::markdown::{Block, Inline, List};
+use std::fmt::Write as _;
+
+#[derive(Default)]
+pub struct Renderer {
+    anchors: Anchors,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer::default()
+    }
+
+    pub fn blocks(&mut self, blocks: &[Block]) -> String {
+        let mut out = String::new();
+        for block in blocks {
+            self.block(block, false, &mut out);
+        }
+        out
+    }
+
+    fn block(&mut self, block: &Block, tight: bool, out: &mut String) {
+        match block {
+            Block::Heading { level, content } => {
+                let id = self.anchors.next(&crate::markdown::plain_text(content));
+                let _ = writeln!(out, "<h{level} id=\"{id}\">{}</h{level}>", inlines(content));
+            }
+            Block::Paragraph(content) if tight => out.push_str(&inlines(content)),
+            Block::Paragraph(content) => {
+                let _ = writeln!(out, "<p>{}</p>", inlines(content));
+            }
+            Block::Code { lang, code } => {
+                if lang == "rust" {
+                    let _ = writeln!(
+                        out,
+                        "<pre class=\"rust\"><code>{}</code></pre>",
+                        highlight::rust(code),
+                    );
+                } else if lang.is_empty() {
+                    let _ = writeln!(out, "<pre><code>{}</code></pre>", escape(code));
+                } else {
+                    let _ = writeln!(
+                        out,
+                        "<pre class=\"{}\"><code>{}</code></pre>",
+                        escape(lang),
+                        escape(code),
+                    );
+                }
+            }
+            Block::Quote(blocks) => {
+                out.push_str("<blockquote>\n");
+                for block in blocks {
+                    self.block(block, false, out);
+                }
+                out.push_str("</blockquote>\n");
+            }
+            Block::List(list) => self.list(list, out),
+            Block::Html(html) => {
+                out.push_str(&xhtml(html));
+                out.push('\n');
+            }
+            Block::Rule => out.push_str("<hr />\n"),
+        }
+    }
+
+    fn list(&mut self, list: &List, out: &mut String) {
+        let tag = match list.start {
+            Some(1) => {
+                out.push_str("<ol>\n");
+                "ol"
+            }
+            Some(start) => {
+                let _ = writeln!(out, "<ol start=\"{start}\">");
+                "ol"
+            }
+            None => {
+                out.push_str("<ul>\n");
+                "ul"
+            }
+        };
+        for item in &list.items {
+            out.push_str("<li>");
+            for (i, block) in item.iter().enumerate() {
+                if !list.loose && i > 0 && matches!(item[i - 1], Block::Paragraph(_)) {
+                    out.push('\n');
+                }
+                self.block(block, !list.loose, out);
+            }
+            out.push_str("</li>\n");
+        }
+        let _ = writeln!(out, "</{tag}>");
+    }
+}
+
+pub fn inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape(text)),
+            Inline::Code(code) => {
+                let _ = write!(out, "<code>{}</code>", escape(code));
+            }
+            Inline::Emphasis(content) => {
+                let _ = write!(out, "<em>{}</em>", self::inlines(content));
+            }
+            Inline::Strong(content) => {
+                let _ = write!(out, "<strong>{}</strong>", self::inlines(content));
+            }
+            Inline::Link { url, content } => {
+                let _ = write!(
+                    out,
+                    "<a href=\"{}\">{}</a>",
+                    escape(url),
+                    self::inlines(content),
+                );
+            }
+            Inline::Html(html) => out.push_str(&xhtml(html)),
+        }
+    }
+    out
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+// Void elements written the XHTML way, `<br />` rather than `<br>`.
+fn xhtml(html: &str) -> String {
+    let mut out = html.to_owned();
+    for tag in ["br", "hr", "img"] {
+        out = out.replace(&format!("<{tag}>"), &format!("<{tag} />"));
+    }
+    out
+}
@@ -0,0 +1,263 @@
+// Converts a CommonMark post with front matter into the source file of an
+// essay: the `/** ... */` doc comment with its title and byline, followed by
+// the metadata macro.
+//
+//     ---
+//     title: Triage at scale for the Rust team
+//     date: 2020-02-20
+//     author: David Tolnay
+//     tags: [triage, community]
+//     ---
+//
+//     Yesterday Mark-Simulacrum [announced] an experimental new ...
+
+use crate::doc::{self, Kind};
+use crate::essays::{self, Essay};
+use crate::html;
+use crate::links;
+use std::fmt::Write as _;
+
+pub struct Post {
+    pub title: String,
+    // As in "2020-02-20".
+    pub date: String,
+    pub author: String,
+    pub tags: Vec<String>,
+    // The Markdown after the front matter, already escaped for the doc
+    // comment.
+    pub body: String,
+}
+
+pub fn parse(src: &str) -> Result<Post, String> {
+    let lines: Vec<&str> = src.lines().collect();
+    if lines.first().map(|line| line.trim_end()) != Some("---") {
+        return Err("expected front matter between `---` lines at the start of the file".into());
+    }
+    let end = 1 + lines[1..]
+        .iter()
+        .position(|line| line.trim_end() == "---")
+        .ok_or("front matter is never closed by `---`")?;
+
+    let mut title = None;
+    let mut date = None;
+    let mut author = None;
+    let mut tags = Vec::new();
+    let mut in_tags = false;
+    for (i, line) in lines.iter().enumerate().take(end).skip(1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // A block sequence, with one `- tag` per line.
+        if let Some(tag) = trimmed.strip_prefix("- ").filter(|_| in_tags) {
+            tags.push(unquote(tag).to_owned());
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("line {}: expected `key: value`", i + 1))?;
+        let value = value.trim();
+        in_tags = false;
+        match key.trim() {
+            "title" => title = Some(unquote(value).to_owned()),
+            "date" => date = Some(unquote(value).to_owned()),
+            "author" => author = Some(unquote(value).to_owned()),
+            "tags" => {
+                in_tags = value.is_empty();
+                let list = value.strip_prefix('[').and_then(|v| v.strip_suffix(']'));
+                tags = list
+                    .unwrap_or(value)
+                    .split(',')
+                    .map(|tag| unquote(tag.trim()).to_owned())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            other => {
+                return Err(format!(
+                    "line {}: unknown front matter field `{other}`",
+                    i + 1
+                ))
+            }
+        }
+    }
+
+    let missing = |name: &str| format!("front matter has no `{name}`");
+    let title = title
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| missing("title"))?;
+    let date = date.ok_or_else(|| missing("date"))?;
+    essays::published(&date)?;
+    let author = author
+        .filter(|a| !a.is_empty())
+        .ok_or_else(|| missing("author"))?;
+
+    // The title comes from the front matter, so a heading repeating it is
+    // dropped.
+    let mut start = end + 1;
+    while lines.get(start).is_some_and(|line| line.trim().is_empty()) {
+        start += 1;
+    }
+    if lines
+        .get(start)
+        .and_then(|line| line.strip_prefix("# "))
+        .is_some_and(|heading| heading.trim() == title)
+    {
+        start += 1;
+    }
+    let body = lines[start.min(lines.len())..].join("\n");
+    let trimmed = body.trim_start_matches('\n');
+    let line = start + body.len() - trimmed.len() + 1;
+    Ok(Post {
+        title,
+        date,
+        author,
+        tags,
+        body: escape(trimmed.trim_end(), line)?,
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+// Where the byline of an earlier essay by the same author links their name.
+pub fn author_url(essays: &[Essay], author: &str) -> Option<String> {
+    essays
+        .iter()
+        .filter(|essay| essay.author == author)
+        .flat_map(|essay| links::definitions(&essay.doc))
+        .find(|def| def.label == author)
+        .map(|def| def.target.to_owned())
+}
+
+// Contents of src/YYYY-MM-DD-slug.rs.
+pub fn source(post: &Post, macro_name: &str, author_url: Option<&str>) -> String {
+    let body = &post.body;
+    let published = essays::published(&post.date).unwrap_or_default();
+    let mut src = String::new();
+    let _ = writeln!(src, "/** # {}\n", escape_prose(&post.title));
+    let author = escape_prose(&post.author);
+    let date = post.date.replace('-', ".");
+    match author_url {
+        Some(url) => {
+            let _ = writeln!(src, "<sup>*by [{author}]&#8202;,&ensp;{date}*</sup>\n");
+            let _ = writeln!(src, "[{author}]: {url}\n");
+        }
+        None => {
+            let _ = writeln!(src, "<sup>*by {author}&#8202;,&ensp;{date}*</sup>\n");
+        }
+    }
+    src.push_str("<br>\n\n");
+    src.push_str(body);
+    if !body.is_empty() {
+        src.push('\n');
+    }
+    src.push_str("*/\n#[macro_export]\n");
+    let _ = writeln!(src, "macro_rules! {macro_name} {{\n    ({{");
+    let _ = writeln!(src, "        date:  {published:?},");
+    let _ = writeln!(src, "        author:  {:?},", post.author);
+    if !post.tags.is_empty() {
+        let tags: Vec<String> = post.tags.iter().map(|tag| format!("{tag:?}")).collect();
+        let _ = writeln!(src, "        tags:  [{}],", tags.join(", "));
+    }
+    src.push_str("    }) => {};\n}\n");
+    src
+}
+
+// Makes Markdown safe inside a `/** ... */` comment, where `*/` would end the
+// comment early and an unclosed `/*` would keep it from ending at all. In
+// prose the slash is written as `&#47;` instead, which renders the same. Code
+// is shown verbatim, so there it is an error unless the comments balance, as
+// they do in `/* snip */`. `first_line` is the line number of `markdown` in the
+// input.
+fn escape(markdown: &str, first_line: usize) -> Result<String, String> {
+    let mut out = String::new();
+    for line in doc::lines(markdown) {
+        if line.kind == Kind::Text {
+            out.push_str(&escape_prose(line.text));
+        } else {
+            out.push_str(line.text);
+        }
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+
+    let bytes = out.as_bytes();
+    let line_of = |offset: usize| first_line + out[..offset].matches('\n').count();
+    let mut opens = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                opens.push(i);
+                i += 2;
+            }
+            (b'*', b'/') => {
+                if opens.pop().is_none() {
+                    return Err(format!(
+                        "line {}: `*/` in code would end the doc comment",
+                        line_of(i),
+                    ));
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    if let Some(&open) = opens.first() {
+        return Err(format!(
+            "line {}: `/*` in code is never closed, so the doc comment would not end",
+            line_of(open),
+        ));
+    }
+    Ok(out)
+}
+
+// Replaces every slash next to an asterisk with `&#47;`. Entities are not
+// decoded inside code spans, so a code span that needs this becomes a `<code>`
+// element instead, which rustdoc renders the same.
+fn escape_prose(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(tick) = rest.find('`') {
+        out.push_str(&escape_slashes(&rest[..tick]));
+        let end = links::skip_code_span(rest, tick);
+        let span = &rest[tick..end];
+        if span.contains("/*") || span.contains("*/") {
+            let run = span.len() - span.trim_start_matches('`').len();
+            let code = &span[run..span.len() - run];
+            let code = code
+                .strip_prefix(' ')
+                .and_then(|code| code.strip_suffix(' '))
+                .unwrap_or(code);
+            let _ = write!(out, "<code>{}</code>", escape_slashes(&html::escape(code)));
+        } else {
+            out.push_str(span);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(&escape_slashes(rest));
+    out
+}
+
+fn escape_slashes(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let star = |i: Option<usize>| i.and_then(|i| bytes.get(i)) == Some(&b'*');
+    let mut out = String::new();
+    for (i, ch) in text.char_indices() {
+        if ch == '/' && (star(i.checked_sub(1)) || star(Some(i + 1))) {
+            out.push_str("&#47;");
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
@@ -0,0 +1,171 @@
+// Reference-style links in an essay: the `[label]: target` definitions, and
+// the `[text]`, `[text][label]` and `[label][]` uses of them in prose. Code
+// blocks and code spans are skipped.
+
+use crate::doc::{self, Kind};
+
+pub struct Definition<'a> {
+    pub label: &'a str,
+    pub target: &'a str,
+}
+
+pub struct Reference {
+    pub label: String,
+    // Line of the doc comment where the link begins, counting from 1.
+    pub line: usize,
+}
+
+// Parses a line of the form `[label]: target`.
+pub fn definition(line: &str) -> Option<Definition<'_>> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let rest = trimmed.strip_prefix('[')?;
+    let close = closing_bracket(rest)?;
+    let label = &rest[..close];
+    let target = rest[close + 1..]
+        .strip_prefix(':')?
+        .split_whitespace()
+        .next()?;
+    let target = target
+        .strip_prefix('<')
+        .and_then(|target| target.strip_suffix('>'))
+        .unwrap_or(target);
+    if label.trim().is_empty() {
+        return None;
+    }
+    Some(Definition { label, target })
+}
+
+pub fn definitions(doc: &str) -> Vec<Definition<'_>> {
+    doc::lines(doc)
+        .iter()
+        .filter(|line| line.kind == Kind::Text)
+        .filter_map(|line| definition(line.text))
+        .collect()
+}
+
+// Every bracketed link that refers to a definition, as opposed to an inline
+// `[text](url)` link.
+pub fn references(doc: &str) -> Vec<Reference> {
+    // Prose only, with code and definitions blanked out.
+    let mut prose = String::new();
+    for line in doc::lines(doc) {
+        if line.kind == Kind::Text && definition(line.text).is_none() {
+            prose.push_str(line.text);
+        }
+        prose.push('\n');
+    }
+
+    let line_of = |offset: usize| prose[..offset].matches('\n').count() + 1;
+    let mut references = Vec::new();
+    let bytes = prose.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => i = skip_code_span(&prose, i),
+            b'[' => {
+                let Some(close) = closing_bracket(&prose[i + 1..]).map(|close| i + 1 + close)
+                else {
+                    i += 1;
+                    continue;
+                };
+                let text = &prose[i + 1..close];
+                let after = &prose[close + 1..];
+                if after.starts_with('(') {
+                    i = close + 1 + closing_paren(after).map_or(1, |paren| paren + 1);
+                    continue;
+                }
+                if let Some(second) = after.strip_prefix('[') {
+                    if let Some(end) = closing_bracket(second) {
+                        let label = &second[..end];
+                        let label = if label.is_empty() { text } else { label };
+                        references.push(Reference {
+                            label: label.to_owned(),
+                            line: line_of(i),
+                        });
+                        i = close + 2 + end + 1;
+                        continue;
+                    }
+                }
+                references.push(Reference {
+                    label: text.to_owned(),
+                    line: line_of(i),
+                });
+                i = close + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    references
+}
+
+// Labels match case-insensitively and regardless of how whitespace inside
+// them is wrapped.
+pub fn normalize(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Offset of the `]` matching a `[` that precedes `rest`.
+pub fn closing_bracket(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'`' => {
+                i = skip_code_span(rest, i);
+                continue;
+            }
+            b'[' => depth += 1,
+            b']' if depth == 0 => return Some(i),
+            b']' => depth -= 1,
+            b'\n' if rest[i + 1..].starts_with('\n') => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Offset of the `)` that ends an inline link destination starting at `(`.
+fn closing_paren(rest: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, byte) in rest.bytes().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            b'\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Offset just past the code span whose opening backticks are at `start`, or
+// just past the backticks if they are never closed.
+pub fn skip_code_span(text: &str, start: usize) -> usize {
+    let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+    let mut i = start + run;
+    while let Some(found) = text[i..].find('`') {
+        let open = i + found;
+        let len = text[open..].len() - text[open..].trim_start_matches('`').len();
+        if len == run {
+            return open + len;
+        }
+        i = open + len;
+    }
+    start + run
+}
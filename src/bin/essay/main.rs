@@ -0,0 +1,330 @@
+//! Tools for the essays themselves, as opposed to the code they discuss.
+//!
+//! ```console
+//! $ cargo run --bin essay -- markdown target/markdown
+//! target/markdown/2019-08-08-await-a-minute.md
+//! target/markdown/2019-10-01-reference-types.md
+//! ...
+//! ```
+//!
+//! - `essay markdown <DIR>` writes each essay as standalone Markdown for
+//!   cross-posting. Lines hidden from rustdoc's rendering of code blocks are
+//!   removed, intra-doc links such as `futures01::Future::map` are replaced by
+//!   the docs.rs or doc.rust-lang.org URL they would link to, and code blocks
+//!   with rustdoc attributes like `compile_fail` are tagged plain `rust`.
+//!   Intra-doc links are resolved the same way as by `essay check` below, so
+//!   this and every other export fails if that documentation is unavailable.
+//!
+//! - `essay site <DIR>` renders a static site independent of docs.rs: an
+//!   index.html listing the essays, an HTML page per essay with highlighted
+//!   Rust code, and a stylesheet. Pages carry the same `<meta>` tags that
+//!   src/html-in-header adds on docs.rs. Section anchors match rustdoc's.
+//!
+//! - `essay epub <FILE>` packages every essay into an EPUB 3 book for
+//!   e-readers, one chapter per essay in date order, with the authors from
+//!   Cargo.toml.
+//!
+//! - `essay gemini <DIR>` writes each essay as Gemini gemtext, with the links
+//!   of each paragraph listed on `=>` lines after it.
+//!
+//! - `essay text <DIR>` writes each essay as plain text wrapped to 72 columns
+//!   for email newsletters, with links as numbered references.
+//!
+//! - `essay import <FILE>` adds a new essay from a Markdown post whose front
+//!   matter gives its `title`, `date`, `author`, and optionally `tags`. The
+//!   essay is written to src/YYYY-MM-DD-slug.rs, with the slug taken from the
+//!   post's file name, and registered in src/lib.rs. Any `*/` in the prose is
+//!   escaped so that it does not end the doc comment.
+//!
+//! - `essay new <TITLE> [<DATE>]` starts a new essay dated today or on the
+//!   given YYYY-MM-DD, with its heading, byline, and metadata macro in place,
+//!   and registers it in src/lib.rs. The file name is the date followed by a
+//!   slug of the title.
+//!
+//! - `essay preview [--addr HOST:PORT] [<ESSAY>]` serves the pages of
+//!   `essay site` on localhost, 127.0.0.1:8000 by default, re-rendering them
+//!   whenever an essay, src/lib.rs, or src/html-in-header changes. Open pages
+//!   reload themselves, so saving the essay shows the result within a second.
+//!   Given the path of an essay file, `/` redirects to that essay's page.
+//!
+//! - `essay check` checks the reference-style links of every essay, offline:
+//!   references that are never defined, definitions that are never used or
+//!   are defined twice, and intra-doc link targets that do not name an item
+//!   documented in core, alloc, std or futures01. Targets are looked up in the
+//!   standard library documentation of the toolchain's rust-docs component and
+//!   in documentation of futures 0.1 that is generated under target/essay from
+//!   its source in the cargo registry. Problems are printed one per line and
+//!   make the command exit with status 1.
+//!
+//! - `essay toc [--check]` regenerates the table of contents below the byline
+//!   of every essay, a list of links to its `##` sections that sits in the doc
+//!   comment inside `<nav class="contents">`, and prints the essays it changed.
+//!   With `--check` nothing is written, and any essay whose table is out of
+//!   date makes the command exit with status 1.
+//!
+//! Essays are found through the `mod` list in src/lib.rs relative to the
+//! current directory, so run this from the root of the repository.
+
+#[path = "../common/lexer.rs"]
+mod lexer;
+
+mod anchors;
+mod check;
+mod doc;
+mod epub;
+mod essays;
+mod gemini;
+mod highlight;
+mod html;
+mod import;
+mod links;
+mod markdown;
+mod preview;
+mod resolve;
+mod site;
+mod text;
+mod toc;
+mod zip;
+
+use crate::epub::Book;
+use crate::essays::Essay;
+use crate::import::Post;
+use crate::site::Site;
+use std::env;
+use std::fs;
+use std::io::{self, Write as _};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["markdown", dir] => export(Path::new(dir), "md", doc::markdown),
+        ["site", dir] => site(Path::new(dir)),
+        ["epub", path] => epub(Path::new(path)),
+        ["gemini", dir] => export(Path::new(dir), "gmi", gemini::gemtext),
+        ["text", dir] => export(Path::new(dir), "txt", text::plain),
+        ["import", path] => import(Path::new(path)),
+        ["new", title] => new(title, None),
+        ["new", title, date] => new(title, Some(date)),
+        ["preview", args @ ..] => preview(args),
+        ["check"] => check(),
+        ["toc"] => toc(false),
+        ["toc", "--check"] => toc(true),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: essay markdown <DIR>");
+    eprintln!("       essay site <DIR>");
+    eprintln!("       essay epub <FILE>");
+    eprintln!("       essay gemini <DIR>");
+    eprintln!("       essay text <DIR>");
+    eprintln!("       essay import <FILE>");
+    eprintln!("       essay new <TITLE> [<DATE>]");
+    eprintln!("       essay preview [--addr HOST:PORT] [<ESSAY>]");
+    eprintln!("       essay check");
+    eprintln!("       essay toc [--check]");
+    process::exit(2);
+}
+
+fn load() -> Vec<Essay> {
+    essays::load(Path::new(".")).unwrap_or_else(|err| fail(&err))
+}
+
+fn fail(err: &essays::Error) -> ! {
+    eprintln!("error: {err}");
+    process::exit(1);
+}
+
+// Writes one file per essay.
+fn export(dir: &Path, extension: &str, render: fn(&Essay) -> Result<String, essays::Error>) {
+    for essay in load() {
+        let path = dir.join(format!("{}.{}", essay.stem(), extension));
+        write(&path, render(&essay).unwrap_or_else(|err| fail(&err)));
+    }
+}
+
+fn site(dir: &Path) {
+    let root = Path::new(".");
+    let site = Site {
+        title: essays::crate_doc(root).unwrap_or_else(|err| fail(&err)),
+        head: essays::read(&root.join("src").join("html-in-header"))
+            .unwrap_or_else(|err| fail(&err)),
+    };
+    let files = site.files(&load()).unwrap_or_else(|err| fail(&err));
+    for (name, contents) in files {
+        write(&dir.join(name), contents);
+    }
+}
+
+fn epub(path: &Path) {
+    let root = Path::new(".");
+    let manifest = essays::manifest(root).unwrap_or_else(|err| fail(&err));
+    let book = Book {
+        title: essays::crate_doc(root).unwrap_or_else(|err| fail(&err)),
+        authors: manifest.authors,
+        identifier: manifest.documentation,
+    };
+    let package = book.package(&load()).unwrap_or_else(|err| fail(&err));
+    write(path, package);
+}
+
+fn import(path: &Path) {
+    let src = essays::read(path).unwrap_or_else(|err| fail(&err));
+    let post = import::parse(&src).unwrap_or_else(|msg| {
+        fail(&essays::Error {
+            path: path.to_owned(),
+            msg,
+        })
+    });
+
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let stem = essays::split_file_name(stem).map_or(stem, |(_date, slug)| slug);
+    let mut slug = essays::slugify(stem);
+    if slug.is_empty() {
+        slug = essays::slugify(&post.title);
+    }
+    create(&post, &slug);
+}
+
+fn new(title: &str, date: Option<&str>) {
+    let slug = essays::slugify(title);
+    if slug.is_empty() {
+        eprintln!("error: title {title:?} has no letters or digits for a file name");
+        process::exit(1);
+    }
+    let date = date.map_or_else(essays::today, str::to_owned);
+    if let Err(err) = essays::published(&date) {
+        eprintln!("error: {err}");
+        process::exit(1);
+    }
+    let manifest = essays::manifest(Path::new(".")).unwrap_or_else(|err| fail(&err));
+    let post = Post {
+        title: title.to_owned(),
+        date,
+        author: manifest.authors.into_iter().next().unwrap_or_default(),
+        tags: Vec::new(),
+        body: String::new(),
+    };
+    create(&post, &slug);
+}
+
+fn preview(args: &[&str]) {
+    let mut addr = "127.0.0.1:8000";
+    let mut essay = None;
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        if arg == "--addr" {
+            addr = args.next().unwrap_or_else(|| usage());
+        } else if let Some(value) = arg.strip_prefix("--addr=") {
+            addr = value;
+        } else if arg.starts_with('-') || essay.is_some() {
+            usage();
+        } else {
+            essay = Some(arg);
+        }
+    }
+
+    // The essay to start from, as a path like src/2019-08-08-await-a-minute.rs.
+    let start = essay.map(|essay| {
+        let path = Path::new(essay);
+        let stem = path.file_stem().and_then(|stem| stem.to_str());
+        match stem.filter(|&stem| load().iter().any(|essay| essay.stem() == stem)) {
+            Some(stem) => stem.to_owned(),
+            None => fail(&essays::Error {
+                path: path.to_owned(),
+                msg: "not an essay registered in src/lib.rs".to_owned(),
+            }),
+        }
+    });
+
+    let listener = TcpListener::bind(addr).unwrap_or_else(|err| {
+        eprintln!("error: {addr}: {err}");
+        process::exit(1);
+    });
+    let local_addr = listener.local_addr().unwrap();
+    println!("listening on http://{local_addr}");
+    let _ = io::stdout().flush();
+    preview::serve(&listener, Path::new("."), start);
+}
+
+fn check() {
+    let mut failed = false;
+    for essay in load() {
+        for problem in check::links(&essay) {
+            println!("src/{}.rs:{}: {}", essay.stem(), problem.line, problem.msg);
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn toc(check: bool) {
+    let mut stale = false;
+    for essay in load() {
+        let path = essay.path();
+        let src = essays::read(&path).unwrap_or_else(|err| fail(&err));
+        let updated = toc::update(&src).unwrap_or_else(|msg| {
+            fail(&essays::Error {
+                path: path.clone(),
+                msg,
+            })
+        });
+        if updated != src {
+            stale = true;
+            if check {
+                println!("{}", path.display());
+            } else {
+                write(&path, updated);
+            }
+        }
+    }
+    if check && stale {
+        process::exit(1);
+    }
+}
+
+// Writes src/YYYY-MM-DD-slug.rs and registers it in src/lib.rs.
+fn create(post: &Post, slug: &str) {
+    let lib_path = Path::new("src").join("lib.rs");
+    let lib = essays::read(&lib_path).unwrap_or_else(|err| fail(&err));
+    let file_name = format!("{}-{}.rs", post.date, slug);
+    let essay_path = Path::new("src").join(&file_name);
+    if essay_path.exists() {
+        fail(&essays::Error {
+            path: essay_path,
+            msg: "already exists".to_owned(),
+        });
+    }
+    let (number, lib) = essays::register(&lib, &file_name);
+    let author_url = import::author_url(&load(), &post.author);
+    let macro_name = essays::macro_name(number, slug);
+    write(
+        &essay_path,
+        import::source(post, &macro_name, author_url.as_deref()),
+    );
+    write(&lib_path, lib);
+}
+
+// Writes an output file, creating its directory if needed, and prints its path.
+fn write(path: &Path, contents: impl AsRef<[u8]>) {
+    let result = match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+    .and_then(|()| fs::write(path, contents));
+    if let Err(err) = result {
+        eprintln!("error: {}: {}", path.display(), err);
+        process::exit(1);
+    }
+    println!("{}", PathBuf::from(path).display());
+}
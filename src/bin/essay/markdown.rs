@@ -0,0 +1,565 @@
+// A parser for the subset of CommonMark that the essays use, for the
+// exporters that need more structure than the Markdown text itself.
+//
+// Supported: ATX headings, paragraphs, fenced code blocks, block quotes,
+// bullet and ordered lists (nested by indentation), thematic breaks, HTML
+// blocks, and reference definitions; inline code spans, `*` and `**`
+// emphasis, inline and reference links, autolinks, inline HTML, entities and
+// backslash escapes. Not supported: setext headings, indented code blocks,
+// `_` emphasis, tables.
+
+use crate::links;
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq)]
+pub enum Block {
+    Heading { level: usize, content: Vec<Inline> },
+    Paragraph(Vec<Inline>),
+    Code { lang: String, code: String },
+    Quote(Vec<Block>),
+    List(List),
+    Html(String),
+    Rule,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct List {
+    // Number of the first item, or None for a bullet list.
+    pub start: Option<usize>,
+    // Whether items are separated by blank lines, which puts each item's text
+    // in a paragraph.
+    pub loose: bool,
+    pub items: Vec<Vec<Block>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Inline {
+    // Entities and escapes already decoded. May contain '\n' at soft line
+    // breaks.
+    Text(String),
+    Code(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Link { url: String, content: Vec<Inline> },
+    Html(String),
+}
+
+type Definitions = BTreeMap<String, String>;
+
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let definitions = links::definitions(markdown)
+        .into_iter()
+        .rev()
+        .map(|def| (links::normalize(def.label), def.target.to_owned()))
+        .collect();
+    let lines: Vec<&str> = markdown.lines().collect();
+    Parser {
+        definitions: &definitions,
+    }
+    .blocks(&lines)
+}
+
+// The text of inline content with all markup removed.
+pub fn plain_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(string) | Inline::Code(string) => text.push_str(string),
+            Inline::Emphasis(content) | Inline::Strong(content) => {
+                text.push_str(&plain_text(content));
+            }
+            Inline::Link { content, .. } => text.push_str(&plain_text(content)),
+            Inline::Html(_) => {}
+        }
+    }
+    text
+}
+
+struct Parser<'a> {
+    definitions: &'a Definitions,
+}
+
+impl Parser<'_> {
+    fn blocks(&self, lines: &[&str]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            if trimmed.is_empty() {
+                i += 1;
+            } else if let Some(marker) = fence(line) {
+                let (block, next) = code_block(lines, i, marker, indent);
+                blocks.push(block);
+                i = next;
+            } else if let Some((level, text)) = heading(line) {
+                blocks.push(Block::Heading {
+                    level,
+                    content: self.inlines(text),
+                });
+                i += 1;
+            } else if is_rule(line) {
+                blocks.push(Block::Rule);
+                i += 1;
+            } else if indent <= 3 && trimmed.starts_with('>') {
+                let mut inner = Vec::new();
+                while i < lines.len() {
+                    let trimmed = lines[i].trim_start();
+                    if let Some(rest) = trimmed.strip_prefix('>') {
+                        inner.push(rest.strip_prefix(' ').unwrap_or(rest));
+                    } else if trimmed.is_empty() || interrupts(lines[i]) {
+                        break;
+                    } else {
+                        // Lazy continuation of a paragraph in the quote.
+                        inner.push(lines[i]);
+                    }
+                    i += 1;
+                }
+                blocks.push(Block::Quote(self.blocks(&inner)));
+            } else if let Some(item) = list_item(line) {
+                let (list, next) = self.list(lines, i, &item);
+                blocks.push(Block::List(list));
+                i = next;
+            } else if indent <= 3 && is_html_block(trimmed) {
+                let start = i;
+                while i < lines.len() && !lines[i].trim().is_empty() {
+                    i += 1;
+                }
+                blocks.push(Block::Html(lines[start..i].join("\n")));
+            } else if indent <= 3 && links::definition(line).is_some() {
+                i += 1;
+            } else {
+                let start = i;
+                i += 1;
+                while i < lines.len() && !lines[i].trim().is_empty() && !interrupts(lines[i]) {
+                    i += 1;
+                }
+                let text: Vec<&str> = lines[start..i].iter().map(|line| line.trim()).collect();
+                blocks.push(Block::Paragraph(self.inlines(&text.join("\n"))));
+            }
+        }
+        blocks
+    }
+
+    fn list(&self, lines: &[&str], mut i: usize, first: &Item) -> (List, usize) {
+        let mut list = List {
+            start: first.number,
+            loose: false,
+            items: Vec::new(),
+        };
+        let mut blank_between = false;
+        while i < lines.len() {
+            let Some(item) = list_item(lines[i]).filter(|item| item.same_list(first)) else {
+                break;
+            };
+            if blank_between {
+                list.loose = true;
+            }
+            let mut content = vec![&lines[i][item.content..]];
+            i += 1;
+            let mut blank = false;
+            while i < lines.len() {
+                let line = lines[i];
+                let indent = line.len() - line.trim_start().len();
+                if line.trim().is_empty() {
+                    blank = true;
+                    content.push("");
+                } else if indent >= item.content {
+                    list.loose |= blank;
+                    blank = false;
+                    content.push(&line[item.content..]);
+                } else if !blank && !interrupts(line) && list_item(line).is_none() {
+                    content.push(line.trim_start());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            while content.last() == Some(&"") {
+                content.pop();
+            }
+            blank_between = blank;
+            list.items.push(self.blocks(&content));
+        }
+        (list, i)
+    }
+
+    fn inlines(&self, text: &str) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        let mut buf = String::new();
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let ch = rest.chars().next().unwrap();
+            let parsed = match ch {
+                '\\' => match rest[1..].chars().next() {
+                    Some(next) if next.is_ascii_punctuation() => {
+                        buf.push(next);
+                        i += 2;
+                        continue;
+                    }
+                    _ => None,
+                },
+                '`' => code_span(rest).map(|(code, len)| (Inline::Code(code), len)),
+                '&' => {
+                    if let Some((decoded, len)) = entity(rest) {
+                        buf.push_str(&decoded);
+                        i += len;
+                        continue;
+                    }
+                    None
+                }
+                '<' => autolink(rest).or_else(|| inline_html(rest)),
+                '[' => self.link(rest),
+                '*' => self.emphasis(text, i),
+                _ => None,
+            };
+            if let Some((inline, len)) = parsed {
+                if !buf.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut buf)));
+                }
+                inlines.push(inline);
+                i += len;
+            } else {
+                buf.push(ch);
+                i += ch.len_utf8();
+            }
+        }
+        if !buf.is_empty() {
+            inlines.push(Inline::Text(buf));
+        }
+        inlines
+    }
+
+    // `[text](url)`, `[text][label]`, `[label][]` or `[label]`, if it refers
+    // to a definition. Returns the link and the length of its source.
+    fn link(&self, rest: &str) -> Option<(Inline, usize)> {
+        let close = 1 + links::closing_bracket(&rest[1..])?;
+        let text = &rest[1..close];
+        let after = &rest[close + 1..];
+        let (url, len) = if let Some(dest) = after.strip_prefix('(') {
+            let end = dest.find(')')?;
+            let url = dest[..end].split_whitespace().next().unwrap_or_default();
+            (url.to_owned(), close + 2 + end + 1)
+        } else if let Some(second) = after.strip_prefix('[') {
+            let end = links::closing_bracket(second)?;
+            let label = if end == 0 { text } else { &second[..end] };
+            let url = self.definitions.get(&links::normalize(label))?;
+            (url.clone(), close + 2 + end + 1)
+        } else {
+            let url = self.definitions.get(&links::normalize(text))?;
+            (url.clone(), close + 1)
+        };
+        let content = self.inlines(text);
+        Some((Inline::Link { url, content }, len))
+    }
+
+    // `*text*` or `**text**` starting at `text[i..]`.
+    fn emphasis(&self, text: &str, i: usize) -> Option<(Inline, usize)> {
+        let run = delimiter_run(&text[i..]);
+        let after = text[i + run..].chars().next()?;
+        if after.is_whitespace() {
+            return None;
+        }
+        if run >= 2 {
+            if let Some(end) = closing_delimiter(text, i + 2, 2) {
+                let content = self.inlines(&text[i + 2..end]);
+                return Some((Inline::Strong(content), end + 2 - i));
+            }
+        }
+        let end = closing_delimiter(text, i + 1, 1)?;
+        let content = self.inlines(&text[i + 1..end]);
+        Some((Inline::Emphasis(content), end + 1 - i))
+    }
+}
+
+struct Item {
+    // For ordered lists, the number of the item.
+    number: Option<usize>,
+    // The bullet character, or `.` or `)` after the number.
+    delimiter: char,
+    // Columns up to where the item's content begins.
+    content: usize,
+}
+
+impl Item {
+    fn same_list(&self, other: &Item) -> bool {
+        self.number.is_some() == other.number.is_some() && self.delimiter == other.delimiter
+    }
+}
+
+fn list_item(line: &str) -> Option<Item> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    if indent > 3 {
+        return None;
+    }
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    let (number, delimiter, marker) = if digits == 0 {
+        let delimiter = trimmed
+            .chars()
+            .next()
+            .filter(|ch| matches!(ch, '-' | '*' | '+'))?;
+        (None, delimiter, 1)
+    } else if digits <= 9 {
+        let delimiter = trimmed[digits..]
+            .chars()
+            .next()
+            .filter(|ch| matches!(ch, '.' | ')'))?;
+        (trimmed[..digits].parse().ok(), delimiter, digits + 1)
+    } else {
+        return None;
+    };
+    let after = &trimmed[marker..];
+    let spaces = after.len() - after.trim_start_matches(' ').len();
+    if after.trim().is_empty() {
+        return Some(Item {
+            number,
+            delimiter,
+            content: indent + marker + 1,
+        });
+    }
+    if spaces == 0 {
+        return None;
+    }
+    let spaces = if spaces > 4 { 1 } else { spaces };
+    Some(Item {
+        number,
+        delimiter,
+        content: indent + marker + spaces,
+    })
+}
+
+// Whether a line starts a block that ends a paragraph without a blank line.
+fn interrupts(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    fence(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || indent <= 3 && trimmed.starts_with('>')
+        || list_item(line).is_some_and(|item| item.number.is_none_or(|n| n == 1))
+}
+
+fn fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let ch = trimmed
+        .chars()
+        .next()
+        .filter(|&ch| ch == '`' || ch == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(ch).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+fn code_block(lines: &[&str], start: usize, marker: &str, indent: usize) -> (Block, usize) {
+    let lang = lines[start].trim_start()[marker.len()..]
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+    let mut code = String::new();
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if let Some(close) = fence(line) {
+            if close.starts_with(marker) && line.trim_start()[close.len()..].trim().is_empty() {
+                break;
+            }
+        }
+        let strip = (line.len() - line.trim_start().len()).min(indent);
+        code.push_str(&line[strip..]);
+        code.push('\n');
+    }
+    (Block::Code { lang, code }, i)
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let text = rest.trim();
+    let text = text.trim_end_matches('#');
+    let text = if text.is_empty() || text.ends_with(' ') {
+        text.trim_end()
+    } else {
+        rest.trim()
+    };
+    Some((level, text))
+}
+
+fn is_rule(line: &str) -> bool {
+    let trimmed = line.trim();
+    if line.len() - line.trim_start().len() > 3 {
+        return false;
+    }
+    ['-', '*', '_'].iter().any(|&ch| {
+        trimmed.chars().filter(|&c| c == ch).count() >= 3
+            && trimmed.chars().all(|c| c == ch || c == ' ' || c == '\t')
+    })
+}
+
+// An HTML block is a line that is nothing but HTML tags, like `<br>`.
+fn is_html_block(trimmed: &str) -> bool {
+    let mut rest = trimmed.trim_end();
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let Some((_, len)) = inline_html(rest) else {
+            return false;
+        };
+        rest = rest[len..].trim_start();
+    }
+    true
+}
+
+fn code_span(rest: &str) -> Option<(String, usize)> {
+    let run = rest.len() - rest.trim_start_matches('`').len();
+    let mut i = run;
+    while let Some(found) = rest[i..].find('`') {
+        let open = i + found;
+        let len = rest[open..].len() - rest[open..].trim_start_matches('`').len();
+        if len == run {
+            let code = rest[run..open].replace('\n', " ");
+            let code = match code
+                .strip_prefix(' ')
+                .and_then(|code| code.strip_suffix(' '))
+            {
+                Some(stripped) if !stripped.trim().is_empty() => stripped.to_owned(),
+                _ => code,
+            };
+            return Some((code, open + len));
+        }
+        i = open + len;
+    }
+    None
+}
+
+fn delimiter_run(text: &str) -> usize {
+    text.len() - text.trim_start_matches('*').len()
+}
+
+// Start of a run of exactly `len` asterisks after `from` that is preceded by
+// something other than whitespace, skipping over code spans.
+fn closing_delimiter(text: &str, from: usize, len: usize) -> Option<usize> {
+    let mut i = from;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with('`') {
+            match code_span(rest) {
+                Some((_, skip)) => i += skip,
+                None => i += delimiter_run_of(rest, '`'),
+            }
+            continue;
+        }
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            i += 1 + escaped.chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with('*') {
+            let run = delimiter_run(rest);
+            let before = text[..i].chars().next_back();
+            if i > from && before.is_some_and(|ch| !ch.is_whitespace()) && run == len {
+                return Some(i);
+            }
+            if len == 1 && run >= 2 {
+                // A nested `**strong**` inside emphasis.
+                if let Some(end) = closing_delimiter(text, i + run, run) {
+                    i = end + run;
+                    continue;
+                }
+            }
+            i += run;
+            continue;
+        }
+        i += rest.chars().next().unwrap().len_utf8();
+    }
+    None
+}
+
+fn delimiter_run_of(text: &str, ch: char) -> usize {
+    text.len() - text.trim_start_matches(ch).len()
+}
+
+// `<https://...>`.
+fn autolink(rest: &str) -> Option<(Inline, usize)> {
+    let end = rest.find('>')?;
+    let url = &rest[1..end];
+    let scheme = url.split_once(':')?.0;
+    let valid = !scheme.is_empty()
+        && scheme
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "+-.".contains(ch))
+        && !url.contains(char::is_whitespace);
+    valid.then(|| {
+        let content = vec![Inline::Text(url.to_owned())];
+        let url = url.to_owned();
+        (Inline::Link { url, content }, end + 1)
+    })
+}
+
+// An opening or closing tag like `<sup>`, `</sup>` or `<br>`.
+fn inline_html(rest: &str) -> Option<(Inline, usize)> {
+    if !rest.starts_with('<') {
+        return None;
+    }
+    let end = rest.find('>')?;
+    let tag = &rest[1..end];
+    let name = tag.strip_prefix('/').unwrap_or(tag);
+    let name = name.split_whitespace().next()?.trim_end_matches('/');
+    let valid = name.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        && !tag.contains('\n');
+    valid.then(|| (Inline::Html(rest[..=end].to_owned()), end + 1))
+}
+
+// Decodes `&mdash;`, `&#8202;` and the like.
+fn entity(rest: &str) -> Option<(String, usize)> {
+    let end = rest.find(';')?;
+    let name = &rest[1..end];
+    let ch = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code)?
+    } else {
+        ENTITIES.iter().find(|(entity, _)| *entity == name)?.1
+    };
+    Some((ch.to_string(), end + 1))
+}
+
+const ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("apos", '\''),
+    ("emsp", '\u{2003}'),
+    ("ensp", '\u{2002}'),
+    ("gt", '>'),
+    ("hellip", '\u{2026}'),
+    ("ldquo", '\u{201c}'),
+    ("lsquo", '\u{2018}'),
+    ("lt", '<'),
+    ("mdash", '\u{2014}'),
+    ("nbsp", '\u{a0}'),
+    ("ndash", '\u{2013}'),
+    ("quot", '"'),
+    ("rdquo", '\u{201d}'),
+    ("rsquo", '\u{2019}'),
+    ("thinsp", '\u{2009}'),
+];
@@ -0,0 +1,227 @@
+// A local server for previewing essays while writing them. Pages are the ones
+// `essay site` renders, plus a script that reloads the page when the sources
+// change. A background thread polls the modification times of src/lib.rs, the
+// essays, and src/html-in-header, so a save shows up in the browser within a
+// fraction of a second.
+
+use crate::essays::{self, Error};
+use crate::html;
+use crate::site::Site;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL: Duration = Duration::from_millis(200);
+
+struct Rendered {
+    // Incremented every time the sources change.
+    version: u64,
+    // Files by name, or what kept the site from rendering.
+    files: Result<BTreeMap<String, String>, String>,
+}
+
+struct Response {
+    status: &'static str,
+    headers: String,
+    body: String,
+}
+
+// Serves forever. `/` goes to the page of the essay with file stem `start`,
+// if given, or otherwise to the index.
+pub fn serve(listener: &TcpListener, root: &Path, start: Option<String>) {
+    let mut seen = stamps(root);
+    let rendered = Arc::new(Mutex::new(Rendered {
+        version: 1,
+        files: render(root),
+    }));
+
+    let watched = Arc::clone(&rendered);
+    let root = root.to_owned();
+    thread::spawn(move || loop {
+        thread::sleep(POLL);
+        let latest = stamps(&root);
+        if latest == seen {
+            continue;
+        }
+        seen = latest;
+        let files = render(&root);
+        match &files {
+            Ok(_) => eprintln!("rendered"),
+            Err(err) => eprintln!("error: {err}"),
+        }
+        let mut rendered = watched.lock().unwrap_or_else(PoisonError::into_inner);
+        rendered.version += 1;
+        rendered.files = files;
+    });
+
+    let start = Arc::new(start);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let rendered = Arc::clone(&rendered);
+        let start = Arc::clone(&start);
+        thread::spawn(move || {
+            if let Err(err) = respond(&stream, &rendered, start.as_deref()) {
+                eprintln!("error: {err}");
+            }
+        });
+    }
+}
+
+fn render(root: &Path) -> Result<BTreeMap<String, String>, String> {
+    let render = || -> Result<_, Error> {
+        let site = Site {
+            title: essays::crate_doc(root)?,
+            head: essays::read(&root.join("src").join("html-in-header"))?,
+        };
+        Ok(site.files(&essays::load(root)?)?.into_iter().collect())
+    };
+    render().map_err(|err| err.to_string())
+}
+
+// Modification time and length of every source, or None for a file that does
+// not exist right now.
+type Stamps = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+
+fn stamps(root: &Path) -> Stamps {
+    let src = root.join("src");
+    let lib = src.join("lib.rs");
+    let mut paths = vec![lib.clone(), src.join("html-in-header")];
+    if let Ok(lib) = fs::read_to_string(&lib) {
+        for (path, module) in essays::modules(&lib) {
+            if module.starts_with('_') {
+                paths.push(src.join(path));
+            }
+        }
+    }
+    paths
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            let stamp = metadata.and_then(|m| Some((m.modified().ok()?, m.len())));
+            (path, stamp)
+        })
+        .collect()
+}
+
+fn respond(stream: &TcpStream, rendered: &Mutex<Rendered>, start: Option<&str>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let request = (
+        parts.next().map(str::to_owned),
+        parts.next().map(str::to_owned),
+    );
+    // Headers are not needed for anything.
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let response = match request {
+        (Some(method), Some(path)) => {
+            let rendered = rendered.lock().unwrap_or_else(PoisonError::into_inner);
+            route(&method, &path, &rendered, start)
+        }
+        _ => text("400 Bad Request", "malformed request\n".to_owned()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.headers,
+        response.body.len(),
+    )?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn route(method: &str, path: &str, rendered: &Rendered, start: Option<&str>) -> Response {
+    if method != "GET" {
+        return text("405 Method Not Allowed", "method not allowed\n".to_owned());
+    }
+    let path = path.split('?').next().unwrap_or_default();
+    if path == "/__version" {
+        return text("200 OK", rendered.version.to_string());
+    }
+    let files = match &rendered.files {
+        Ok(files) => files,
+        Err(err) => {
+            let body = format!("<h1>Error</h1>\n<pre>{}</pre>\n", html::escape(err));
+            let page = format!(
+                "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Error</title>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            );
+            return Response {
+                status: "500 Internal Server Error",
+                headers: "Content-Type: text/html; charset=utf-8\r\n".to_owned(),
+                body: reloading(&page, rendered.version),
+            };
+        }
+    };
+    let name = match (path, start) {
+        ("/", Some(stem)) => {
+            return Response {
+                status: "302 Found",
+                headers: format!("Location: /{stem}.html\r\n"),
+                body: String::new(),
+            };
+        }
+        ("/", None) => "index.html",
+        _ => path.strip_prefix('/').unwrap_or(path),
+    };
+    let Some(contents) = files.get(name) else {
+        return text("404 Not Found", "not found\n".to_owned());
+    };
+    if Path::new(name)
+        .extension()
+        .is_some_and(|extension| extension == "css")
+    {
+        return Response {
+            status: "200 OK",
+            headers: "Content-Type: text/css; charset=utf-8\r\n".to_owned(),
+            body: contents.clone(),
+        };
+    }
+    Response {
+        status: "200 OK",
+        headers: "Content-Type: text/html; charset=utf-8\r\n".to_owned(),
+        body: reloading(contents, rendered.version),
+    }
+}
+
+// Adds a script that polls for a newer version of the sources and reloads the
+// page when there is one.
+fn reloading(page: &str, version: u64) -> String {
+    let script = format!(
+        "<script>\n\
+         (function poll() {{\n\
+         \x20 fetch(\"/__version\").then((response) => response.text()).then(\n\
+         \x20   (latest) => latest === \"{version}\" ? setTimeout(poll, 250) : location.reload(),\n\
+         \x20   () => setTimeout(poll, 1000),\n\
+         \x20 );\n\
+         }})();\n\
+         </script>\n",
+    );
+    match page.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &page[..end], script, &page[end..]),
+        None => page.to_owned() + &script,
+    }
+}
+
+fn text(status: &'static str, body: String) -> Response {
+    Response {
+        status,
+        headers: "Content-Type: text/plain; charset=utf-8\r\n".to_owned(),
+        body,
+    }
+}
@@ -0,0 +1,353 @@
+// Resolves rustdoc intra-doc link targets like `futures01::Future::map` to the
+// URL rustdoc would link to, without running rustdoc on the essays. Targets are
+// looked up in real documentation: the standard library's as installed by the
+// rust-docs component of the toolchain, and futures 0.1's as generated from its
+// source in the cargo registry the first time it is needed. A path resolves if
+// rustdoc has a page for it, and a trailing method, variant, field or
+// associated item if that page documents it.
+
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::OnceLock;
+
+struct Crate {
+    // As written in intra-doc paths.
+    name: &'static str,
+    // Directory of the crate's pages under its documentation root.
+    dir: &'static str,
+    // Where that documentation root is published. The standard library matches
+    // the `--extern-html-root-url` flags in Cargo.toml; futures01 is on docs.rs
+    // under the name it is published as.
+    url: &'static str,
+    // The documentation root on this machine.
+    html: fn() -> &'static Result<PathBuf, String>,
+}
+
+const CRATES: &[Crate] = &[
+    Crate {
+        name: "std",
+        dir: "std",
+        url: "https://doc.rust-lang.org/",
+        html: std_html,
+    },
+    Crate {
+        name: "core",
+        dir: "core",
+        url: "https://doc.rust-lang.org/",
+        html: std_html,
+    },
+    Crate {
+        name: "alloc",
+        dir: "alloc",
+        url: "https://doc.rust-lang.org/",
+        html: std_html,
+    },
+    Crate {
+        name: "futures01",
+        dir: "futures",
+        url: "https://docs.rs/futures/0.1/",
+        html: futures_html,
+    },
+];
+
+// The kinds of page rustdoc writes for an item, as in `struct.Cell.html`.
+const PAGES: &[&str] = &[
+    "struct",
+    "enum",
+    "trait",
+    "type",
+    "fn",
+    "macro",
+    "union",
+    "constant",
+    "static",
+    "primitive",
+    "traitalias",
+    "derive",
+    "attr",
+];
+
+// The kinds of anchor rustdoc gives to what an item's page documents, as in
+// `#method.map`. Required trait methods are `tymethod`.
+const MEMBERS: &[&str] = &[
+    "method",
+    "tymethod",
+    "variant",
+    "structfield",
+    "associatedtype",
+    "associatedconstant",
+];
+
+// Whether an intra-doc link target is a path rather than a URL or a relative
+// link, for example `core::cell::Cell` but not `https://...` or `#heading`.
+pub fn is_path(target: &str) -> bool {
+    let target = strip_decorations(target);
+    !target.is_empty()
+        && target.split("::").all(|segment| {
+            let mut chars = segment.chars();
+            chars
+                .next()
+                .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+                && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+        })
+}
+
+// The URL for an intra-doc link target, None if it is not a path to an item
+// documented in one of the crates in CRATES, or an error if the documentation
+// of that crate is not available.
+pub fn resolve(target: &str) -> Result<Option<String>, String> {
+    let path = strip_decorations(target);
+    if !is_path(path) {
+        return Ok(None);
+    }
+    let segments: Vec<&str> = path.split("::").collect();
+    let (krate, first) = match CRATES.iter().find(|krate| krate.name == segments[0]) {
+        Some(krate) => (krate, None),
+        // Anything else has to be in scope without a path: the prelude, the
+        // macros exported by std, and primitive types.
+        None => (&CRATES[0], Some(segments[0])),
+    };
+    let html = (krate.html)().as_ref().map_err(String::clone)?;
+    let root = html.join(krate.dir);
+    let page = match first {
+        None => Some(root.join("index.html")),
+        Some(name) => prelude(&root, name),
+    };
+    let Some((page, anchor)) = page.and_then(|page| walk(page, &segments[1..])) else {
+        return Ok(None);
+    };
+    let Ok(relative) = page.strip_prefix(html) else {
+        return Ok(None);
+    };
+    let mut url = krate.url.to_owned();
+    for (i, component) in relative.iter().enumerate() {
+        if i > 0 {
+            url.push('/');
+        }
+        url.push_str(&component.to_string_lossy());
+    }
+    if let Some(anchor) = anchor {
+        url.push('#');
+        url.push_str(&anchor);
+    }
+    Ok(Some(url))
+}
+
+// The page of a name that is in scope in every crate.
+fn prelude(std: &Path, name: &str) -> Option<PathBuf> {
+    ["rust_2021", "v1"]
+        .iter()
+        .find_map(|edition| item(&std.join("prelude").join(edition), name))
+        .or_else(|| {
+            ["macro", "primitive"]
+                .iter()
+                .map(|kind| std.join(format!("{kind}.{name}.html")))
+                .find(|page| page.is_file())
+        })
+}
+
+// Follows the remaining segments of a path from the page of its first part.
+// Returns the page, and the anchor on it if the last segment is a member.
+fn walk(mut page: PathBuf, segments: &[&str]) -> Option<(PathBuf, Option<String>)> {
+    for (i, segment) in segments.iter().enumerate() {
+        if page.ends_with("index.html") {
+            page = item(page.parent()?, segment)?;
+        } else if i + 1 == segments.len() {
+            let anchor = member(&page, segment)?;
+            return Some((page, Some(anchor)));
+        } else {
+            return None;
+        }
+    }
+    Some((page, None))
+}
+
+// The page of the item `name` in the module whose pages are in `dir`.
+fn item(dir: &Path, name: &str) -> Option<PathBuf> {
+    let module = dir.join(name).join("index.html");
+    let page = PAGES
+        .iter()
+        .map(|kind| dir.join(format!("{kind}.{name}.html")))
+        .chain([module])
+        .find(|page| page.is_file());
+    if let Some(page) = page {
+        return follow(page);
+    }
+    // A re-export that rustdoc lists on the module page without inlining, like
+    // `pub use future::Future;` at the root of futures.
+    let index = fs::read_to_string(dir.join("index.html")).ok()?;
+    let start = index.find(&format!("<dt id=\"reexport.{name}\">"))?;
+    let entry = &index[start..];
+    let entry = &entry[..entry.find("</dt>")?];
+    let href = &entry[entry.rfind("href=\"")? + "href=\"".len()..];
+    let href = &href[..href.find('"')?];
+    follow(normalize(&dir.join(href)))
+}
+
+// Rustdoc leaves a page that redirects to the real one at some paths an item
+// is reachable through, like core/ptr/non_null/struct.NonNull.html.
+fn follow(mut page: PathBuf) -> Option<PathBuf> {
+    for _ in 0..8 {
+        let html = fs::read_to_string(&page).ok()?;
+        let Some(start) = html.find("http-equiv=\"refresh\"") else {
+            return Some(page);
+        };
+        let target = &html[start..];
+        let target = &target[target.find("URL=")? + "URL=".len()..];
+        let target = &target[..target.find('"')?];
+        page = normalize(&page.parent()?.join(target));
+    }
+    None
+}
+
+// The anchor of `name` on the page of a type or trait, as in `method.map`.
+fn member(page: &Path, name: &str) -> Option<String> {
+    let html = fs::read_to_string(page).ok()?;
+    MEMBERS
+        .iter()
+        .map(|kind| format!("{kind}.{name}"))
+        .find(|anchor| html.contains(&format!("id=\"{anchor}\"")))
+}
+
+// Removes `.` and `..` from a path without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// The documentation of the standard library in the sysroot of the toolchain.
+fn std_html() -> &'static Result<PathBuf, String> {
+    static HTML: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    HTML.get_or_init(|| {
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc)
+            .args(["--print", "sysroot"])
+            .output()
+            .map_err(|err| format!("failed to run rustc: {err}"))?;
+        if !output.status.success() {
+            return Err("`rustc --print sysroot` failed".to_owned());
+        }
+        let sysroot = String::from_utf8_lossy(&output.stdout);
+        let html = Path::new(sysroot.trim()).join("share/doc/rust/html");
+        if html.join("std/index.html").is_file() {
+            Ok(html)
+        } else {
+            Err(format!(
+                "{} has no standard library documentation, install it with `rustup component add rust-docs`",
+                html.display(),
+            ))
+        }
+    })
+}
+
+// Documentation of futures 0.1 generated under target/essay from the newest
+// 0.1 release in the cargo registry, with the crate's default features.
+fn futures_html() -> &'static Result<PathBuf, String> {
+    static HTML: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    HTML.get_or_init(|| {
+        let src = futures_src()?;
+        let out = Path::new("target")
+            .join("essay")
+            .join(src.file_name().unwrap_or_default());
+        if out.join("futures/index.html").is_file() {
+            return Ok(out);
+        }
+        // Generated next to where it goes and then moved into place, so that
+        // two runs at the same time never see a partial copy.
+        let tmp = out.with_extension(process::id().to_string());
+        let rustdoc = env::var_os("RUSTDOC").unwrap_or_else(|| "rustdoc".into());
+        let output = Command::new(rustdoc)
+            .args([
+                "--edition=2015",
+                "--crate-name=futures",
+                "--cap-lints=allow",
+            ])
+            .args([
+                "--cfg",
+                "feature=\"use_std\"",
+                "--cfg",
+                "feature=\"with-deprecated\"",
+            ])
+            .arg("--out-dir")
+            .arg(&tmp)
+            .arg(src.join("src/lib.rs"))
+            .output()
+            .map_err(|err| format!("failed to run rustdoc: {err}"))?;
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&tmp);
+            return Err(format!("rustdoc failed to document {}", src.display()));
+        }
+        if fs::rename(&tmp, &out).is_err() {
+            let _ = fs::remove_dir_all(&tmp);
+            if !out.join("futures/index.html").is_file() {
+                return Err(format!("failed to create {}", out.display()));
+            }
+        }
+        Ok(out)
+    })
+}
+
+// The source of the newest futures 0.1 release that cargo has downloaded.
+fn futures_src() -> Result<PathBuf, String> {
+    let cargo_home = env::var_os("CARGO_HOME").map_or_else(
+        || {
+            env::var_os("HOME")
+                .map(|home| Path::new(&home).join(".cargo"))
+                .unwrap_or_default()
+        },
+        PathBuf::from,
+    );
+    let registry = cargo_home.join("registry").join("src");
+    let mut newest: Option<(u64, PathBuf)> = None;
+    for index in fs::read_dir(&registry).into_iter().flatten().flatten() {
+        for package in fs::read_dir(index.path()).into_iter().flatten().flatten() {
+            let name = package.file_name();
+            let Some(patch) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("futures-0.1."))
+                .and_then(|patch| patch.parse().ok())
+            else {
+                continue;
+            };
+            if newest.as_ref().is_none_or(|(newest, _)| patch > *newest) {
+                newest = Some((patch, package.path()));
+            }
+        }
+    }
+    newest.map(|(_, path)| path).ok_or_else(|| {
+        format!(
+            "futures 0.1 is not in {}, download it with `cargo fetch`",
+            registry.display(),
+        )
+    })
+}
+
+// Removes what rustdoc allows around a path: a `struct@`-style disambiguator,
+// a trailing `()` or `!`, generic arguments, and surrounding backticks.
+fn strip_decorations(target: &str) -> &str {
+    let mut target = target.trim().trim_matches('`');
+    if let Some((prefix, rest)) = target.split_once('@') {
+        if prefix.chars().all(|ch| ch.is_ascii_lowercase()) {
+            target = rest;
+        }
+    }
+    target = target.strip_suffix("()").unwrap_or(target);
+    target = target.strip_suffix('!').unwrap_or(target);
+    if let Some(generics) = target.find('<') {
+        if target.ends_with('>') {
+            target = &target[..generics];
+        }
+    }
+    target
+}
@@ -0,0 +1,141 @@
+// A static site with the same content as the docs.rs rendering of the crate:
+// an index page listing the essays and one page per essay.
+
+use crate::doc;
+use crate::essays::{Error, Essay};
+use crate::html::{self, Renderer};
+use crate::markdown;
+use std::fmt::Write as _;
+
+pub struct Site {
+    // From the crate-level doc comment in src/lib.rs.
+    pub title: String,
+    // Contents of src/html-in-header, which Cargo.toml passes to rustdoc.
+    pub head: String,
+}
+
+const STYLE: &str = "\
+body {
+  margin: 0 auto;
+  max-width: 50em;
+  padding: 1em 2em 4em;
+  font: 17px/1.5 Georgia, serif;
+  color: #222;
+}
+nav {
+  font-family: sans-serif;
+  font-size: 15px;
+  margin-bottom: 2em;
+}
+a {
+  color: #2a6496;
+}
+h1, h2, h3 {
+  font-family: sans-serif;
+  line-height: 1.2;
+}
+h2 {
+  margin-top: 2em;
+}
+code, pre {
+  font: 14px/1.4 Menlo, Consolas, monospace;
+}
+code {
+  background: #f5f5f5;
+  border-radius: 3px;
+  padding: 0 .2em;
+}
+pre {
+  background: #f5f5f5;
+  overflow-x: auto;
+  padding: .7em 1em;
+}
+pre code {
+  padding: 0;
+}
+blockquote {
+  border-left: 3px solid #ccc;
+  margin-left: 0;
+  padding-left: 1em;
+}
+hr {
+  border: none;
+  margin: 2.5em 0;
+}
+nav.contents ul {
+  margin: 0;
+  padding-left: 1.2em;
+}
+ul.essays {
+  list-style: none;
+  padding: 0;
+}
+ul.essays .date {
+  color: #777;
+  margin-left: .5em;
+}
+.rust .kw { color: #8959a8; }
+.rust .string { color: #718c00; }
+.rust .number { color: #f5871f; }
+.rust .comment { color: #8e908c; }
+.rust .lifetime { color: #b76514; }
+.rust .macro { color: #3e999f; }
+.rust .type { color: #ad7c37; }
+";
+
+impl Site {
+    // Every file of the site by name.
+    pub fn files(&self, essays: &[Essay]) -> Result<Vec<(String, String)>, Error> {
+        let mut files = vec![
+            ("index.html".to_owned(), self.index(essays)),
+            ("style.css".to_owned(), STYLE.to_owned()),
+        ];
+        for essay in essays {
+            files.push((format!("{}.html", essay.stem()), self.essay(essay)?));
+        }
+        Ok(files)
+    }
+
+    fn index(&self, essays: &[Essay]) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<ul class=\"essays\">\n",
+            html::escape(&self.title)
+        );
+        for essay in essays {
+            let _ = writeln!(
+                body,
+                "<li><a href=\"{}.html\">{}</a><span class=\"date\">{}</span></li>",
+                essay.stem(),
+                html::escape(&essay.title),
+                html::escape(&essay.published),
+            );
+        }
+        body.push_str("</ul>\n");
+        self.page(&self.title, &body)
+    }
+
+    fn essay(&self, essay: &Essay) -> Result<String, Error> {
+        let blocks = markdown::parse(&doc::markdown(essay)?);
+        let body = Renderer::new().blocks(&blocks);
+        let nav = format!(
+            "<nav><a href=\"index.html\">{}</a></nav>\n",
+            html::escape(&self.title),
+        );
+        Ok(self.page(&essay.title, &(nav + &body)))
+    }
+
+    fn page(&self, title: &str, body: &str) -> String {
+        let mut page = String::new();
+        page.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        page.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        for line in self.head.lines().filter(|line| !line.trim().is_empty()) {
+            page.push_str(line);
+            page.push('\n');
+        }
+        let _ = writeln!(page, "<title>{}</title>", html::escape(title));
+        page.push_str("<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n<main>\n");
+        page.push_str(body);
+        page.push_str("</main>\n</body>\n</html>\n");
+        page
+    }
+}
@@ -0,0 +1,177 @@
+// Plain text wrapped for email newsletters. Links become numbered references
+// like "announced [1]", listed with their URLs at the end of the essay.
+
+use crate::doc;
+use crate::essays::{Error, Essay};
+use crate::markdown::{self, Block, Inline};
+use std::fmt::Write as _;
+
+const WIDTH: usize = 72;
+
+// Joins a link's reference number to the word before it so that wrapping never
+// puts the number at the start of a line. Becomes a space once wrapped.
+const GLUE: char = '\u{a0}';
+
+pub fn plain(essay: &Essay) -> Result<String, Error> {
+    let blocks = markdown::parse(&doc::markdown(essay)?);
+    let mut renderer = Renderer { urls: Vec::new() };
+    let mut out = String::new();
+    for line in renderer.blocks(&blocks, WIDTH, false) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if !renderer.urls.is_empty() {
+        out.push('\n');
+        for (i, url) in renderer.urls.iter().enumerate() {
+            let _ = writeln!(out, "[{}] {}", i + 1, url);
+        }
+    }
+    Ok(out)
+}
+
+struct Renderer {
+    // Links by reference number, starting from 1.
+    urls: Vec<String>,
+}
+
+impl Renderer {
+    // Blocks are separated by a blank line, except inside the items of a tight
+    // list.
+    fn blocks(&mut self, blocks: &[Block], width: usize, tight: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        for block in blocks {
+            let block = self.block(block, width);
+            if block.is_empty() {
+                continue;
+            }
+            if !tight && !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(block);
+        }
+        lines
+    }
+
+    fn block(&mut self, block: &Block, width: usize) -> Vec<String> {
+        match block {
+            Block::Heading { level, content } => {
+                let text = self.inlines(content).replace(GLUE, " ");
+                let underline = match level {
+                    1 => "=",
+                    2 => "-",
+                    _ => return vec![text],
+                };
+                let underline = underline.repeat(text.chars().count());
+                vec![text, underline]
+            }
+            Block::Paragraph(content) => wrap(&self.inlines(content), width),
+            Block::Code { code, .. } => code
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("    {line}")
+                    }
+                })
+                .collect(),
+            Block::Quote(blocks) => self
+                .blocks(blocks, width.saturating_sub(2), false)
+                .into_iter()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_owned()
+                    } else {
+                        format!("> {line}")
+                    }
+                })
+                .collect(),
+            Block::List(list) => {
+                let mut lines = Vec::new();
+                for (i, item) in list.items.iter().enumerate() {
+                    if list.loose && i > 0 {
+                        lines.push(String::new());
+                    }
+                    let marker = match list.start {
+                        Some(start) => format!("{}. ", start + i),
+                        None => "- ".to_owned(),
+                    };
+                    let indent = " ".repeat(marker.len());
+                    let body = self.blocks(item, width.saturating_sub(marker.len()), !list.loose);
+                    for (j, line) in body.into_iter().enumerate() {
+                        if j == 0 {
+                            lines.push(marker.clone() + &line);
+                        } else if line.is_empty() {
+                            lines.push(line);
+                        } else {
+                            lines.push(indent.clone() + &line);
+                        }
+                    }
+                }
+                lines
+            }
+            // Only meaningful to renderers that speak HTML.
+            Block::Html(_) => Vec::new(),
+            Block::Rule => vec!["* * *".to_owned()],
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) -> String {
+        let mut out = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => out.push_str(&text.replace('\n', " ")),
+                Inline::Code(code) => {
+                    let _ = write!(out, "`{code}`");
+                }
+                Inline::Emphasis(content) => {
+                    let _ = write!(out, "_{}_", self.inlines(content));
+                }
+                Inline::Strong(content) => {
+                    let _ = write!(out, "*{}*", self.inlines(content));
+                }
+                Inline::Link { url, content } => {
+                    let label = self.inlines(content);
+                    out.push_str(&label);
+                    // Sections of the same essay, like the table of contents,
+                    // need no reference.
+                    if label != *url && !url.starts_with('#') {
+                        if !self.urls.contains(url) {
+                            self.urls.push(url.clone());
+                        }
+                        let number = 1 + self.urls.iter().position(|u| u == url).unwrap();
+                        let _ = write!(out, "{GLUE}[{number}]");
+                    }
+                }
+                Inline::Html(_) => {}
+            }
+        }
+        out
+    }
+}
+
+// Greedy wrapping at spaces. Words longer than the width, such as URLs, get a
+// line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut len = 0;
+    for word in text.split(' ').filter(|word| !word.is_empty()) {
+        let word_len = word.chars().count();
+        if len > 0 && len + 1 + word_len > width {
+            lines.push(line.replace(GLUE, " "));
+            line.clear();
+            len = 0;
+        }
+        if len > 0 {
+            line.push(' ');
+            len += 1;
+        }
+        line.push_str(word);
+        len += word_len;
+    }
+    if len > 0 {
+        lines.push(line.replace(GLUE, " "));
+    }
+    lines
+}
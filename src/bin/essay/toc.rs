@@ -0,0 +1,142 @@
+// The table of contents of an essay: links to its `##` sections, kept in the
+// doc comment right below the byline so that the docs.rs page has it as well
+// as every other rendering. `essay toc` regenerates it when headings change.
+//
+// The anchor tests include this module as well, so that they check the same
+// anchors that the table of contents links to.
+
+use crate::anchors::Anchors;
+use crate::essays;
+use crate::markdown::{self, Block, Inline};
+use std::fmt::Write as _;
+
+const OPEN: &str = "<nav class=\"contents\">";
+const CLOSE: &str = "</nav>";
+
+pub struct Heading<'a> {
+    pub level: usize,
+    pub content: &'a [Inline],
+    // As assigned by rustdoc, like "borrowing-1".
+    pub anchor: String,
+}
+
+// Every heading of a document in order, including those nested in quotes and
+// lists. Anchors are counted over all headings, the way rustdoc does.
+pub fn headings(blocks: &[Block]) -> Vec<Heading<'_>> {
+    fn walk<'a>(anchors: &mut Anchors, blocks: &'a [Block], out: &mut Vec<Heading<'a>>) {
+        for block in blocks {
+            match block {
+                Block::Heading { level, content } => out.push(Heading {
+                    level: *level,
+                    content,
+                    anchor: anchors.next(&markdown::plain_text(content)),
+                }),
+                Block::Quote(blocks) => walk(anchors, blocks, out),
+                Block::List(list) => {
+                    for item in &list.items {
+                        walk(anchors, item, out);
+                    }
+                }
+                Block::Paragraph(_) | Block::Code { .. } | Block::Html(_) | Block::Rule => {}
+            }
+        }
+    }
+
+    let mut headings = Vec::new();
+    walk(&mut Anchors::new(), blocks, &mut headings);
+    headings
+}
+
+// The table of contents for the text of a doc comment, or nothing if it has no
+// sections. A heading that is nothing but a link, like the
+// `## [https://areweasyncyet.rs/]` that closes an essay, points elsewhere
+// rather than starting a section.
+pub fn contents(doc: &str) -> String {
+    let blocks = markdown::parse(doc);
+    let mut items = String::new();
+    for heading in headings(&blocks) {
+        if heading.level == 2 && !matches!(heading.content, [Inline::Link { .. }]) {
+            let text = link_text(heading.content);
+            let _ = writeln!(items, "- [{text}](#{})", heading.anchor);
+        }
+    }
+    if items.is_empty() {
+        return String::new();
+    }
+    format!("{OPEN}\n\n{items}\n{CLOSE}\n")
+}
+
+// The source of an essay with its table of contents brought up to date.
+pub fn update(src: &str) -> Result<String, String> {
+    let (doc, _) = essays::doc_comment(src)?;
+    let contents = contents(doc);
+    let lines: Vec<&str> = src.split_inclusive('\n').collect();
+    let byline = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with("<sup>"))
+        .ok_or("essay has no `<sup>` byline to put the table of contents under")?;
+
+    // An earlier table of contents, after the blank line below the byline.
+    let mut rest = byline + 1;
+    let mut next = rest;
+    while lines.get(next).is_some_and(|line| line.trim().is_empty()) {
+        next += 1;
+    }
+    if lines.get(next).is_some_and(|line| line.trim() == OPEN) {
+        let close = (next..lines.len())
+            .find(|&i| lines[i].trim() == CLOSE)
+            .ok_or("table of contents is never closed by `</nav>`")?;
+        rest = close + 1;
+    }
+
+    let mut out = lines[..=byline].concat();
+    if !contents.is_empty() {
+        out.push('\n');
+        out.push_str(&contents);
+    }
+    out.push_str(&lines[rest..].concat());
+    Ok(out)
+}
+
+// Heading text as the text of a Markdown link. Code spans are kept; other
+// markup, including links, which cannot nest in a link, is reduced to its text.
+fn link_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(string) => {
+                for ch in string.chars() {
+                    if "\\`*_[]<>&".contains(ch) {
+                        text.push('\\');
+                    }
+                    text.push(if ch == '\n' { ' ' } else { ch });
+                }
+            }
+            Inline::Code(code) => text.push_str(&code_span(code)),
+            Inline::Emphasis(content) | Inline::Strong(content) | Inline::Link { content, .. } => {
+                text.push_str(&link_text(content));
+            }
+            Inline::Html(_) => {}
+        }
+    }
+    text
+}
+
+// A code span containing `code`, delimited by more backticks than any run of
+// them inside it. The padding that CommonMark strips keeps a leading or
+// trailing backtick or space from being taken as part of the delimiter.
+fn code_span(code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in code.chars() {
+        run = if ch == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let ticks = "`".repeat(longest + 1);
+    let pad = if code.starts_with(['`', ' ']) || code.ends_with(['`', ' ']) {
+        " "
+    } else {
+        ""
+    };
+    format!("{ticks}{pad}{code}{pad}{ticks}")
+}
@@ -0,0 +1,114 @@
+// Just enough of the zip format to package an EPUB: entries are stored
+// without compression, which every reader supports, and with a fixed
+// timestamp so that the same input always produces the same archive.
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+#[derive(Default)]
+pub struct Writer {
+    out: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+// 1980-01-01 00:00:00, the earliest date representable in a zip.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = 1 << 5 | 1;
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer::default()
+    }
+
+    pub fn file(&mut self, name: &str, data: &[u8]) {
+        let size = u32::try_from(data.len()).expect("zip entry larger than 4 GiB");
+        let entry = Entry {
+            name: name.to_owned(),
+            crc: crc32(data),
+            size,
+            offset: self.position(),
+        };
+        self.u32(0x0403_4b50);
+        self.u16(10); // version needed to extract
+        self.u16(0); // flags
+        self.u16(0); // stored
+        self.u16(DOS_TIME);
+        self.u16(DOS_DATE);
+        self.u32(entry.crc);
+        self.u32(size);
+        self.u32(size);
+        self.u16(name_len(name));
+        self.u16(0); // extra field length
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(data);
+        self.entries.push(entry);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let start = self.position();
+        let entries = std::mem::take(&mut self.entries);
+        for entry in &entries {
+            self.u32(0x0201_4b50);
+            self.u16(20); // version made by
+            self.u16(10); // version needed to extract
+            self.u16(0); // flags
+            self.u16(0); // stored
+            self.u16(DOS_TIME);
+            self.u16(DOS_DATE);
+            self.u32(entry.crc);
+            self.u32(entry.size);
+            self.u32(entry.size);
+            self.u16(name_len(&entry.name));
+            self.u16(0); // extra field length
+            self.u16(0); // comment length
+            self.u16(0); // disk number
+            self.u16(0); // internal attributes
+            self.u32(0); // external attributes
+            self.u32(entry.offset);
+            self.out.extend_from_slice(entry.name.as_bytes());
+        }
+        let size = self.position() - start;
+        let count = u16::try_from(entries.len()).expect("too many zip entries");
+        self.u32(0x0605_4b50);
+        self.u16(0); // this disk
+        self.u16(0); // disk with the central directory
+        self.u16(count);
+        self.u16(count);
+        self.u32(size);
+        self.u32(start);
+        self.u16(0); // comment length
+        self.out
+    }
+
+    fn position(&self) -> u32 {
+        u32::try_from(self.out.len()).expect("zip larger than 4 GiB")
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn name_len(name: &str) -> u16 {
+    u16::try_from(name.len()).expect("zip entry name too long")
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,220 @@
+//! Reports every `unsafe fn` and `unsafe {}` block that is not accompanied by
+//! a comment explaining why it is sound, following the convention of
+//! `frob_unchecked` in the soundness bugs essay.
+//!
+//! ```console
+//! $ cargo run --bin safety-audit -- src
+//! src/ffi.rs:14: unsafe fn `frob` has no `// Safety:` comment
+//! src/lib.rs:22: unsafe block has no `// Safety:` comment
+//! ```
+//!
+//! An unsafe fn is accepted if the comments directly above it, skipping over
+//! attributes, include one that begins with `Safety:` or a `# Safety` section
+//! in its doc comment. An unsafe block is accepted if a comment beginning with
+//! `Safety:` is on the lines directly above it, on the same line, or first
+//! inside the block. Either spelling, `Safety:` or `SAFETY:`, is fine. The
+//! exit code is 1 if anything was reported.
+
+#[path = "common/files.rs"]
+mod files;
+#[path = "common/lexer.rs"]
+mod lexer;
+
+use crate::lexer::{Kind, Token};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+struct Lines<'a> {
+    code: BTreeSet<usize>,
+    attrs: BTreeSet<usize>,
+    comments: BTreeMap<usize, Vec<&'a str>>,
+}
+
+fn main() {
+    let mut roots: Vec<PathBuf> = env::args_os().skip(1).map(PathBuf::from).collect();
+    if roots.is_empty() {
+        roots.push(PathBuf::from("."));
+    }
+
+    let mut files = Vec::new();
+    for root in &roots {
+        if let Err(err) = files::collect(root, &mut files) {
+            eprintln!("error: {}: {}", root.display(), err);
+            process::exit(2);
+        }
+    }
+
+    let mut findings = 0;
+    for path in &files {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(2);
+            }
+        };
+        let tokens = match lexer::tokenize(&src) {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(2);
+            }
+        };
+        for (line, what) in audit(&tokens) {
+            println!(
+                "{}:{}: {} has no `// Safety:` comment",
+                path.display(),
+                line,
+                what
+            );
+            findings += 1;
+        }
+    }
+
+    if findings > 0 {
+        eprintln!();
+        eprintln!(
+            "{} unsafe {} without a safety comment",
+            findings,
+            if findings == 1 { "item" } else { "items" },
+        );
+        process::exit(1);
+    }
+}
+
+fn audit(tokens: &[Token]) -> Vec<(usize, String)> {
+    let lines = Lines::new(tokens);
+    let code: Vec<(usize, &Token)> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !token.is_comment())
+        .collect();
+
+    let mut findings = Vec::new();
+    for (i, &(_, token)) in code.iter().enumerate() {
+        if token.kind != Kind::Ident || token.text != "unsafe" {
+            continue;
+        }
+        let next = |n: usize| code.get(i + n).map(|&(_, token)| token);
+        if next(1).is_some_and(|next| next.is("{")) {
+            let first_inside = tokens.get(code[i + 1].0 + 1);
+            let justified = lines.justified_above(token.line, false)
+                || lines.justified_on(token.line)
+                || first_inside.is_some_and(|first| first.is_comment() && is_safety(first.text));
+            if !justified {
+                findings.push((token.line, "unsafe block".to_owned()));
+            }
+            continue;
+        }
+
+        let mut n = 1;
+        if next(n).is_some_and(|next| next.is("extern")) {
+            n += 1;
+            if next(n).is_some_and(|next| next.kind == Kind::Literal) {
+                n += 1;
+            }
+        }
+        if !next(n).is_some_and(|next| next.is("fn")) {
+            continue;
+        }
+        let name = next(n + 1).map_or("", |name| name.text);
+        let start = item_start(&code, i);
+        if !lines.justified_above(start, true) {
+            findings.push((start, format!("unsafe fn `{name}`")));
+        }
+    }
+    findings
+}
+
+// Line on which the item containing the `unsafe` keyword at code[i] begins,
+// including any visibility and qualifiers before it.
+fn item_start(code: &[(usize, &Token)], mut i: usize) -> usize {
+    while i > 0 {
+        let prev = code[i - 1].1;
+        if ["pub", "const", "async", "default"]
+            .iter()
+            .any(|keyword| prev.is(keyword))
+        {
+            i -= 1;
+        } else if prev.is(")") {
+            let Some(open) = code[..i].iter().rposition(|(_, token)| token.is("(")) else {
+                break;
+            };
+            if open == 0 || !code[open - 1].1.is("pub") {
+                break;
+            }
+            i = open - 1;
+        } else {
+            break;
+        }
+    }
+    code[i].1.line
+}
+
+impl<'a> Lines<'a> {
+    fn new(tokens: &[Token<'a>]) -> Self {
+        let mut code = BTreeSet::new();
+        let mut attrs = BTreeSet::new();
+        let mut comments: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+        for token in tokens {
+            if token.is_comment() {
+                let end = token.line + token.text.matches('\n').count();
+                for line in token.line..=end {
+                    comments.entry(line).or_default().push(token.text);
+                }
+            } else if code.insert(token.line) && token.is("#") {
+                attrs.insert(token.line);
+            }
+        }
+        Lines {
+            code,
+            attrs,
+            comments,
+        }
+    }
+
+    // Whether a safety comment appears in the run of comment lines directly
+    // above this line, optionally looking past attributes.
+    fn justified_above(&self, line: usize, skip_attrs: bool) -> bool {
+        let mut line = line;
+        while line > 1 {
+            line -= 1;
+            if skip_attrs && self.attrs.contains(&line) {
+                continue;
+            }
+            if self.code.contains(&line) {
+                return false;
+            }
+            let Some(comments) = self.comments.get(&line) else {
+                return false;
+            };
+            if comments.iter().any(|comment| is_safety(comment)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn justified_on(&self, line: usize) -> bool {
+        self.comments
+            .get(&line)
+            .is_some_and(|comments| comments.iter().any(|comment| is_safety(comment)))
+    }
+}
+
+// Whether a comment begins with "Safety:" on any of its lines or is a doc
+// comment containing a "# Safety" section.
+fn is_safety(comment: &str) -> bool {
+    let body = comment
+        .trim_start_matches('/')
+        .trim_start_matches(['*', '!'])
+        .trim_end_matches("*/");
+    body.lines().any(|line| {
+        let line = line.trim().trim_start_matches('*').trim_start();
+        let lower = line.to_ascii_lowercase();
+        lower.starts_with("safety:") || lower == "# safety"
+    })
+}
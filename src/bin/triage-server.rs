@@ -0,0 +1,363 @@
+//! Local stand-in for the Triagebot notification service, for trying out the
+//! triage companion module end to end without GitHub or Zulip.
+//!
+//! ```console
+//! $ cargo run --bin triage-server -- --addr 127.0.0.1:8000 notifications.log
+//! listening on http://127.0.0.1:8000
+//! ```
+//!
+//! - `GET /` lists everyone who has a list.
+//! - `GET /USER` is the user's page as HTML, `GET /USER.json` is the same list
+//!   as JSON, and `GET /USER.md` is the markdown for a PR comment.
+//! - `POST /command` runs the chat command in the request body, such as
+//!   `@dtolnay add https://github.com/dtolnay/syn/issues/700 Question`, on
+//!   behalf of the editor named by the `X-Editor` header. It responds with the
+//!   edited list as JSON, or with status 400 and the error message if the
+//!   command is malformed or does not apply to the list.
+//!
+//! Edits are appended to the file given on the command line, which may be
+//! shared with other instances of the server. Port 0 picks any free port; the
+//! address actually bound is printed on startup. A request with a line longer
+//! than 8 KiB is rejected, and a client that stalls for 10 seconds is
+//! disconnected.
+
+use dtolnay::triage_scale::command::Command;
+use dtolnay::triage_scale::notifications::{self, Item, Notifications};
+use dtolnay::triage_scale::render;
+use dtolnay::triage_scale::store::{self, Store};
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const MAX_BODY: usize = 64 * 1024;
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+// How long a client may leave the server waiting on any one read or write.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Request {
+    method: String,
+    path: String,
+    editor: Option<String>,
+    body: String,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+fn main() {
+    let mut addr = "127.0.0.1:8000".to_owned();
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--addr" {
+            addr = args.next().unwrap_or_else(|| usage());
+        } else if let Some(value) = arg.strip_prefix("--addr=") {
+            value.clone_into(&mut addr);
+        } else if arg.starts_with('-') || path.is_some() {
+            usage();
+        } else {
+            path = Some(arg);
+        }
+    }
+    let Some(path) = path else {
+        usage();
+    };
+
+    let store = Store::open(&path).unwrap_or_else(|err| {
+        eprintln!("error: {path}: {err}");
+        process::exit(1);
+    });
+    let listener = TcpListener::bind(&addr).unwrap_or_else(|err| {
+        eprintln!("error: {addr}: {err}");
+        process::exit(1);
+    });
+    let local_addr = listener.local_addr().unwrap();
+    println!("listening on http://{local_addr}");
+    let _ = io::stdout().flush();
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let store = store.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve(&stream, &store) {
+                eprintln!("error: {err}");
+            }
+        });
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: triage-server [--addr HOST:PORT] <STORE>");
+    process::exit(2);
+}
+
+fn serve(stream: &TcpStream, store: &Store) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let request = read_request(&mut reader)?;
+    let response = match &request {
+        Some(request) => route(request, store),
+        None => text("400 Bad Request", "malformed request\n".to_owned()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len(),
+    )?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()?;
+    if request.is_none() {
+        // Closing with part of the request unread would reset the connection
+        // and could discard the response before the client reads it.
+        stream.shutdown(Shutdown::Write)?;
+        let limit = u64::try_from(MAX_BODY).unwrap_or(u64::MAX);
+        io::copy(&mut reader.take(limit), &mut io::sink())?;
+    }
+    Ok(())
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+    if !read_line(reader, &mut line)? {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let method = method.to_owned();
+    let path = path.to_owned();
+
+    let mut content_length = 0;
+    let mut editor = None;
+    for i in 0.. {
+        line.clear();
+        if i == MAX_HEADERS || !read_line(reader, &mut line)? {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Ok(None);
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            match value.parse() {
+                Ok(len) if len <= MAX_BODY => content_length = len,
+                _ => return Ok(None),
+            }
+        } else if name.eq_ignore_ascii_case("x-editor") {
+            editor = Some(value.to_owned());
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(None);
+    };
+    Ok(Some(Request {
+        method,
+        path,
+        editor,
+        body,
+    }))
+}
+
+// Reads one line including its newline, or returns false at the end of the
+// stream or if the line is longer than MAX_LINE.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    let limit = u64::try_from(MAX_LINE).unwrap_or(u64::MAX);
+    reader.take(limit).read_line(line)?;
+    Ok(line.ends_with('\n'))
+}
+
+fn route(request: &Request, store: &Store) -> Response {
+    let path = request.path.split('?').next().unwrap_or_default();
+    match (request.method.as_str(), path) {
+        ("POST", "/command") => command(request, store),
+        ("GET", "/") => load(store, |notifications| html("200 OK", index(notifications))),
+        ("GET", path) => {
+            let Some((user, ext)) = page(path) else {
+                return not_found();
+            };
+            load(store, |notifications| {
+                let list = notifications.list(user);
+                match ext {
+                    "json" => json("200 OK", list_json(user, list)),
+                    "md" => Response {
+                        status: "200 OK",
+                        content_type: "text/markdown; charset=utf-8",
+                        body: render::markdown(user, list, Some(&format!("/{user}"))),
+                    },
+                    _ => html("200 OK", render::html(user, list)),
+                }
+            })
+        }
+        (_, path) if path == "/" || page(path).is_some() => {
+            text("405 Method Not Allowed", "method not allowed\n".to_owned())
+        }
+        (_, _) => not_found(),
+    }
+}
+
+// The user and extension of a user's page, like `/dtolnay.json`, with an
+// empty extension for the HTML page.
+fn page(path: &str) -> Option<(&str, &str)> {
+    let page = path.strip_prefix('/')?;
+    let (user, ext) = page.rsplit_once('.').unwrap_or((page, ""));
+    let valid = |ch: char| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_';
+    if user.is_empty() || !user.chars().all(valid) || !["", "json", "md"].contains(&ext) {
+        return None;
+    }
+    Some((user, ext))
+}
+
+fn command(request: &Request, store: &Store) -> Response {
+    let Some(editor) = request
+        .editor
+        .as_deref()
+        .filter(|editor| !editor.is_empty())
+    else {
+        return text("400 Bad Request", "missing X-Editor header\n".to_owned());
+    };
+    let command: Command = match request.body.trim().parse() {
+        Ok(command) => command,
+        Err(err) => return text("400 Bad Request", format!("{err}\n")),
+    };
+    let user = command.user.clone();
+    let edit = command.into_edit(editor, notifications::now());
+    match store.apply(edit) {
+        Ok(notifications) => json("200 OK", list_json(&user, notifications.list(&user))),
+        Err(store::Error::Edit(err)) => text("400 Bad Request", format!("{err}\n")),
+        Err(err) => internal_error(&err),
+    }
+}
+
+fn load(store: &Store, f: impl FnOnce(&Notifications) -> Response) -> Response {
+    match store.load() {
+        Ok(notifications) => f(&notifications),
+        Err(err) => internal_error(&err),
+    }
+}
+
+fn index(notifications: &Notifications) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Notifications</title>\n</head>\n<body>\n<h1>Notifications</h1>\n<ul>\n");
+    for user in notifications.users() {
+        let len = notifications.list(user).len();
+        let _ = writeln!(
+            out,
+            "<li><a href=\"/{}\">{}</a> ({len})</li>",
+            percent_encode(user),
+            render::escape_html(user),
+        );
+    }
+    out.push_str("</ul>\n</body>\n</html>\n");
+    out
+}
+
+// A user name as a single path segment. Everything but the unreserved
+// characters of RFC 3986 is encoded, so the result is safe in an attribute too.
+fn percent_encode(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+fn list_json(user: &str, list: &[Item]) -> String {
+    let mut out = format!("{{\"user\":{},\"items\":[", json_string(user));
+    for (i, item) in list.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let tags: Vec<String> = item.tags.iter().map(|tag| json_string(tag)).collect();
+        let _ = write!(
+            out,
+            "{{\"url\":{},\"description\":{},\"added_by\":{},\"added_at\":{},\"tags\":[{}]}}",
+            json_string(&item.url),
+            json_string(&item.description),
+            json_string(&item.added_by),
+            item.added_at,
+            tags.join(","),
+        );
+    }
+    out.push_str("]}\n");
+    out
+}
+
+fn json_string(string: &str) -> String {
+    let mut out = String::with_capacity(string.len() + 2);
+    out.push('"');
+    for ch in string.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if u32::from(ch) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(ch));
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn html(status: &'static str, body: String) -> Response {
+    Response {
+        status,
+        content_type: "text/html; charset=utf-8",
+        body,
+    }
+}
+
+fn json(status: &'static str, body: String) -> Response {
+    Response {
+        status,
+        content_type: "application/json",
+        body,
+    }
+}
+
+fn text(status: &'static str, body: String) -> Response {
+    Response {
+        status,
+        content_type: "text/plain; charset=utf-8",
+        body,
+    }
+}
+
+fn not_found() -> Response {
+    text("404 Not Found", "not found\n".to_owned())
+}
+
+fn internal_error(err: &store::Error) -> Response {
+    eprintln!("error: {err}");
+    text("500 Internal Server Error", format!("{err}\n"))
+}
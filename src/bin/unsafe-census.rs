@@ -0,0 +1,350 @@
+//! Counts the unsafe code in a Rust source tree, in the shape of the breakdown
+//! in *Where things stand* from the soundness bugs essay.
+//!
+//! ```console
+//! $ cargo run --bin unsafe-census -- --ffi C++=ffi --ffi C++=cxx::* --ffi Python=pyo3::* path/to/repo
+//! 523,118 lines of Rust code, 99.7% safe code.
+//!
+//! - 958 unsafe blocks — FFI to C++
+//! - 37 — FFI to Python
+//! - 93 — would exist even if the whole codebase were Rust
+//!
+//! 12 unsafe fns, 4 unsafe impls.
+//! ```
+//!
+//! Each `--ffi LANGUAGE=PATTERN` assigns unsafe blocks that call a function
+//! whose path matches PATTERN to the category "FFI to LANGUAGE". Patterns are
+//! matched against the path as written at the call site, either in full or
+//! against any leading run of its segments, and may contain `*` wildcards.
+//! Categories are tried in the order given on the command line. Blocks that
+//! match no pattern are the ones that would exist even if the whole codebase
+//! were Rust. Pass `--verbose` to list every unsafe block with its category.
+
+#[path = "common/files.rs"]
+mod files;
+#[path = "common/lexer.rs"]
+mod lexer;
+
+use crate::lexer::{Kind, Token, TokenTree};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const PURE_RUST: &str = "would exist even if the whole codebase were Rust";
+
+struct Category {
+    name: String,
+    patterns: Vec<String>,
+    blocks: usize,
+}
+
+#[derive(Default)]
+struct Census {
+    code_lines: usize,
+    unsafe_lines: usize,
+    unsafe_fns: usize,
+    unsafe_impls: usize,
+    pure_rust: usize,
+}
+
+struct Site {
+    line: usize,
+    calls: Vec<String>,
+}
+
+fn main() {
+    let mut categories: Vec<Category> = Vec::new();
+    let mut roots = Vec::new();
+    let mut verbose = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--verbose" {
+            verbose = true;
+        } else if arg == "--ffi" || arg.starts_with("--ffi=") {
+            let spec = match arg.strip_prefix("--ffi=") {
+                Some(spec) => spec.to_owned(),
+                None => args.next().unwrap_or_else(|| usage()),
+            };
+            let Some((language, pattern)) = spec.split_once('=') else {
+                usage();
+            };
+            let name = format!("FFI to {language}");
+            match categories.iter_mut().find(|category| category.name == name) {
+                Some(category) => category.patterns.push(pattern.to_owned()),
+                None => categories.push(Category {
+                    name,
+                    patterns: vec![pattern.to_owned()],
+                    blocks: 0,
+                }),
+            }
+        } else if arg.starts_with('-') {
+            usage();
+        } else {
+            roots.push(PathBuf::from(arg));
+        }
+    }
+    if roots.is_empty() {
+        roots.push(PathBuf::from("."));
+    }
+
+    let mut files = Vec::new();
+    for root in &roots {
+        if let Err(err) = files::collect(root, &mut files) {
+            eprintln!("error: {}: {}", root.display(), err);
+            process::exit(1);
+        }
+    }
+
+    let mut census = Census::default();
+    for path in &files {
+        let sites = match fs::read_to_string(path) {
+            Ok(src) => census.scan(&src).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        let sites = sites.unwrap_or_else(|err| {
+            eprintln!("error: {}: {}", path.display(), err);
+            process::exit(1);
+        });
+        for site in sites {
+            let name = census.classify(&site, &mut categories);
+            if verbose {
+                println!("{}:{}: {}", path.display(), site.line, name);
+            }
+        }
+    }
+    if verbose {
+        println!();
+    }
+
+    println!(
+        "{} lines of Rust code, {}% safe code.",
+        thousands(census.code_lines),
+        percent_safe(census.code_lines, census.unsafe_lines),
+    );
+    println!();
+    let rows = categories
+        .iter()
+        .map(|category| (category.blocks, category.name.as_str()))
+        .chain([(census.pure_rust, PURE_RUST)]);
+    for (i, (blocks, name)) in rows.enumerate() {
+        let unit = match (i, blocks) {
+            (0, 1) => " unsafe block",
+            (0, _) => " unsafe blocks",
+            _ => "",
+        };
+        println!("- {}{} — {}", thousands(blocks), unit, name);
+    }
+    println!();
+    println!(
+        "{} unsafe {}, {} unsafe {}.",
+        thousands(census.unsafe_fns),
+        if census.unsafe_fns == 1 { "fn" } else { "fns" },
+        thousands(census.unsafe_impls),
+        if census.unsafe_impls == 1 {
+            "impl"
+        } else {
+            "impls"
+        },
+    );
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: unsafe-census [--verbose] [--ffi LANGUAGE=PATTERN]... [PATH]...");
+    process::exit(2);
+}
+
+impl Census {
+    fn scan(&mut self, src: &str) -> Result<Vec<Site>, lexer::Error> {
+        let tokens = lexer::tokenize(src)?;
+        let trees = lexer::trees(&tokens)?;
+        let code_lines: BTreeSet<usize> = tokens
+            .iter()
+            .filter(|token| !token.is_comment())
+            .map(|token| token.line)
+            .collect();
+        self.code_lines += code_lines.len();
+
+        let mut unsafe_lines = BTreeSet::new();
+        let mut sites = Vec::new();
+        self.visit(&trees, &mut unsafe_lines, &mut sites);
+        self.unsafe_lines += unsafe_lines.len();
+        Ok(sites)
+    }
+
+    // The first category with a pattern matching any call made inside of the
+    // unsafe block.
+    fn classify<'c>(&mut self, site: &Site, categories: &'c mut [Category]) -> &'c str {
+        let category = categories.iter_mut().find(|category| {
+            site.calls.iter().any(|call| {
+                category
+                    .patterns
+                    .iter()
+                    .any(|pattern| path_matches(pattern, call))
+            })
+        });
+        if let Some(category) = category {
+            category.blocks += 1;
+            &category.name
+        } else {
+            self.pure_rust += 1;
+            PURE_RUST
+        }
+    }
+
+    fn visit(&mut self, trees: &[TokenTree], lines: &mut BTreeSet<usize>, sites: &mut Vec<Site>) {
+        for (i, tree) in trees.iter().enumerate() {
+            let TokenTree::Token(token) = tree else {
+                if let TokenTree::Group(group) = tree {
+                    self.visit(&group.trees, lines, sites);
+                }
+                continue;
+            };
+            if !token.is("unsafe") {
+                continue;
+            }
+            let next = trees.get(i + 1);
+            if let Some(TokenTree::Group(block)) = next.filter(|next| next.group("{").is_some()) {
+                let mut calls = Vec::new();
+                collect_calls(&block.trees, &mut calls);
+                sites.push(Site {
+                    line: token.line,
+                    calls,
+                });
+                lines.insert(token.line);
+                lines.insert(block.close.line);
+                unsafe_lines(&block.trees, lines);
+            } else if next.is_some_and(|next| next.is("impl")) {
+                self.unsafe_impls += 1;
+            } else if is_fn(&trees[i + 1..]) {
+                self.unsafe_fns += 1;
+                if let Some(TokenTree::Group(body)) = fn_body(&trees[i + 1..]) {
+                    lines.insert(token.line);
+                    lines.insert(body.close.line);
+                    unsafe_lines(&body.trees, lines);
+                }
+            }
+        }
+    }
+}
+
+fn is_fn(trees: &[TokenTree]) -> bool {
+    let mut i = 0;
+    if trees.first().is_some_and(|tree| tree.is("extern")) {
+        i += 1;
+        if trees
+            .get(i)
+            .is_some_and(|tree| tree.first().kind == Kind::Literal)
+        {
+            i += 1;
+        }
+    }
+    trees.get(i).is_some_and(|tree| tree.is("fn"))
+}
+
+// The body of the function whose signature begins these trees, or None for a
+// declaration without a body.
+fn fn_body<'t, 'a>(trees: &'t [TokenTree<'a>]) -> Option<&'t TokenTree<'a>> {
+    for tree in trees {
+        if tree.is(";") {
+            return None;
+        } else if tree.group("{").is_some() {
+            return Some(tree);
+        }
+    }
+    None
+}
+
+fn unsafe_lines(trees: &[TokenTree], lines: &mut BTreeSet<usize>) {
+    for tree in trees {
+        match tree {
+            TokenTree::Token(token) => {
+                lines.insert(token.line);
+            }
+            TokenTree::Group(group) => {
+                lines.insert(group.open.line);
+                lines.insert(group.close.line);
+                unsafe_lines(&group.trees, lines);
+            }
+        }
+    }
+}
+
+// Paths of every function called inside of an unsafe block, such as
+// `ffi::frob` in `unsafe { ffi::frob(arg) }`.
+fn collect_calls(trees: &[TokenTree], calls: &mut Vec<String>) {
+    let mut path: Vec<&Token> = Vec::new();
+    let mut method = false;
+    for tree in trees {
+        match tree {
+            // A method call like `.frob()` has no path to match against.
+            TokenTree::Token(token) if token.kind == Kind::Ident && method => method = false,
+            TokenTree::Token(token) if token.kind == Kind::Ident => {
+                if path.last().is_some_and(|last| last.kind == Kind::Ident) {
+                    path.clear();
+                }
+                path.push(token);
+            }
+            TokenTree::Token(token) if token.is("::") && !path.is_empty() => path.push(token),
+            TokenTree::Token(token) if token.is("::") => {}
+            TokenTree::Group(group) => {
+                let is_call = group.open.text == "("
+                    && path.last().is_some_and(|last| last.kind == Kind::Ident);
+                if is_call {
+                    calls.push(path.iter().map(|token| token.text).collect());
+                }
+                path.clear();
+                collect_calls(&group.trees, calls);
+            }
+            TokenTree::Token(token) => {
+                method = token.is(".");
+                path.clear();
+            }
+        }
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let segments: Vec<&str> = path.split("::").collect();
+    (1..=segments.len()).any(|n| glob(pattern.as_bytes(), segments[..n].join("::").as_bytes()))
+}
+
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        Some((ch, rest)) => text.first() == Some(ch) && glob(rest, &text[1..]),
+    }
+}
+
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+// Rounded to the fewest decimal places that still distinguish it from 100%
+// when any unsafe code exists, like "99.7" or "99.9994".
+fn percent_safe(total: usize, unsafe_lines: usize) -> String {
+    if total == 0 || unsafe_lines == 0 {
+        return "100".to_owned();
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let percent = 100.0 - 100.0 * unsafe_lines as f64 / total as f64;
+    let mut precision = 1;
+    loop {
+        let formatted = format!("{percent:.precision$}");
+        if !formatted.starts_with("100") || precision >= 10 {
+            return formatted;
+        }
+        precision += 1;
+    }
+}
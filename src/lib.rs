@@ -1,6 +1,6 @@
 //! Essays by David Tolnay
 
-#![allow(non_snake_case)]
+#![allow(non_snake_case, clippy::must_use_candidate)]
 #![doc(html_logo_url = "https://raw.githubusercontent.com/dtolnay/essay/avatar/avatar.png")]
 
 #[path = "2019-08-08-await-a-minute.rs"]
@@ -14,3 +14,12 @@ mod _03;
 
 #[path = "2020-02-20-triage-scale.rs"]
 mod _04;
+
+#[path = "2019-10-01-reference-types/mod.rs"]
+pub mod reference_types;
+
+#[path = "2019-12-09-soundness-bugs/mod.rs"]
+pub mod soundness_bugs;
+
+#[path = "2020-02-20-triage-scale/mod.rs"]
+pub mod triage_scale;
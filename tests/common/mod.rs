@@ -0,0 +1,84 @@
+// Helpers shared by the integration tests, which include this module by path.
+// Each test uses only part of it.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+pub struct CodeBlock {
+    // The info string after the opening fence, such as `compile_fail`.
+    pub info: String,
+    // The code as compiled by rustdoc, including hidden lines.
+    pub full: String,
+    // The code as shown to the reader.
+    pub visible: String,
+}
+
+// Fenced code blocks of an essay or of its Markdown export. Lines starting
+// with `# ` are hidden from the reader only in Rust code.
+pub fn code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<CodeBlock> = None;
+    for line in markdown.lines() {
+        if let Some(info) = line.strip_prefix("```") {
+            match current.take() {
+                Some(block) => blocks.push(block),
+                None => {
+                    current = Some(CodeBlock {
+                        info: info.to_owned(),
+                        full: String::new(),
+                        visible: String::new(),
+                    });
+                }
+            }
+            continue;
+        }
+        let Some(block) = &mut current else {
+            continue;
+        };
+        let rust = matches!(block.info.as_str(), "" | "rust" | "compile_fail");
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        if rust && trimmed == "#" {
+            block.full.push('\n');
+        } else if let Some(hidden) = trimmed.strip_prefix("# ").filter(|_| rust) {
+            block.full.push_str(indent);
+            block.full.push_str(hidden);
+            block.full.push('\n');
+        } else {
+            block.full.push_str(line);
+            block.full.push('\n');
+            block.visible.push_str(line);
+            block.visible.push('\n');
+        }
+    }
+    blocks
+}
+
+// A copy of the crate's manifest and the top level of src, in a fresh
+// directory so that the repository itself is left alone.
+pub fn scratch(name: &str) -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&root);
+    let src = root.join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::copy(manifest_dir.join("Cargo.toml"), root.join("Cargo.toml")).unwrap();
+    for entry in fs::read_dir(manifest_dir.join("src")).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            fs::copy(&path, src.join(path.file_name().unwrap())).unwrap();
+        }
+    }
+    root
+}
+
+pub fn essay(root: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_essay"))
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
@@ -0,0 +1,152 @@
+// Feeds each futures 0.1 "before" snippet from the await essay through the
+// async-rewrite tool and checks that the essay's own async/await "after"
+// snippet appears in the suggestion, up to the names of local variables.
+
+#[path = "common/mod.rs"]
+mod common;
+#[path = "../src/bin/common/lexer.rs"]
+mod lexer;
+
+use common::code_blocks;
+use lexer::Kind;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const ESSAY: &str = "src/2019-08-08-await-a-minute.rs";
+
+// Tokens with comments dropped and every `let`-bound variable renamed in order
+// of introduction, so that two snippets differing only in local variable
+// names compare equal.
+fn canonical(tokens: &[lexer::Token]) -> Vec<String> {
+    let mut names = BTreeMap::new();
+    let mut canonical = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let after_let = i >= 1 && tokens[i - 1].is("let")
+            || i >= 2 && tokens[i - 2].is("let") && tokens[i - 1].is("mut");
+        if token.kind == Kind::Ident && after_let && !token.is("mut") {
+            let n = names.len();
+            names.insert(token.text, format!("${n}"));
+        }
+        canonical.push(match names.get(token.text) {
+            Some(name) if token.kind == Kind::Ident => name.clone(),
+            _ => token.text.to_owned(),
+        });
+    }
+    canonical
+}
+
+fn tokens(src: &str) -> Vec<lexer::Token<'_>> {
+    let mut tokens = lexer::tokenize(src).unwrap();
+    tokens.retain(|token| !token.is_comment());
+    tokens
+}
+
+fn assert_contains_rewrite(output: &str, expected: &str) {
+    let output = tokens(output);
+    let expected = tokens(expected);
+    let want = canonical(&expected);
+    let found = (0..=output.len().saturating_sub(expected.len()))
+        .any(|start| canonical(&output[start..start + expected.len()]) == want);
+    assert!(found, "expected rewrite not found in suggestion");
+}
+
+fn rewrite(src: &str, name: &str) -> String {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("async-rewrite");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, src).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_async-rewrite"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the async-rewrite binary")]
+fn test_handle_get_counters() {
+    let essay = fs::read_to_string(ESSAY).unwrap();
+    let blocks = code_blocks(&essay);
+    let output = rewrite(&blocks[0].full, "handle_get_counters.rs");
+    assert_contains_rewrite(&output, &blocks[1].visible);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the async-rewrite binary")]
+fn test_add_modules() {
+    let essay = fs::read_to_string(ESSAY).unwrap();
+    let blocks = code_blocks(&essay);
+    // Rustdoc wraps snippets without a main function in one.
+    let src = format!("fn main() {{\n{}}}\n", blocks[2].full);
+    let output = rewrite(&src, "add_modules.rs");
+    assert_contains_rewrite(&output, &blocks[3].visible);
+}
+
+const FETCH: &str = "
+fn fetch(x: u32) -> impl Future<Item = u32, Error = Error> {
+    future::ok(x)
+}
+";
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the async-rewrite binary")]
+fn test_then_verbatim() {
+    let src = format!(
+        "{FETCH}
+fn relay(x: u32) -> impl Future<Item = u32, Error = Error> {{
+    fetch(x).then(|r| match r {{ Ok(v) => Ok(v), Err(e) => Err(e) }})
+}}
+
+fn relay_block(x: u32) -> impl Future<Item = u32, Error = Error> {{
+    fetch(x).then(|r| {{
+        let n = 1;
+        match r {{ Ok(v) => Ok(v + n), Err(e) => Err(e) }}
+    }})
+}}
+",
+    );
+    let output = rewrite(&src, "then_verbatim.rs");
+    assert_contains_rewrite(
+        &output,
+        "async fn relay(x: u32) -> Result<u32, Error> {
+            let r = fetch(x).await;
+            let ret = match r { Ok(v) => Ok(v), Err(e) => Err(e) }?;
+            Ok(ret)
+        }",
+    );
+    assert_contains_rewrite(
+        &output,
+        "async fn relay_block(x: u32) -> Result<u32, Error> {
+            let n = 1;
+            let r = fetch(x).await;
+            let ret = match r { Ok(v) => Ok(v + n), Err(e) => Err(e) }?;
+            Ok(ret)
+        }",
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the async-rewrite binary")]
+fn test_unchanged() {
+    let join_all = "fn fetch_all(xs: Vec<u32>) -> impl Future<Item = Vec<u32>, Error = Error> {
+    future::join_all(xs.into_iter().map(fetch))
+}";
+    let branches = "fn pick(x: u32, twice: bool) -> impl Future<Item = u32, Error = Error> {
+    if twice {
+        fetch(x).and_then(fetch)
+    } else {
+        fetch(x).map(|v| v + 1)
+    }
+}";
+    let src = format!("{FETCH}\n{join_all}\n\n{branches}\n");
+    let output = rewrite(&src, "unchanged.rs");
+    for (name, code) in [("fetch_all", join_all), ("pick", branches)] {
+        let expected = format!(
+            "// note: left `{name}` unchanged; its body does not chain combinators in a form this tool can rewrite\n{code}\n",
+        );
+        assert!(output.contains(&expected), "{output}");
+    }
+}
@@ -0,0 +1,138 @@
+// The per-thread counter from the soundness bugs companion module, hammered
+// from many threads. Iteration counts are scaled down under Miri, which checks
+// the same code paths for data races.
+
+use dtolnay::soundness_bugs::counter::Counter;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::thread;
+
+const THREADS: u64 = if cfg!(miri) { 4 } else { 16 };
+const ITERATIONS: u64 = if cfg!(miri) { 50 } else { 100_000 };
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<Counter>();
+}
+
+#[test]
+fn test_single_thread() {
+    let counter = Counter::new();
+    assert_eq!(counter.get(), 0);
+    counter.increment();
+    counter.add(41);
+    assert_eq!(counter.get(), 42);
+    assert_eq!(format!("{counter:?}"), "Counter(42)");
+}
+
+#[test]
+fn test_wrapping() {
+    let counter = Counter::new();
+    counter.add(u64::MAX);
+    counter.add(2);
+    assert_eq!(counter.get(), 1);
+
+    // Summing a retired count with a live shard wraps the same way.
+    let counter = Counter::new();
+    thread::scope(|scope| {
+        scope.spawn(|| counter.add(u64::MAX));
+    });
+    counter.add(2);
+    assert_eq!(counter.get(), 1);
+}
+
+#[test]
+fn test_stress() {
+    let counter = Counter::new();
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..ITERATIONS {
+                    counter.increment();
+                }
+            });
+        }
+        // Concurrent reads never go backward.
+        scope.spawn(|| {
+            let mut prev = 0;
+            for _ in 0..ITERATIONS {
+                let current = counter.get();
+                assert!(current >= prev);
+                prev = current;
+            }
+        });
+    });
+    assert_eq!(counter.get(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn test_many_counters() {
+    let counters: Vec<Counter> = (0..8).map(|_| Counter::new()).collect();
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for (i, counter) in counters.iter().enumerate() {
+                    counter.add(i as u64);
+                }
+            });
+        }
+    });
+    for (i, counter) in counters.iter().enumerate() {
+        assert_eq!(counter.get(), THREADS * i as u64);
+    }
+}
+
+#[test]
+fn test_thread_exit() {
+    let counter = Arc::new(Counter::new());
+    for _ in 0..THREADS {
+        let counter = Arc::clone(&counter);
+        thread::spawn(move || counter.add(ITERATIONS))
+            .join()
+            .unwrap();
+    }
+    assert_eq!(counter.get(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn test_counter_dropped_before_thread_exit() {
+    thread::spawn(|| {
+        let counter = Counter::new();
+        counter.increment();
+        drop(counter);
+        // A new counter possibly at the same address starts from zero.
+        let counter = Counter::new();
+        assert_eq!(counter.get(), 0);
+        counter.increment();
+        assert_eq!(counter.get(), 1);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn test_add_from_thread_local_destructor() {
+    struct AddOnDrop(Arc<Counter>);
+
+    impl Drop for AddOnDrop {
+        fn drop(&mut self) {
+            self.0.add(1);
+        }
+    }
+
+    thread_local! {
+        static GUARD: RefCell<Option<AddOnDrop>> = const { RefCell::new(None) };
+    }
+
+    let counter = Arc::new(Counter::new());
+    let clone = Arc::clone(&counter);
+    thread::spawn(move || {
+        clone.add(1);
+        GUARD.with(|guard| *guard.borrow_mut() = Some(AddOnDrop(clone)));
+    })
+    .join()
+    .unwrap();
+    assert_eq!(counter.get(), 2);
+}
@@ -0,0 +1,209 @@
+// Readers link to sections of the essays on docs.rs by the anchors rustdoc
+// derives from heading text, like `#comprehensible-error-handling`. Every anchor
+// that has ever been published is recorded in tests/snapshots/anchors.txt, and
+// this fails when one of them stops existing, which is what renaming or removing
+// a heading does. To keep inbound links working after a rename, declare the old
+// anchor as an alias by putting `<a id="old-anchor"></a>` on its own line above
+// the renamed heading. New headings are recorded by rerunning with
+// UPDATE_SNAPSHOTS=1.
+
+// Anchors are derived by the same code as the table of contents, toc.rs; the
+// rest of these modules are what it depends on.
+#[path = "../src/bin/essay/anchors.rs"]
+mod anchors;
+#[allow(dead_code)]
+#[path = "../src/bin/essay/doc.rs"]
+mod doc;
+#[path = "../src/bin/essay/essays.rs"]
+mod essays;
+#[allow(dead_code)]
+#[path = "../src/bin/essay/links.rs"]
+mod links;
+#[path = "../src/bin/essay/markdown.rs"]
+mod markdown;
+#[path = "../src/bin/essay/resolve.rs"]
+mod resolve;
+#[allow(dead_code)]
+#[path = "../src/bin/essay/toc.rs"]
+mod toc;
+
+use crate::markdown::Block;
+use std::env;
+use std::fs;
+
+const ANCHORS: &str = "tests/snapshots/anchors.txt";
+
+// The doc comment of an essay, parsed.
+fn blocks(src: &str) -> Vec<Block> {
+    let (doc, _) = essays::doc_comment(src).unwrap();
+    markdown::parse(doc)
+}
+
+// Anchors that rustdoc assigns to the headings of an essay, in order.
+fn headings(blocks: &[Block]) -> Vec<String> {
+    toc::headings(blocks)
+        .into_iter()
+        .map(|heading| heading.anchor)
+        .collect()
+}
+
+// Anchors declared with `<a id="..."></a>` to stand in for old headings.
+fn aliases(blocks: &[Block], aliases: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Html(html) => {
+                let mut rest = html.as_str();
+                while let Some(start) = rest.find("<a id=\"") {
+                    rest = &rest[start + "<a id=\"".len()..];
+                    if let Some(end) = rest.find('"') {
+                        aliases.push(rest[..end].to_owned());
+                        rest = &rest[end..];
+                    }
+                }
+            }
+            Block::Quote(blocks) => self::aliases(blocks, aliases),
+            Block::List(list) => {
+                for item in &list.items {
+                    self::aliases(item, aliases);
+                }
+            }
+            Block::Heading { .. } | Block::Paragraph(_) | Block::Code { .. } | Block::Rule => {}
+        }
+    }
+}
+
+// Checks the recorded `file-stem#anchor` lines against the essays, given as
+// (file name, source). Returns the problems, and the recorded lines with any
+// new headings added.
+fn check(recorded: &str, essays: &[(String, String)]) -> (Vec<String>, String) {
+    let mut errors = Vec::new();
+    let mut updated = String::new();
+    let mut current = Vec::new();
+    for (file_name, src) in essays {
+        let stem = file_name.strip_suffix(".rs").unwrap_or(file_name);
+        let blocks = blocks(src);
+        let mut declared = Vec::new();
+        aliases(&blocks, &mut declared);
+        for anchor in headings(&blocks) {
+            if declared.contains(&anchor) {
+                errors.push(format!(
+                    "src/{file_name}: alias `{anchor}` is also the anchor of a heading",
+                ));
+            }
+            current.push((format!("{stem}#{anchor}"), file_name));
+        }
+        for alias in declared {
+            current.push((format!("{stem}#{alias}"), file_name));
+        }
+    }
+
+    for line in recorded.lines().filter(|line| !line.is_empty()) {
+        updated.push_str(line);
+        updated.push('\n');
+        if current.iter().all(|(anchor, _)| anchor != line) {
+            let (stem, anchor) = line.split_once('#').unwrap_or((line, ""));
+            errors.push(format!(
+                "src/{stem}.rs: no heading has the published anchor `#{anchor}` anymore; \
+                 declare `<a id=\"{anchor}\"></a>` above the heading that replaced it",
+            ));
+        }
+    }
+    for (anchor, file_name) in &current {
+        if recorded.lines().all(|line| line != anchor) {
+            updated.push_str(anchor);
+            updated.push('\n');
+            errors.push(format!(
+                "src/{file_name}: `{anchor}` is not recorded in {ANCHORS}, \
+                 rerun with UPDATE_SNAPSHOTS=1 to add it",
+            ));
+        }
+    }
+    (errors, updated)
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads the essays")]
+fn test_anchors() {
+    let lib = fs::read_to_string("src/lib.rs").unwrap();
+    let essays: Vec<_> = essays::modules(&lib)
+        .into_iter()
+        .filter(|(_, name)| name.starts_with('_'))
+        .map(|(file_name, _)| {
+            let src = fs::read_to_string(format!("src/{file_name}")).unwrap();
+            (file_name.to_owned(), src)
+        })
+        .collect();
+    assert!(essays.len() >= 4);
+
+    let recorded = fs::read_to_string(ANCHORS).unwrap_or_default();
+    let (mut errors, updated) = check(&recorded, &essays);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(ANCHORS, updated).unwrap();
+        errors.retain(|error| !error.contains("UPDATE_SNAPSHOTS"));
+    }
+    assert!(errors.is_empty(), "\n{}\n", errors.join("\n"));
+}
+
+#[test]
+fn test_headings() {
+    let src = "\
+/** # Example: *one* &mdash; two
+
+## [https://areweasyncyet.rs/]
+
+```
+## Not a heading
+```
+
+## Target audience 1: the [Rust](https://www.rust-lang.org) community
+
+## Borrowing
+
+## Borrowing
+
+## Borrowing
+*/
+";
+    assert_eq!(
+        headings(&blocks(src)),
+        [
+            "example-one--two",
+            "httpsareweasyncyetrs",
+            "target-audience-1-the-rust-community",
+            "borrowing",
+            "borrowing-1",
+            "borrowing-2",
+        ],
+    );
+}
+
+#[test]
+fn test_rename() {
+    let essays = |doc: &str| {
+        [(
+            "2021-01-02-example.rs".to_owned(),
+            format!("/** {doc}\n*/\n"),
+        )]
+    };
+    let recorded = "2021-01-02-example#example\n2021-01-02-example#old-name\n";
+
+    let (errors, _) = check(recorded, &essays("# Example\n\n## Old name"));
+    assert_eq!(errors, Vec::<String>::new());
+
+    let (errors, updated) = check(recorded, &essays("# Example\n\n## New name"));
+    assert_eq!(
+        errors,
+        [
+            "src/2021-01-02-example.rs: no heading has the published anchor `#old-name` anymore; declare `<a id=\"old-name\"></a>` above the heading that replaced it",
+            "src/2021-01-02-example.rs: `2021-01-02-example#new-name` is not recorded in tests/snapshots/anchors.txt, rerun with UPDATE_SNAPSHOTS=1 to add it",
+        ],
+    );
+    assert_eq!(updated, format!("{recorded}2021-01-02-example#new-name\n"));
+
+    let (errors, updated) = check(
+        &updated,
+        &essays("# Example\n\n<a id=\"old-name\"></a>\n\n## New name"),
+    );
+    assert_eq!(errors, Vec::<String>::new());
+    assert_eq!(updated, format!("{recorded}2021-01-02-example#new-name\n"));
+}
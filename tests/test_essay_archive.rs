@@ -0,0 +1,266 @@
+// The essays link to pages outside of this repository that can go away at any
+// time. archive.toml records each one with the date of the first essay that
+// cites it and, where someone has checked, its title, the passage the essay
+// relies on, and when it was last verified. This checks that every external
+// URL in an essay registered in src/lib.rs has an entry there, and that every
+// entry is well formed and still cited by the essay it says.
+
+#[path = "../src/bin/essay/essays.rs"]
+mod essays;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+// Every http or https URL in the doc comment of an essay, with its line.
+fn urls(src: &str) -> Vec<(usize, &str)> {
+    let doc = src.find("\n*/\n").map_or(src, |end| &src[..end]);
+    let mut urls = Vec::new();
+    for (i, line) in doc.lines().enumerate() {
+        let mut rest = line;
+        while let Some(start) = ["http://", "https://"]
+            .iter()
+            .filter_map(|scheme| rest.find(scheme))
+            .min()
+        {
+            let tail = &rest[start..];
+            let len = tail
+                .find(|ch: char| ch.is_whitespace() || "<>()[]\"'`".contains(ch))
+                .unwrap_or(tail.len());
+            let url = tail[..len].trim_end_matches(['.', ',', ';', ':']);
+            urls.push((i + 1, url));
+            rest = &tail[len..];
+        }
+    }
+    urls
+}
+
+struct Entry {
+    line: usize,
+    title: Option<String>,
+    cited: Option<String>,
+    verified: Option<String>,
+}
+
+// The entries of archive.toml by URL, or problems with its syntax.
+fn parse(archive: &str) -> (BTreeMap<String, Entry>, Vec<String>) {
+    let mut entries = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut current: Option<String> = None;
+    for (i, line) in archive.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let Some(url) = string(key) else {
+                errors.push(format!(
+                    "line {line_number}: expected a quoted URL in `{line}`"
+                ));
+                current = None;
+                continue;
+            };
+            if let Some(first) = entries.get(&url).map(|entry: &Entry| entry.line) {
+                errors.push(format!(
+                    "line {line_number}: `{url}` is already recorded on line {first}",
+                ));
+                current = None;
+                continue;
+            }
+            entries.insert(
+                url.clone(),
+                Entry {
+                    line: line_number,
+                    title: None,
+                    cited: None,
+                    verified: None,
+                },
+            );
+            current = Some(url);
+            continue;
+        }
+        let Some(entry) = current.as_ref().and_then(|url| entries.get_mut(url)) else {
+            errors.push(format!(
+                "line {line_number}: `{line}` is outside of any entry"
+            ));
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            errors.push(format!("line {line_number}: expected `key = \"value\"`"));
+            continue;
+        };
+        let Some(value) = string(value.trim()) else {
+            errors.push(format!("line {line_number}: expected a quoted string"));
+            continue;
+        };
+        match key.trim() {
+            "title" => entry.title = Some(value),
+            "quote" => {}
+            "cited" => entry.cited = Some(value),
+            "verified" => entry.verified = Some(value),
+            other => errors.push(format!("line {line_number}: unexpected key `{other}`")),
+        }
+    }
+
+    for (url, entry) in &entries {
+        let line = entry.line;
+        if entry.title.as_deref() == Some("") {
+            errors.push(format!("line {line}: `{url}` has an empty title"));
+        }
+        match &entry.cited {
+            Some(date) if is_date(date) => {}
+            Some(date) => errors.push(format!(
+                "line {line}: `{url}` was cited on `{date}`, which is not a YYYY-MM-DD date",
+            )),
+            None => errors.push(format!("line {line}: `{url}` has no cited date")),
+        }
+        match &entry.verified {
+            Some(date) if !is_date(date) => errors.push(format!(
+                "line {line}: `{url}` was verified on `{date}`, which is not a YYYY-MM-DD date",
+            )),
+            Some(_) if entry.title.is_none() => {
+                errors.push(format!("line {line}: `{url}` is verified but has no title"));
+            }
+            _ => {}
+        }
+    }
+    (entries, errors)
+}
+
+// A basic string in double quotes, with `\"` and `\\` escapes.
+fn string(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next()? {
+                escaped @ ('"' | '\\') => value.push(escaped),
+                _ => return None,
+            },
+            '"' => return None,
+            _ => value.push(ch),
+        }
+    }
+    Some(value)
+}
+
+fn is_date(date: &str) -> bool {
+    essays::published(date).is_ok()
+}
+
+// Problems with archive.toml, given the source of each essay by file name.
+fn check(essays: &[(String, String)], archive: &str) -> Vec<String> {
+    let (entries, mut errors) = parse(archive);
+    for error in &mut errors {
+        *error = format!("archive.toml: {error}");
+    }
+    // The date of the first essay citing each URL, which file names start with.
+    let mut cited = BTreeMap::new();
+    for (file_name, src) in essays {
+        let date = file_name.get(..10).unwrap_or_default();
+        for (line, url) in urls(src) {
+            let first = cited.entry(url).or_insert(date);
+            *first = date.min(first);
+            if !entries.contains_key(url) {
+                errors.push(format!(
+                    "src/{file_name}:{line}: `{url}` is not recorded in archive.toml",
+                ));
+            }
+        }
+    }
+    for (url, entry) in &entries {
+        let line = entry.line;
+        let Some(first) = cited.get(url.as_str()) else {
+            errors.push(format!(
+                "archive.toml: line {line}: `{url}` is not cited by any essay",
+            ));
+            continue;
+        };
+        if let Some(date) = entry.cited.as_ref().filter(|date| is_date(date)) {
+            if date != first {
+                errors.push(format!(
+                    "archive.toml: line {line}: `{url}` is first cited on {first}, not {date}",
+                ));
+            }
+        }
+    }
+    errors
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads the essays")]
+fn test_archive() {
+    let lib = fs::read_to_string("src/lib.rs").unwrap();
+    let essays: Vec<_> = essays::modules(&lib)
+        .into_iter()
+        .filter(|(_, name)| name.starts_with('_'))
+        .map(|(file_name, _)| {
+            let src = fs::read_to_string(format!("src/{file_name}")).unwrap();
+            (file_name.to_owned(), src)
+        })
+        .collect();
+    assert!(essays.len() >= 4);
+    let archive = fs::read_to_string("archive.toml").unwrap();
+    let errors = check(&essays, &archive);
+    assert!(errors.is_empty(), "\n{}\n", errors.join("\n"));
+}
+
+#[test]
+fn test_rejects() {
+    let essay = "\
+/** # Example
+
+Read [the announcement] or <https://example.com/autolink>.
+
+[the announcement]: https://example.com/announcement?page=2
+*/
+#[macro_export]
+macro_rules! _05__example {
+    ({
+        date:  \"January 2, 2021\",
+        author:  \"David Tolnay\",
+    }) => {};
+}
+";
+    let essays = [("2021-01-02-example.rs".to_owned(), essay.to_owned())];
+
+    let archive = r#"
+["https://example.com/announcement?page=2"]
+title = "An \"announcement\""
+cited = "2021-01-02"
+verified = "2024-02-29"
+
+["https://example.com/autolink"]
+quote = "C:\\"
+cited = "2021-01-02"
+"#;
+    assert_eq!(check(&essays, archive), Vec::<String>::new());
+
+    let archive = r#"
+["https://example.com/announcement?page=2"]
+cited = "2021-01-03"
+verified = "2021-02-29"
+
+["https://example.com/announcement?page=2"]
+title = "Again"
+
+["https://example.com/gone"]
+titel = "Gone"
+verified = "2021-01-02"
+"#;
+    assert_eq!(
+        check(&essays, archive),
+        [
+            "archive.toml: line 6: `https://example.com/announcement?page=2` is already recorded on line 2",
+            "archive.toml: line 7: `title = \"Again\"` is outside of any entry",
+            "archive.toml: line 10: unexpected key `titel`",
+            "archive.toml: line 2: `https://example.com/announcement?page=2` was verified on `2021-02-29`, which is not a YYYY-MM-DD date",
+            "archive.toml: line 9: `https://example.com/gone` has no cited date",
+            "archive.toml: line 9: `https://example.com/gone` is verified but has no title",
+            "src/2021-01-02-example.rs:3: `https://example.com/autolink` is not recorded in archive.toml",
+            "archive.toml: line 2: `https://example.com/announcement?page=2` is first cited on 2021-01-02, not 2021-01-03",
+            "archive.toml: line 9: `https://example.com/gone` is not cited by any essay",
+        ],
+    );
+}
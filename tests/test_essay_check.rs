@@ -0,0 +1,81 @@
+// Runs the essay link checker over the real essays, which must be clean, and
+// over a scratch copy of the crate with an essay that has one of each kind of
+// problem.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const ESSAY: &str = r#"/** # Links
+
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.01.02*</sup>
+
+[David Tolnay]: https://github.com/dtolnay
+
+<br>
+
+An [announcement] and a [typo], plus [`Cell`] and [`RefCell`], [Sync],
+[`AtomicU32`], [`NonNull`], [`Option::map`], [`.map()`] and [`.poll()`],
+but not [`.nonexistent()`]. Brackets in code are not links: `[not a link]`.
+
+```rust
+let array = [not_a_link];
+```
+
+[announcement]: https://example.com/announcement
+[`Cell`]: core::cell::Cell
+[`RefCell`]: core::cell::NoSuchCell
+[`AtomicU32`]: core::sync::atomic::AtomicU32
+[`NonNull`]: core::ptr::NonNull
+[`.map()`]: futures01::Future::map
+[`.poll()`]: futures01::Future::poll
+[`.nonexistent()`]: core::cell::Cell::nonexistent
+[Announcement]: https://example.com/duplicate
+[unused]: https://example.com/unused
+*/
+#[macro_export]
+macro_rules! _05__links {
+    ({
+        date:  "January 2, 2021",
+        author:  "David Tolnay",
+    }) => {};
+}
+"#;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_essays() {
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg("check")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+    assert!(output.status.success());
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_problems() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-check");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    let lib = "#[path = \"2021-01-02-links.rs\"]\nmod _05;\n";
+    fs::write(root.join("src/lib.rs"), lib).unwrap();
+    fs::write(root.join("src/2021-01-02-links.rs"), ESSAY).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg("check")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    let expected = "\
+src/2021-01-02-links.rs:9: `[typo]` is used but never defined
+src/2021-01-02-links.rs:19: `core::cell::NoSuchCell` is not an item documented in core, alloc, std or futures01
+src/2021-01-02-links.rs:24: `core::cell::Cell::nonexistent` is not an item documented in core, alloc, std or futures01
+src/2021-01-02-links.rs:25: `[Announcement]` is already defined on line 17
+src/2021-01-02-links.rs:26: `[unused]` is defined but never used
+";
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,135 @@
+// Checks the structure of the EPUB export by reading the archive back: entry
+// order and checksums of the zip, the package metadata, and that every page is
+// well-formed XML as EPUB requires.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+struct Entry {
+    name: String,
+    data: Vec<u8>,
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> usize {
+    usize::from(u16::from_le_bytes([bytes[pos], bytes[pos + 1]]))
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+}
+
+// Reads the local file headers from the start of the archive, which is where
+// readers look for the mimetype entry.
+fn entries(bytes: &[u8]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while u32_at(bytes, pos) == 0x0403_4b50 {
+        assert_eq!(u16_at(bytes, pos + 8), 0, "entries must be stored");
+        let crc = u32_at(bytes, pos + 14);
+        let size = u32_at(bytes, pos + 18) as usize;
+        let name_len = u16_at(bytes, pos + 26);
+        let extra_len = u16_at(bytes, pos + 28);
+        let name = &bytes[pos + 30..pos + 30 + name_len];
+        let start = pos + 30 + name_len + extra_len;
+        let data = bytes[start..start + size].to_vec();
+        assert_eq!(crc, crc32(&data));
+        entries.push(Entry {
+            name: String::from_utf8(name.to_vec()).unwrap(),
+            data,
+        });
+        pos = start + size;
+    }
+    assert_eq!(u32_at(bytes, pos), 0x0201_4b50, "central directory");
+    entries
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Every start tag is closed by a matching end tag, or is self-closing.
+fn assert_balanced(name: &str, xml: &str) {
+    let mut stack = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>').unwrap();
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with(['?', '!']) || tag.ends_with('/') {
+            continue;
+        }
+        if let Some(close) = tag.strip_prefix('/') {
+            assert_eq!(stack.pop(), Some(close), "{name}: unexpected </{close}>");
+        } else {
+            stack.push(tag.split_whitespace().next().unwrap());
+        }
+    }
+    assert!(stack.is_empty(), "{name}: unclosed {stack:?}");
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_epub() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-epub/essays.epub");
+    let _ = fs::remove_file(&path);
+    let status = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg("epub")
+        .arg(&path)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let entries = entries(&fs::read(&path).unwrap());
+    let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "mimetype",
+            "META-INF/container.xml",
+            "OEBPS/content.opf",
+            "OEBPS/nav.xhtml",
+            "OEBPS/cover.xhtml",
+            "OEBPS/style.css",
+            "OEBPS/2019-08-08-await-a-minute.xhtml",
+            "OEBPS/2019-10-01-reference-types.xhtml",
+            "OEBPS/2019-12-09-soundness-bugs.xhtml",
+            "OEBPS/2020-02-20-triage-scale.xhtml",
+        ],
+    );
+    assert_eq!(entries[0].data, b"application/epub+zip");
+
+    let text = |name: &str| {
+        let entry = entries.iter().find(|entry| entry.name == name).unwrap();
+        String::from_utf8(entry.data.clone()).unwrap()
+    };
+    let opf = text("OEBPS/content.opf");
+    assert!(opf.contains("<dc:creator id=\"creator0\">David Tolnay</dc:creator>"));
+    assert!(opf.contains("<dc:identifier id=\"id\">https://docs.rs/dtolnay</dc:identifier>"));
+    assert!(opf.contains("<dc:title>Essays by David Tolnay</dc:title>"));
+
+    let nav = text("OEBPS/nav.xhtml");
+    let await_a_minute = nav.find("2019-08-08-await-a-minute.xhtml").unwrap();
+    let triage_scale = nav.find("2020-02-20-triage-scale.xhtml").unwrap();
+    assert!(await_a_minute < triage_scale);
+
+    for entry in &entries {
+        let extension = Path::new(&entry.name).extension();
+        if extension.is_some_and(|ext| ext == "xhtml" || ext == "opf" || ext == "xml") {
+            assert_balanced(&entry.name, &text(&entry.name));
+        }
+    }
+    assert!(text("OEBPS/2019-12-09-soundness-bugs.xhtml").contains("<pre class=\"rust\">"));
+}
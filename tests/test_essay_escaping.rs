@@ -0,0 +1,194 @@
+// Each essay is one `/** ... */` doc comment followed by its metadata macro.
+// Block comments nest, so a `/* snip */` inside a code block is harmless, but a
+// lone `*/` anywhere in the essay ends the doc comment early and a lone `/*`
+// keeps it from ending where intended. Either way rustdoc renders a truncated
+// essay or the rest of the file is parsed as something else. This checks every
+// essay registered in src/lib.rs for those, for code fences that are never
+// closed, and for anything after the essay other than the metadata macro.
+
+#[path = "../src/bin/essay/essays.rs"]
+mod essays;
+
+use std::fs;
+
+// The essays of the crate root by file name, with the NN of their `mod _NN;`.
+fn essays(lib: &str) -> Vec<(String, usize)> {
+    essays::modules(lib)
+        .into_iter()
+        .filter_map(|(path, name)| {
+            let number = name.strip_prefix('_')?.parse().ok()?;
+            Some((path.to_owned(), number))
+        })
+        .collect()
+}
+
+fn line_of(src: &str, offset: usize) -> usize {
+    src[..offset].matches('\n').count() + 1
+}
+
+// Problems with the source of essay number `number` in file `file_name`.
+fn check(file_name: &str, number: usize, src: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    if !src.starts_with("/**") {
+        errors.push("line 1: essay does not begin with `/**`".to_owned());
+        return errors;
+    }
+
+    // The intended end of the essay is a line of just `*/`.
+    let Some(close) = src
+        .match_indices("\n*/\n")
+        .map(|(i, _)| i + 1)
+        .find(|&i| src[i + 3..].trim_start().starts_with("#[macro_export]"))
+    else {
+        errors.push("essay is not closed by a `*/` line before the metadata macro".to_owned());
+        return errors;
+    };
+
+    // Where rustc thinks the doc comment ends. Every `*/` that closes a comment
+    // before the intended end is one that was meant as text.
+    let mut start = 3;
+    while let Some(end) = essays::comment_end(&src[start..]).map(|end| start + end) {
+        if end >= close {
+            break;
+        }
+        errors.push(format!(
+            "line {}: unescaped `*/` ends the essay early",
+            line_of(src, end),
+        ));
+        start = end + 2;
+    }
+    if start == 3 {
+        // A `/*` whose comment runs past the intended end keeps the essay open.
+        for (open, _) in src[..close].match_indices("/*").skip(1) {
+            let end = essays::comment_end(&src[open + 2..]).map(|end| open + 2 + end);
+            if end.is_none_or(|end| end >= close) {
+                errors.push(format!(
+                    "line {}: unescaped `/*` is never closed, so the essay does not end at its `*/`",
+                    line_of(src, open),
+                ));
+            }
+        }
+    }
+
+    // Code fences, which CommonMark closes with a run of the same character at
+    // least as long as the opening one.
+    let mut fence: Option<(char, usize, usize)> = None;
+    for (i, line) in src[..close].lines().enumerate() {
+        let trimmed = line.trim_start();
+        let Some(ch) = trimmed.chars().next().filter(|ch| ['`', '~'].contains(ch)) else {
+            continue;
+        };
+        let len = trimmed.len() - trimmed.trim_start_matches(ch).len();
+        if len < 3 {
+            continue;
+        }
+        match fence {
+            None => fence = Some((ch, len, i + 1)),
+            Some((open, open_len, _)) => {
+                if ch == open && len >= open_len && trimmed[len..].trim().is_empty() {
+                    fence = None;
+                }
+            }
+        }
+    }
+    if let Some((_, _, line)) = fence {
+        errors.push(format!("line {line}: code fence is never closed"));
+    }
+
+    // After the essay, exactly the metadata macro.
+    let slug = file_name
+        .strip_suffix(".rs")
+        .and_then(essays::split_file_name)
+        .map_or("", |(_, slug)| slug);
+    let macro_name = essays::macro_name(number, slug);
+    let expected = format!("#[macro_export]\nmacro_rules! {macro_name} {{\n    ({{\n");
+    let rest = &src[close + 3..];
+    let line = line_of(src, close) + 1;
+    let Some(fields) = rest.trim_start_matches('\n').strip_prefix(&expected) else {
+        errors.push(format!(
+            "line {line}: expected `macro_rules! {macro_name}` after the essay",
+        ));
+        return errors;
+    };
+    let Some(fields) = fields.strip_suffix("    }) => {};\n}\n") else {
+        errors.push(format!(
+            "line {line}: metadata macro is not followed by the end of the file",
+        ));
+        return errors;
+    };
+    for field in fields.lines() {
+        let name = field
+            .trim_start()
+            .split_once(':')
+            .map(|(name, _)| name)
+            .filter(|name| ["date", "author", "tags"].contains(name));
+        if name.is_none() || !field.ends_with(',') {
+            errors.push(format!(
+                "line {line}: unexpected `{}` in the metadata macro",
+                field.trim(),
+            ));
+        }
+    }
+    errors
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads the essays")]
+fn test_essays() {
+    let lib = fs::read_to_string("src/lib.rs").unwrap();
+    let essays = essays(&lib);
+    assert!(essays.len() >= 4);
+    let mut errors = Vec::new();
+    for (file_name, number) in essays {
+        let src = fs::read_to_string(format!("src/{file_name}")).unwrap();
+        for error in check(&file_name, number, &src) {
+            errors.push(format!("src/{file_name}: {error}"));
+        }
+    }
+    assert!(errors.is_empty(), "\n{}\n", errors.join("\n"));
+}
+
+const MACRO: &str = "\
+#[macro_export]
+macro_rules! _05__example {
+    ({
+        date:  \"January 2, 2021\",
+        author:  \"David Tolnay\",
+    }) => {};
+}
+";
+
+fn essay(doc: &str) -> String {
+    format!("/** # Example\n\n{doc}\n*/\n{MACRO}")
+}
+
+#[test]
+fn test_rejects() {
+    let check = |src: &str| check("2021-01-02-example.rs", 5, src);
+
+    assert!(check(&essay("Fine.\n\n```\nlet x = 1; /* snip */\n```")).is_empty());
+    assert_eq!(
+        check(&essay("Text.\n\n```cpp\nint x; // ends */\n```")),
+        ["line 6: unescaped `*/` ends the essay early"],
+    );
+    assert_eq!(
+        check(&essay("A glob like src/*.rs.")),
+        ["line 3: unescaped `/*` is never closed, so the essay does not end at its `*/`"],
+    );
+    assert_eq!(
+        check(&essay("```rust\nfn main() {}")),
+        ["line 3: code fence is never closed"],
+    );
+    assert_eq!(
+        check(&format!("{}fn stray() {{}}\n", essay("Text."))),
+        ["line 5: metadata macro is not followed by the end of the file"],
+    );
+    assert_eq!(
+        check(&essay("Text.").replace("_05__example", "_04__example")),
+        ["line 5: expected `macro_rules! _05__example` after the essay"],
+    );
+    assert_eq!(
+        check(&essay("Text.").replace("author:", "editor:")),
+        ["line 5: unexpected `editor:  \"David Tolnay\",` in the metadata macro"],
+    );
+}
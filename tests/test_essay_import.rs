@@ -0,0 +1,124 @@
+// Tests of `essay import`, run against a copy of src/lib.rs and the essays in
+// a scratch directory so that the repository itself is left alone.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::{essay, scratch};
+use std::fs;
+
+const POST: &str = r#"---
+title: "Comments */ in essays"
+date: 2021-01-02
+author: David Tolnay
+tags: [meta, "doc comments"]
+---
+
+# Comments */ in essays
+
+A glob like src/*/mod.rs, or `src/*/mod.rs` in code.
+
+```rust
+let x = 1; /* snip */
+```
+"#;
+
+const EXPECTED: &str = r#"/** # Comments *&#47; in essays
+
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.01.02*</sup>
+
+[David Tolnay]: https://github.com/dtolnay
+
+<br>
+
+A glob like src&#47;*&#47;mod.rs, or <code>src&#47;*&#47;mod.rs</code> in code.
+
+```rust
+let x = 1; /* snip */
+```
+*/
+#[macro_export]
+macro_rules! _05__comments {
+    ({
+        date:  "January 2, 2021",
+        author:  "David Tolnay",
+        tags:  ["meta", "doc comments"],
+    }) => {};
+}
+"#;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_import() {
+    let root = scratch("essay-import");
+    fs::write(root.join("comments.md"), POST).unwrap();
+    let output = essay(&root, &["import", "comments.md"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "src/2021-01-02-comments.rs\nsrc/lib.rs\n",
+    );
+
+    let source = fs::read_to_string(root.join("src/2021-01-02-comments.rs")).unwrap();
+    assert_eq!(source, EXPECTED);
+    let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    assert!(lib.contains(concat!(
+        "mod _04;\n",
+        "\n",
+        "#[path = \"2021-01-02-comments.rs\"]\n",
+        "mod _05;\n",
+    )));
+
+    // The new essay is picked up like the others.
+    let output = essay(&root, &["markdown", "markdown"]);
+    assert!(output.status.success());
+    let markdown = fs::read_to_string(root.join("markdown/2021-01-02-comments.md")).unwrap();
+    assert!(
+        markdown.starts_with("# Comments *&#47; in essays\n\n*by [David Tolnay], 2021.01.02*\n")
+    );
+
+    // Importing again would overwrite it.
+    let output = essay(&root, &["import", "comments.md"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_errors() {
+    let root = scratch("essay-import-errors");
+    let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    let cases = [
+        (
+            "---\ntitle: Unbalanced\ndate: 2021-01-02\nauthor: David Tolnay\n---\n\nText.\n\n```\nfoo */\n```\n",
+            "error: unbalanced.md: line 10: `*/` in code would end the doc comment\n",
+        ),
+        (
+            "---\ntitle: Unbalanced\ndate: 2021-01-02\nauthor: David Tolnay\n---\n\n```c\n/* foo\n```\n",
+            "error: unbalanced.md: line 8: `/*` in code is never closed, so the doc comment would not end\n",
+        ),
+        (
+            "---\ntitle: Unbalanced\ndate: 2021-1-2\nauthor: David Tolnay\n---\n",
+            "error: unbalanced.md: date `2021-1-2` is not of the form YYYY-MM-DD\n",
+        ),
+        (
+            "---\ntitle: Unbalanced\ndate: 2021-02-29\nauthor: David Tolnay\n---\n",
+            "error: unbalanced.md: `2021-02-29` is not a valid date\n",
+        ),
+        (
+            "---\ntitle: Unbalanced\ndate: 2021-01-02\n---\n",
+            "error: unbalanced.md: front matter has no `author`\n",
+        ),
+        (
+            "# Unbalanced\n",
+            "error: unbalanced.md: expected front matter between `---` lines at the start of the file\n",
+        ),
+    ];
+    for (post, expected) in cases {
+        fs::write(root.join("unbalanced.md"), post).unwrap();
+        let output = essay(&root, &["import", "unbalanced.md"]);
+        assert!(!output.status.success());
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), expected);
+        assert!(!root.join("src/2021-01-02-unbalanced.rs").exists());
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), lib);
+    }
+}
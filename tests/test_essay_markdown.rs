@@ -0,0 +1,122 @@
+// Exports every essay to Markdown and checks that nothing specific to rustdoc
+// survives: the code blocks read the same as on docs.rs, and every link
+// definition is a URL that works outside of rustdoc.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::code_blocks;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const ESSAYS: [&str; 4] = [
+    "2019-08-08-await-a-minute",
+    "2019-10-01-reference-types",
+    "2019-12-09-soundness-bugs",
+    "2020-02-20-triage-scale",
+];
+
+fn export(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("essay-markdown")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg("markdown")
+        .arg(&dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    dir
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_code_blocks() {
+    let dir = export("code-blocks");
+    for essay in ESSAYS {
+        let source = fs::read_to_string(format!("src/{essay}.rs")).unwrap();
+        let markdown = fs::read_to_string(dir.join(format!("{essay}.md"))).unwrap();
+        let expected = code_blocks(&source);
+        let actual = code_blocks(&markdown);
+        assert_eq!(actual.len(), expected.len(), "{essay}");
+        for (expected, actual) in expected.iter().zip(&actual) {
+            let expected_info = match expected.info.as_str() {
+                "" | "compile_fail" => "rust",
+                other => other,
+            };
+            assert_eq!(actual.info, expected_info, "{essay}");
+            assert_eq!(actual.visible, expected.visible, "{essay}");
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_links() {
+    let dir = export("links");
+    for essay in ESSAYS {
+        let markdown = fs::read_to_string(dir.join(format!("{essay}.md"))).unwrap();
+        assert!(!markdown.contains("<br>"), "{essay}");
+        assert!(!markdown.contains("<sup>"), "{essay}");
+        for line in markdown.lines() {
+            if let Some((_, target)) = line
+                .strip_prefix('[')
+                .and_then(|line| line.split_once("]: "))
+            {
+                assert!(target.starts_with("https://"), "{essay}: {line}");
+            }
+        }
+    }
+
+    let await_a_minute = fs::read_to_string(dir.join("2019-08-08-await-a-minute.md")).unwrap();
+    assert!(await_a_minute.starts_with(
+        "# Await a minute, why bother?\n\n*by [David Tolnay], 2019.08.08*\n\n<nav class=\"contents\">\n\n- [Comprehensible error handling](#comprehensible-error-handling)\n",
+    ));
+    assert!(await_a_minute.contains(
+        "\n[`.map(...)`]: https://docs.rs/futures/0.1/futures/future/trait.Future.html#method.map\n",
+    ));
+    assert!(await_a_minute
+        .ends_with("\n[Sync]: https://doc.rust-lang.org/std/marker/trait.Sync.html\n"));
+
+    let reference_types = fs::read_to_string(dir.join("2019-10-01-reference-types.md")).unwrap();
+    assert!(reference_types.contains(
+        "\n[`AtomicU32`]: https://doc.rust-lang.org/core/sync/atomic/struct.AtomicU32.html\n",
+    ));
+    assert!(reference_types
+        .contains("\n[`Mutex<T>`]: https://doc.rust-lang.org/std/sync/struct.Mutex.html\n"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_missing_docs() {
+    // Without the standard library's documentation there is nothing for
+    // intra-doc links to point to, and every export refuses to leave the bare
+    // path in place of a URL.
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-missing-docs");
+    let _ = fs::remove_dir_all(&dir);
+    for (format, out) in [
+        ("markdown", "markdown"),
+        ("site", "site"),
+        ("epub", "essays.epub"),
+        ("gemini", "gemini"),
+        ("text", "text"),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+            .arg(format)
+            .arg(dir.join(out))
+            .env("RUSTC", "/bin/false")
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{format}");
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "error: src/2019-08-08-await-a-minute.rs: cannot resolve `Sync`: `rustc --print sysroot` failed\n",
+            "{format}",
+        );
+    }
+    assert!(!dir.exists());
+}
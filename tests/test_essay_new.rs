@@ -0,0 +1,121 @@
+// Tests of `essay new`, run against a copy of the crate root in a scratch
+// directory so that the repository itself is left alone.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::{essay, scratch};
+use std::fs;
+
+const EXPECTED: &str = r#"/** # Why don't traits compose?
+
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.03.04*</sup>
+
+[David Tolnay]: https://github.com/dtolnay
+
+<br>
+
+*/
+#[macro_export]
+macro_rules! _05__why_dont_traits_compose {
+    ({
+        date:  "March 4, 2021",
+        author:  "David Tolnay",
+    }) => {};
+}
+"#;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_new() {
+    let root = scratch("essay-new");
+    let output = essay(&root, &["new", "Why don't traits compose?", "2021-03-04"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "src/2021-03-04-why-dont-traits-compose.rs\nsrc/lib.rs\n",
+    );
+    let source = fs::read_to_string(root.join("src/2021-03-04-why-dont-traits-compose.rs"));
+    assert_eq!(source.unwrap(), EXPECTED);
+    let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    assert!(lib.contains(concat!(
+        "mod _04;\n",
+        "\n",
+        "#[path = \"2021-03-04-why-dont-traits-compose.rs\"]\n",
+        "mod _05;\n",
+    )));
+
+    // Without a date, today's.
+    let output = essay(&root, &["new", "Another one"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let path = stdout.lines().next().unwrap();
+    let date = &path["src/".len()..path.len() - "-another-one.rs".len()];
+    assert!(path.ends_with("-another-one.rs"));
+    assert!(date.len() == 10 && date.as_bytes()[4] == b'-' && date.as_bytes()[7] == b'-');
+    let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    assert!(lib.contains(&format!("#[path = \"{date}-another-one.rs\"]\nmod _06;\n")));
+    let source = fs::read_to_string(root.join(path)).unwrap();
+    assert!(source.contains("macro_rules! _06__another_one {"));
+
+    // Both are picked up like the other essays.
+    let output = essay(&root, &["markdown", "markdown"]);
+    assert!(output.status.success());
+    let markdown = fs::read_to_string(root.join("markdown/2021-03-04-why-dont-traits-compose.md"));
+    assert_eq!(
+        markdown.unwrap(),
+        "# Why don't traits compose?\n\n*by [David Tolnay], 2021.03.04*\n\n[David Tolnay]: https://github.com/dtolnay\n",
+    );
+
+    // Leap days exist in leap years.
+    let output = essay(&root, &["new", "Leap day", "2000-02-29"]);
+    assert!(output.status.success());
+    let source = fs::read_to_string(root.join("src/2000-02-29-leap-day.rs")).unwrap();
+    assert!(source.contains("date:  \"February 29, 2000\","));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_errors() {
+    let root = scratch("essay-new-errors");
+    let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    for (args, expected) in [
+        (
+            &["new", "Title", "2021-1-2"][..],
+            "error: date `2021-1-2` is not of the form YYYY-MM-DD\n",
+        ),
+        (
+            &["new", "Title", "March 4, 2021"],
+            "error: date `March 4, 2021` is not of the form YYYY-MM-DD\n",
+        ),
+        (
+            &["new", "Title", "2021-13-01"],
+            "error: `2021-13-01` is not a valid date\n",
+        ),
+        (
+            &["new", "Title", "2021-02-29"],
+            "error: `2021-02-29` is not a valid date\n",
+        ),
+        (
+            &["new", "Title", "1900-02-29"],
+            "error: `1900-02-29` is not a valid date\n",
+        ),
+        (
+            &["new", "Title", "2021-04-31"],
+            "error: `2021-04-31` is not a valid date\n",
+        ),
+        (
+            &["new", "???"],
+            "error: title \"???\" has no letters or digits for a file name\n",
+        ),
+        (
+            &["new", "Triage scale", "2020-02-20"],
+            "error: src/2020-02-20-triage-scale.rs: already exists\n",
+        ),
+    ] {
+        let output = essay(&root, args);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), expected);
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), lib);
+    }
+}
@@ -0,0 +1,125 @@
+// Spins up `essay preview` on a loopback port against a copy of the crate
+// root, then edits an essay underneath it the way an author would.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::scratch;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ESSAY: &str = "src/2020-02-20-triage-scale.rs";
+
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start(root: &Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_essay"))
+            .args(["preview", "--addr", "127.0.0.1:0", ESSAY])
+            .current_dir(root)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line
+            .trim()
+            .strip_prefix("listening on http://")
+            .unwrap()
+            .to_owned();
+        Server { child, addr }
+    }
+
+    fn request(&self, method: &str, path: &str) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        let request = format!("{method} {path} HTTP/1.1\r\nHost: {}\r\n\r\n", self.addr);
+        io::Write::write_all(&mut stream, request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, head.to_owned(), body.to_owned())
+    }
+
+    fn get(&self, path: &str) -> (u16, String) {
+        let (status, _head, body) = self.request("GET", path);
+        (status, body)
+    }
+
+    // Waits for the server to notice a change to the sources.
+    fn wait_for_version(&self, version: &str) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while self.get("/__version").1 != version {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for version {version}"
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_preview() {
+    let root = scratch("essay-preview");
+    let server = Server::start(&root);
+
+    let (status, head, _body) = server.request("GET", "/");
+    assert_eq!(status, 302);
+    assert!(head.contains("\r\nLocation: /2020-02-20-triage-scale.html\r\n"));
+
+    let page = "/2020-02-20-triage-scale.html";
+    let (status, body) = server.get(page);
+    assert_eq!(status, 200);
+    assert!(body.contains("<title>Triage at scale for the Rust team</title>"));
+    assert!(body.contains("fetch(\"/__version\")"));
+    assert_eq!(server.get("/__version"), (200, "1".to_owned()));
+    assert_eq!(server.get("/index.html").0, 200);
+    assert_eq!(server.get("/style.css").0, 200);
+    assert_eq!(server.get("/nope.html").0, 404);
+    assert_eq!(server.request("POST", page).0, 405);
+
+    // Saving the essay re-renders it.
+    let path = root.join(ESSAY);
+    let original = fs::read_to_string(&path).unwrap();
+    let edited = original.replace("# Triage at scale", "# Triage at a larger scale");
+    fs::write(&path, edited).unwrap();
+    server.wait_for_version("2");
+    let (status, body) = server.get(page);
+    assert_eq!(status, 200);
+    assert!(body.contains("<title>Triage at a larger scale for the Rust team</title>"));
+
+    // A broken essay shows the error until it is fixed.
+    fs::write(&path, original.replace("\n*/\n", "\n")).unwrap();
+    server.wait_for_version("3");
+    let (status, body) = server.get(page);
+    assert_eq!(status, 500);
+    assert!(body.contains("doc comment is never closed by `*/`"));
+    assert!(body.contains("fetch(\"/__version\")"));
+
+    fs::write(&path, original).unwrap();
+    server.wait_for_version("4");
+    let (status, body) = server.get(page);
+    assert_eq!(status, 200);
+    assert!(body.contains("<title>Triage at scale for the Rust team</title>"));
+}
@@ -0,0 +1,105 @@
+// Snapshot tests of the static site rendering of the essays. Run with
+// UPDATE_SNAPSHOTS=1 to accept changed output, then review the diff of
+// tests/snapshots/site.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_site() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-site");
+    let _ = fs::remove_dir_all(&dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg("site")
+        .arg(&dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let snapshots = Path::new("tests/snapshots/site");
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "2019-08-08-await-a-minute.html",
+            "2019-10-01-reference-types.html",
+            "2019-12-09-soundness-bugs.html",
+            "2020-02-20-triage-scale.html",
+            "index.html",
+            "style.css",
+        ],
+    );
+
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if update {
+        fs::create_dir_all(snapshots).unwrap();
+    }
+    for name in &names {
+        let actual = fs::read_to_string(dir.join(name)).unwrap();
+        let path = snapshots.join(name);
+        if update {
+            fs::write(&path, actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            actual == expected,
+            "{} does not match, rerun with UPDATE_SNAPSHOTS=1 to update\n\n{}",
+            path.display(),
+            actual,
+        );
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_markup() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-site-markup");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    let lib = "//! Essays\n\n#[path = \"2021-01-02-markup.rs\"]\nmod _01;\n";
+    fs::write(root.join("src/lib.rs"), lib).unwrap();
+    fs::write(root.join("src/html-in-header"), "").unwrap();
+    let essay = "\
+/** # Markup
+
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.01.02*</sup>
+
+[David Tolnay]: https://github.com/dtolnay
+
+## Ärger Über
+
+```x\"><script>
+code
+```
+*/
+#[macro_export]
+macro_rules! _01__markup {
+    ({
+        date:  \"January 2, 2021\",
+        author:  \"David Tolnay\",
+    }) => {};
+}
+";
+    fs::write(root.join("src/2021-01-02-markup.rs"), essay).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .args(["site", "site"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let page = fs::read_to_string(root.join("site/2021-01-02-markup.html")).unwrap();
+
+    // The same anchor as rustdoc, which lowercases only ASCII.
+    assert!(page.contains("<h2 id=\"Ärger-Über\">Ärger Über</h2>"));
+    assert!(page.contains("<pre class=\"x&quot;&gt;&lt;script&gt;\"><code>code\n</code></pre>"));
+}
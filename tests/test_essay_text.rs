@@ -0,0 +1,71 @@
+// Golden-file tests of the gemtext and plain text exports of the essays. Run
+// with UPDATE_SNAPSHOTS=1 to accept changed output, then review the diff of
+// tests/snapshots/gemini and tests/snapshots/text.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const ESSAYS: &[&str] = &[
+    "2019-08-08-await-a-minute",
+    "2019-10-01-reference-types",
+    "2019-12-09-soundness-bugs",
+    "2020-02-20-triage-scale",
+];
+
+fn check(format: &str, extension: &str) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("essay-{format}"));
+    let _ = fs::remove_dir_all(&dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_essay"))
+        .arg(format)
+        .arg(&dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    let expected: Vec<String> = ESSAYS
+        .iter()
+        .map(|essay| format!("{essay}.{extension}"))
+        .collect();
+    assert_eq!(names, expected);
+
+    let snapshots = Path::new("tests/snapshots").join(format);
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if update {
+        fs::create_dir_all(&snapshots).unwrap();
+    }
+    for name in &names {
+        let actual = fs::read_to_string(dir.join(name)).unwrap();
+        let path = snapshots.join(name);
+        if update {
+            fs::write(&path, actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            actual == expected,
+            "{} does not match, rerun with UPDATE_SNAPSHOTS=1 to update\n\n{}",
+            path.display(),
+            actual,
+        );
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_gemini() {
+    check("gemini", "gmi");
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_text() {
+    check("text", "txt");
+}
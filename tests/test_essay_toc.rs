@@ -0,0 +1,106 @@
+// Each essay carries a table of contents below its byline, generated by `essay
+// toc` from its `##` headings. This fails when a heading was added, renamed or
+// removed without regenerating it, and checks regeneration on a scratch copy
+// of the crate.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::essay;
+use std::fs;
+use std::path::Path;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_essays() {
+    let output = essay(Path::new(env!("CARGO_MANIFEST_DIR")), &["toc", "--check"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "",
+        "table of contents is out of date, rerun `cargo run --bin essay -- toc`",
+    );
+    assert!(output.status.success());
+}
+
+const ESSAY: &str = r#"/** # Example
+
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.01.02*</sup>
+
+[David Tolnay]: https://github.com/dtolnay
+
+<br>
+
+## First *part*
+
+### Not listed
+
+## Second `code` [part]
+
+## First part
+
+## A `` `tick` `` span
+
+## [Elsewhere](https://example.com)
+
+[part]: https://example.com
+*/
+#[macro_export]
+macro_rules! _05__example {
+    ({
+        date:  "January 2, 2021",
+        author:  "David Tolnay",
+    }) => {};
+}
+"#;
+
+const CONTENTS: &str = "\
+<sup>*by [David Tolnay]&#8202;,&ensp;2021.01.02*</sup>
+
+<nav class=\"contents\">
+
+- [First part](#first-part)
+- [Second `code` part](#second-code-part)
+- [First part](#first-part-1)
+- [A `` `tick` `` span](#a-tick-span)
+
+</nav>
+
+[David Tolnay]: https://github.com/dtolnay
+";
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the essay binary")]
+fn test_update() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("essay-toc");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    let lib = "#[path = \"2021-01-02-example.rs\"]\nmod _05;\n";
+    fs::write(root.join("src/lib.rs"), lib).unwrap();
+    let path = root.join("src/2021-01-02-example.rs");
+    fs::write(&path, ESSAY).unwrap();
+
+    let output = essay(&root, &["toc", "--check"]);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "src/2021-01-02-example.rs\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&path).unwrap(), ESSAY);
+
+    let output = essay(&root, &["toc"]);
+    assert!(output.status.success());
+    let updated = fs::read_to_string(&path).unwrap();
+    assert!(updated.contains(CONTENTS), "{updated}");
+    assert!(essay(&root, &["toc", "--check"]).status.success());
+
+    // Renaming a heading makes the table stale, and regenerating replaces it.
+    fs::write(&path, updated.replace("## Second", "## Other")).unwrap();
+    assert_eq!(essay(&root, &["toc", "--check"]).status.code(), Some(1));
+    assert!(essay(&root, &["toc"]).status.success());
+    let renamed = fs::read_to_string(&path).unwrap();
+    assert_eq!(renamed.matches("<nav class=\"contents\">").count(), 1);
+    assert!(
+        renamed.contains("- [Other `code` part](#other-code-part)\n"),
+        "{renamed}"
+    );
+}
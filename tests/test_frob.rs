@@ -0,0 +1,48 @@
+// Safe callers of the frob bindings from the soundness bugs essay. Only the
+// UNSOUND binding lets safe code reach the out-of-bounds write in ffi::frob,
+// which is why that test is ignored by default. CI runs it on its own under
+// `cargo miri test -- --ignored` and requires Miri to report the UB.
+
+use dtolnay::soundness_bugs::frob::{frob, frob_level, frob_unchecked, frob_unsound, FrobLevel};
+use std::panic;
+
+#[test]
+fn test_checked() {
+    for arg in -3..=20 {
+        let result = panic::catch_unwind(|| frob(arg));
+        assert_eq!(result.is_err(), arg == 10, "frob({arg})");
+    }
+}
+
+#[test]
+fn test_level() {
+    for level in [
+        FrobLevel::Low,
+        FrobLevel::Medium,
+        FrobLevel::High,
+        FrobLevel::Critical,
+    ] {
+        frob_level(level);
+    }
+}
+
+#[test]
+fn test_unchecked() {
+    for arg in 0..10 {
+        // SAFETY: arg is never 10.
+        unsafe { frob_unchecked(arg) }
+    }
+}
+
+#[test]
+fn test_unsound_not_10() {
+    for arg in (-3..=20).filter(|&arg| arg != 10) {
+        frob_unsound(arg);
+    }
+}
+
+#[test]
+#[ignore = "undefined behavior; run under Miri to see it reported"]
+fn test_unsound_10() {
+    frob_unsound(10);
+}
@@ -0,0 +1,98 @@
+// Exact outcome sets of small programs built around the essay's reset
+// function, `COUNTER.store(0, Ordering::Release)`.
+
+use dtolnay::reference_types::memory_model::{Instr, Outcome, Program};
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
+
+fn outcomes(list: &[(&[&[u32]], &[u32])]) -> BTreeSet<Outcome> {
+    list.iter()
+        .map(|(reads, memory)| Outcome {
+            reads: reads.iter().map(|reads| reads.to_vec()).collect(),
+            memory: memory.to_vec(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_reset_races_with_increments() {
+    let mut program = Program::new();
+    let counter = program.atomic(5);
+    program.thread([
+        Instr::FetchAdd(counter, 1, Ordering::Relaxed),
+        Instr::FetchAdd(counter, 1, Ordering::Relaxed),
+    ]);
+    program.thread([Instr::Store(counter, 0, Ordering::Release)]);
+
+    // Every ordering gives the same results here: a single atomic is always
+    // seen consistently, and increments are never lost except by the reset.
+    let expected = outcomes(&[
+        (&[&[5, 6], &[]], &[0]),
+        (&[&[5, 0], &[]], &[1]),
+        (&[&[0, 1], &[]], &[2]),
+    ]);
+    assert_eq!(program.outcomes(), expected);
+}
+
+fn reset_after_write(store: Ordering, load: Ordering) -> BTreeSet<Outcome> {
+    let mut program = Program::new();
+    let data = program.atomic(0);
+    let counter = program.atomic(1);
+    program.thread([
+        Instr::Store(data, 42, Ordering::Relaxed),
+        Instr::Store(counter, 0, store),
+    ]);
+    program.thread([
+        Instr::Load(counter, load),
+        Instr::Load(data, Ordering::Relaxed),
+    ]);
+    program.outcomes()
+}
+
+#[test]
+fn test_reset_release_acquire() {
+    let expected = outcomes(&[
+        (&[&[], &[0, 42]], &[42, 0]),
+        (&[&[], &[1, 0]], &[42, 0]),
+        (&[&[], &[1, 42]], &[42, 0]),
+    ]);
+    assert_eq!(
+        reset_after_write(Ordering::Release, Ordering::Acquire),
+        expected
+    );
+    assert_eq!(
+        reset_after_write(Ordering::SeqCst, Ordering::SeqCst),
+        expected
+    );
+}
+
+#[test]
+fn test_reset_relaxed() {
+    // Seeing the reset says nothing about whether the data is visible.
+    let expected = outcomes(&[
+        (&[&[], &[0, 0]], &[42, 0]),
+        (&[&[], &[0, 42]], &[42, 0]),
+        (&[&[], &[1, 0]], &[42, 0]),
+        (&[&[], &[1, 42]], &[42, 0]),
+    ]);
+    assert_eq!(
+        reset_after_write(Ordering::Relaxed, Ordering::Relaxed),
+        expected
+    );
+    assert_eq!(
+        reset_after_write(Ordering::Release, Ordering::Relaxed),
+        expected
+    );
+    assert_eq!(
+        reset_after_write(Ordering::Relaxed, Ordering::Acquire),
+        expected
+    );
+}
+
+#[test]
+#[should_panic = "there is no such thing as an acquire store"]
+fn test_acquire_store() {
+    let mut program = Program::new();
+    let counter = program.atomic(0);
+    program.thread([Instr::Store(counter, 0, Ordering::Acquire)]);
+}
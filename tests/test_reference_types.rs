@@ -0,0 +1,84 @@
+// Contended use of the from-scratch Mutex and RwLock from the reference types
+// companion module. Run under Miri, these also check that the atomic orderings
+// are strong enough for the data behind the lock to be free of data races.
+
+use dtolnay::reference_types::cell::Cell;
+use dtolnay::reference_types::mutex::Mutex;
+use dtolnay::reference_types::refcell::RefCell;
+use dtolnay::reference_types::rwlock::RwLock;
+use std::thread;
+
+const THREADS: usize = if cfg!(miri) { 3 } else { 8 };
+const ITERATIONS: usize = if cfg!(miri) { 20 } else { 1_000 };
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_auto_traits() {
+    assert_send::<Cell<i32>>();
+    assert_send::<RefCell<i32>>();
+    assert_sync::<Mutex<Cell<i32>>>();
+    assert_sync::<RwLock<i32>>();
+}
+
+#[test]
+fn test_mutex() {
+    // Non-atomic read-modify-write of a plain String, which would lose or
+    // corrupt updates if two threads were ever inside the lock at once.
+    let mutex = Mutex::new(String::new());
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..ITERATIONS {
+                    let mut guard = mutex.lock();
+                    let len = guard.len();
+                    guard.push('x');
+                    assert_eq!(guard.len(), len + 1);
+                }
+            });
+        }
+    });
+    assert_eq!(mutex.into_inner().len(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn test_rwlock() {
+    let lock = RwLock::new(vec![0usize]);
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..ITERATIONS {
+                    let mut vec = lock.write();
+                    let next = vec.len();
+                    vec.push(next);
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..ITERATIONS {
+                    let vec = lock.read();
+                    assert!(vec.iter().enumerate().all(|(i, &n)| i == n));
+                }
+            });
+        }
+    });
+    assert_eq!(lock.read().len(), THREADS * ITERATIONS + 1);
+}
+
+#[test]
+fn test_refcell_borrows() {
+    let cell = RefCell::new(1);
+    {
+        let a = cell.borrow();
+        let b = cell.borrow();
+        assert_eq!(*a + *b, 2);
+        assert!(cell.try_borrow_mut().is_none());
+    }
+    *cell.borrow_mut() += 1;
+    {
+        let _writer = cell.borrow_mut();
+        assert!(cell.try_borrow().is_none());
+        assert!(cell.try_borrow_mut().is_none());
+    }
+    assert_eq!(cell.into_inner(), 2);
+}
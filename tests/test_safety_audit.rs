@@ -0,0 +1,42 @@
+// Runs the safety comment auditor over the code snippets of the soundness bugs
+// essay, as shown to the reader. Only `frob_unchecked` follows the convention
+// of explaining its unsafe code in a `// Safety:` comment.
+
+#[path = "common/mod.rs"]
+mod common;
+
+use common::code_blocks;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const ESSAY: &str = "src/2019-12-09-soundness-bugs.rs";
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the safety-audit binary")]
+fn test_soundness_bugs_snippets() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("safety-audit");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let essay = fs::read_to_string(ESSAY).unwrap();
+    let blocks = code_blocks(&essay);
+    assert_eq!(blocks.len(), 5);
+    for (i, block) in blocks.iter().enumerate() {
+        fs::write(dir.join(format!("snippet{i}.rs")), &block.visible).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_safety-audit"))
+        .arg(".")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let expected = "\
+./snippet1.rs:3: unsafe block has no `// Safety:` comment
+./snippet2.rs:10: unsafe block has no `// Safety:` comment
+./snippet4.rs:3: unsafe block has no `// Safety:` comment
+";
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
@@ -0,0 +1,158 @@
+// Reviewer recommendations for a small team, checked against the full
+// explanation so that any change in scoring shows up as a readable diff.
+
+use dtolnay::triage_scale::assign::{recommend, Member, PullRequest, Team, Weights};
+use dtolnay::triage_scale::notifications::{Change, Edit, Notifications};
+
+fn team() -> Team {
+    Team {
+        name: "libs".to_owned(),
+        members: vec![
+            Member::new("amanieu", ["library/core/src/sync", "library/std/src/sync"]),
+            Member::new("dtolnay", ["library/proc_macro", "library/core/src/fmt"]),
+            Member::new("m-ou-se", ["library/std/src/sync", "library/core/src/fmt/"]),
+            Member::new("joshtriplett", []),
+        ],
+    }
+}
+
+fn queues(lengths: &[(&str, usize)]) -> Notifications {
+    let mut notifications = Notifications::new();
+    for &(user, len) in lengths {
+        for i in 0..len {
+            notifications
+                .apply(Edit {
+                    at: 1_582_156_800,
+                    editor: "triage".to_owned(),
+                    user: user.to_owned(),
+                    change: Change::Add {
+                        index: None,
+                        url: format!("https://github.com/rust-lang/rust/pull/{i}"),
+                        description: String::new(),
+                    },
+                })
+                .unwrap();
+        }
+    }
+    notifications
+}
+
+fn pr(author: &str, files: &[&str], exclude: &[&str]) -> PullRequest {
+    PullRequest {
+        author: author.to_owned(),
+        files: files.iter().copied().map(str::to_owned).collect(),
+        exclude: exclude.iter().copied().map(str::to_owned).collect(),
+    }
+}
+
+#[test]
+fn test_expertise_outweighs_short_queue() {
+    let notifications = queues(&[("amanieu", 6), ("dtolnay", 2), ("m-ou-se", 4)]);
+    let pr = pr(
+        "jane",
+        &[
+            "library/std/src/sync/mutex.rs",
+            "library/std/src/sync/rwlock.rs",
+        ],
+        &[],
+    );
+    let recommendation = recommend(&team(), &notifications, &pr, &Weights::default()).unwrap();
+    assert_eq!(
+        recommendation.to_string(),
+        "\
+libs: recommending m-ou-se
+- m-ou-se: +100 expertise (2 of 2 files), -40 load (4 queued) = +60
+- amanieu: +100 expertise (2 of 2 files), -60 load (6 queued) = +40
+- joshtriplett: +0 expertise (0 of 2 files), +0 load (0 queued) = +0
+- dtolnay: +0 expertise (0 of 2 files), -20 load (2 queued) = -20
+",
+    );
+}
+
+#[test]
+fn test_stale_reassignment() {
+    // The assigned reviewer let the PR go stale; the author is on the team.
+    let notifications = queues(&[("amanieu", 1), ("dtolnay", 9)]);
+    let pr = pr(
+        "amanieu",
+        &["library/core/src/fmt/mod.rs", "library/core/src/fmt/rt.rs"],
+        &["dtolnay"],
+    );
+    let recommendation = recommend(&team(), &notifications, &pr, &Weights::default()).unwrap();
+    assert_eq!(
+        recommendation.to_string(),
+        "\
+libs: recommending m-ou-se
+- m-ou-se: +100 expertise (2 of 2 files), +0 load (0 queued) = +100
+- joshtriplett: +0 expertise (0 of 2 files), +0 load (0 queued) = +0
+",
+    );
+}
+
+#[test]
+fn test_area_matches_whole_components() {
+    let notifications = Notifications::new();
+    let pr = pr("jane", &["library/proc_macro_bridge/lib.rs"], &[]);
+    let recommendation = recommend(&team(), &notifications, &pr, &Weights::default()).unwrap();
+    assert!(recommendation.ranking.iter().all(|c| c.matched == 0));
+}
+
+#[test]
+fn test_ties() {
+    // Equal totals go to the shorter queue, then alphabetically.
+    let notifications = queues(&[("dtolnay", 5)]);
+    let weights = Weights {
+        expertise: 50,
+        per_item: 10,
+    };
+    let pr = pr("jane", &["library/proc_macro/src/lib.rs"], &[]);
+    let recommendation = recommend(&team(), &notifications, &pr, &weights).unwrap();
+    let ranking: Vec<(&str, i64)> = recommendation
+        .ranking
+        .iter()
+        .map(|candidate| (candidate.name.as_str(), candidate.total))
+        .collect();
+    assert_eq!(
+        ranking,
+        [
+            ("amanieu", 0),
+            ("joshtriplett", 0),
+            ("m-ou-se", 0),
+            ("dtolnay", 0)
+        ],
+    );
+}
+
+#[test]
+fn test_nobody_eligible() {
+    let team = Team {
+        name: "solo".to_owned(),
+        members: vec![Member::new("dtolnay", [])],
+    };
+    let pr = pr("dtolnay", &["README.md"], &[]);
+    assert!(recommend(&team, &Notifications::new(), &pr, &Weights::default()).is_none());
+}
+
+#[test]
+fn test_extreme_weights() {
+    // A negative per-item weight rewards a long queue, and nothing overflows.
+    let notifications = queues(&[("dtolnay", 3)]);
+    let weights = Weights {
+        expertise: i64::MAX,
+        per_item: i64::MIN,
+    };
+    let pr = pr(
+        "jane",
+        &["library/proc_macro/src/lib.rs"],
+        &["amanieu", "m-ou-se"],
+    );
+    let recommendation = recommend(&team(), &notifications, &pr, &weights).unwrap();
+    assert_eq!(
+        recommendation.to_string(),
+        "\
+libs: recommending dtolnay
+- dtolnay: +9223372036854775807 expertise (1 of 1 file), +9223372036854775807 load (3 queued) = +9223372036854775807
+- joshtriplett: +0 expertise (0 of 1 file), +0 load (0 queued) = +0
+",
+    );
+}
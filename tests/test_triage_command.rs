@@ -0,0 +1,104 @@
+// Table of triage chat commands, each with either the command it parses to or
+// the error message and the span of input it points at.
+
+use dtolnay::triage_scale::command::Command;
+use dtolnay::triage_scale::notifications::{Change, Notifications};
+
+const URL: &str = "https://github.com/dtolnay/syn/issues/700";
+
+fn command(change: Change) -> Command {
+    Command {
+        user: "dtolnay".to_owned(),
+        change,
+    }
+}
+
+fn add(index: Option<usize>, description: &str) -> Change {
+    Change::Add {
+        index,
+        url: URL.to_owned(),
+        description: description.to_owned(),
+    }
+}
+
+#[test]
+fn test_parse() {
+    #[rustfmt::skip]
+    let cases = [
+        ("@dtolnay add https://github.com/dtolnay/syn/issues/700", Ok(command(add(None, "")))),
+        ("@dtolnay add https://github.com/dtolnay/syn/issues/700 Question about  attributes ", Ok(command(add(None, "Question about  attributes")))),
+        ("  @dtolnay   add 2 https://github.com/dtolnay/syn/issues/700 Question", Ok(command(add(Some(1), "Question")))),
+        ("@dtolnay move 3 1", Ok(command(Change::Move { from: 2, to: 0 }))),
+        ("@dtolnay remove 2", Ok(command(Change::Remove { index: 1 }))),
+        ("@dtolnay tag 1 beta-nominated", Ok(command(Change::Tag { index: 0, tag: "beta-nominated".to_owned() }))),
+        ("@dtolnay untag 1 p-high", Ok(command(Change::Untag { index: 0, tag: "p-high".to_owned() }))),
+        ("", Err(("empty command", ""))),
+        ("   ", Err(("empty command", "   "))),
+        ("dtolnay add https://example.com", Err(("expected `@user` whose list to edit, found `dtolnay`", "dtolnay"))),
+        ("@ add https://example.com", Err(("invalid user name `@`", "@"))),
+        ("@dtol/nay remove 1", Err(("invalid user name `@dtol/nay`", "@dtol/nay"))),
+        ("@dtolnay", Err(("expected a command after `@dtolnay`", ""))),
+        ("@dtolnay frob 1", Err(("unknown command `frob`, expected add, move, remove, tag or untag", "frob"))),
+        ("@dtolnay add", Err(("`add` needs a URL", ""))),
+        ("@dtolnay add 2", Err(("`add` needs a URL after the position", ""))),
+        ("@dtolnay add syn#700", Err(("expected a URL starting with https:// or http://, found `syn#700`", "syn#700"))),
+        ("@dtolnay add 2x https://example.com", Err(("expected a position, found `2x`", "2x"))),
+        ("@dtolnay add 0 https://example.com", Err(("positions start at 1", "0"))),
+        ("@dtolnay move", Err(("`move` needs a position to move from", ""))),
+        ("@dtolnay move 3", Err(("`move` needs a position to move to", ""))),
+        ("@dtolnay move 3 first", Err(("expected a position, found `first`", "first"))),
+        ("@dtolnay move -1 2", Err(("expected a position, found `-1`", "-1"))),
+        ("@dtolnay remove", Err(("`remove` needs a position", ""))),
+        ("@dtolnay remove 1 2 3 ", Err(("unexpected `2` after `remove` command", "2 3"))),
+        ("@dtolnay tag", Err(("`tag` needs a position", ""))),
+        ("@dtolnay untag 1", Err(("expected a tag after the position", ""))),
+        ("@dtolnay tag 1 Beta_Nominated", Err(("invalid tag `Beta_Nominated`, tags are lowercase letters, digits and dashes", "Beta_Nominated"))),
+    ];
+
+    for (input, expected) in cases {
+        let actual = input.parse::<Command>().map_err(|err| {
+            let span = &input[err.span.clone()];
+            (err.to_string(), span.to_owned())
+        });
+        let expected = expected.map_err(|(msg, span)| (msg.to_owned(), span.to_owned()));
+        assert_eq!(actual, expected, "{input:?}");
+    }
+}
+
+#[test]
+fn test_execute() {
+    let mut notifications = Notifications::new();
+    let mut run = |input: &str| {
+        let command: Command = input.parse().unwrap();
+        notifications
+            .apply(command.into_edit("jane", 1_582_156_800))
+            .map_err(|err| err.to_string())
+    };
+
+    run("@dtolnay add https://example.com/a A").unwrap();
+    run("@dtolnay add https://example.com/b B").unwrap();
+    run("@dtolnay add 1 https://example.com/c C").unwrap();
+    run("@dtolnay move 3 2").unwrap();
+    run("@dtolnay tag 1 urgent").unwrap();
+    run("@dtolnay tag 1 urgent").unwrap();
+    run("@dtolnay tag 2 later").unwrap();
+    run("@dtolnay untag 2 later").unwrap();
+    run("@dtolnay remove 3").unwrap();
+    assert_eq!(
+        run("@dtolnay remove 3").unwrap_err(),
+        "dtolnay's list has 2 items, there is no position 3",
+    );
+    assert_eq!(
+        run("@ghost tag 1 urgent").unwrap_err(),
+        "ghost's list has 0 items, there is no position 1",
+    );
+
+    let list = notifications.list("dtolnay");
+    let summary: Vec<(&str, &[String])> = list
+        .iter()
+        .map(|item| (item.description.as_str(), item.tags.as_slice()))
+        .collect();
+    assert_eq!(summary, [("C", &["urgent".to_owned()][..]), ("B", &[][..])]);
+    assert!(list.iter().all(|item| item.added_by == "jane"));
+    assert_eq!(notifications.users().collect::<Vec<_>>(), ["dtolnay"]);
+}
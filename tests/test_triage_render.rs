@@ -0,0 +1,108 @@
+// Snapshot tests of a team member's triage page. Run with UPDATE_SNAPSHOTS=1
+// to accept changed output, then review the diff of tests/snapshots.
+
+use dtolnay::triage_scale::notifications::Item;
+use dtolnay::triage_scale::render;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn list() -> Vec<Item> {
+    vec![
+        Item {
+            url: "https://github.com/rust-lang/rust/pull/69000".to_owned(),
+            description: "Review proc_macro::Span::start & end".to_owned(),
+            added_by: "Mark-Simulacrum".to_owned(),
+            added_at: 1_582_156_800,
+            tags: vec!["beta-nominated".to_owned(), "p-high".to_owned()],
+        },
+        Item {
+            url: "https://github.com/dtolnay/syn/issues/700".to_owned(),
+            description: "Would it be okay if I take this issue? <script>alert(1)</script>"
+                .to_owned(),
+            added_by: "jane".to_owned(),
+            added_at: 1_582_243_199,
+            tags: Vec::new(),
+        },
+        Item {
+            url:
+                "https://internals.rust-lang.org/t/triagebot-notifications/11857?u=dtolnay&page=(2)"
+                    .to_owned(),
+            description: String::new(),
+            added_by: "dtolnay".to_owned(),
+            added_at: 1_709_251_200,
+            tags: Vec::new(),
+        },
+    ]
+}
+
+fn check(name: &str, actual: &str) {
+    let path = Path::new("tests/snapshots").join(name);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "{} does not match, rerun with UPDATE_SNAPSHOTS=1 to update\n\n{}",
+        path.display(),
+        actual,
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads snapshot files")]
+fn test_html() {
+    check("triage-dtolnay.html", &render::html("dtolnay", &list()));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads snapshot files")]
+fn test_html_empty() {
+    check("triage-empty.html", &render::html("m-ou-se", &[]));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads snapshot files")]
+fn test_markdown() {
+    let page = "https://triage.example.com/dtolnay";
+    check(
+        "triage-dtolnay.md",
+        &render::markdown("dtolnay", &list(), Some(page)),
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads snapshot files")]
+fn test_markdown_empty() {
+    check("triage-empty.md", &render::markdown("m-ou-se", &[], None));
+}
+
+#[test]
+fn test_hostile() {
+    let list = [Item {
+        url: "javascript:alert(1)".to_owned(),
+        description: String::new(),
+        added_by: "jane @rust-lang/libs [x](https://example.com)".to_owned(),
+        added_at: 1_582_156_800,
+        tags: Vec::new(),
+    }];
+
+    let html = render::html("dtolnay", &list);
+    assert!(!html.contains("href"));
+    assert!(html.contains("<li>javascript:alert(1)<br>"));
+
+    assert_eq!(
+        render::markdown("dtolnay", &list, None),
+        "\
+**@dtolnay's queue** has 1 item:
+
+1. javascript:alert(1) &mdash; added by `jane @rust-lang/libs [x](https://example.com)` on 2020-02-20
+",
+    );
+    assert_eq!(
+        render::markdown("@everyone *", &[], None),
+        "**`@everyone *`'s queue** is empty.\n",
+    );
+}
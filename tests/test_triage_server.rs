@@ -0,0 +1,208 @@
+// Spins up the triage-server binary on a loopback port and drives it over
+// HTTP, the way a browser or chat bot would.
+
+use dtolnay::triage_scale::notifications::{Change, Edit};
+use dtolnay::triage_scale::store::Store;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+struct Server {
+    child: Child,
+    addr: String,
+    store: PathBuf,
+}
+
+impl Server {
+    fn start(name: &str) -> Self {
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("triage-server");
+        fs::create_dir_all(&dir).unwrap();
+        let store = dir.join(name);
+        let _ = fs::remove_file(&store);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_triage-server"))
+            .args(["--addr", "127.0.0.1:0"])
+            .arg(&store)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line
+            .trim()
+            .strip_prefix("listening on http://")
+            .unwrap()
+            .to_owned();
+        Server { child, addr, store }
+    }
+
+    fn request(&self, method: &str, path: &str, headers: &[&str], body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        let mut request = format!("{method} {path} HTTP/1.1\r\nHost: {}\r\n", self.addr);
+        for header in headers {
+            request.push_str(header);
+            request.push_str("\r\n");
+        }
+        let _ = write!(request, "Content-Length: {}\r\n\r\n{body}", body.len());
+        io::Write::write_all(&mut stream, request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.to_owned())
+    }
+
+    fn get(&self, path: &str) -> (u16, String) {
+        self.request("GET", path, &[], "")
+    }
+
+    fn command(&self, editor: &str, command: &str) -> (u16, String) {
+        let header = format!("X-Editor: {editor}");
+        self.request("POST", "/command", &[&header], command)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// The JSON with the timestamps, which depend on the clock, blanked out.
+fn without_timestamps(json: &str) -> String {
+    let mut out = String::new();
+    let mut rest = json;
+    while let Some(i) = rest.find("\"added_at\":") {
+        let (before, after) = rest.split_at(i + "\"added_at\":".len());
+        out.push_str(before);
+        out.push('0');
+        rest = after.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    }
+    out.push_str(rest);
+    out
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the triage-server binary")]
+fn test_edit_and_view() {
+    let server = Server::start("edit-and-view");
+
+    let (status, _) = server.command(
+        "jane",
+        "@dtolnay add https://github.com/dtolnay/syn/issues/700 Question about \"attributes\"",
+    );
+    assert_eq!(status, 200);
+    let (status, body) = server.command(
+        "Mark-Simulacrum",
+        "@dtolnay add 1 https://github.com/rust-lang/rust/pull/69000 Review span API",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(
+        without_timestamps(&body),
+        concat!(
+            r#"{"user":"dtolnay","items":["#,
+            r#"{"url":"https://github.com/rust-lang/rust/pull/69000","description":"Review span API","added_by":"Mark-Simulacrum","added_at":0,"tags":[]},"#,
+            r#"{"url":"https://github.com/dtolnay/syn/issues/700","description":"Question about \"attributes\"","added_by":"jane","added_at":0,"tags":[]}"#,
+            "]}\n",
+        ),
+    );
+
+    let (status, _) = server.command("jane", "@dtolnay tag 2 question");
+    assert_eq!(status, 200);
+    let (status, _) = server.command("jane", "@dtolnay move 2 1");
+    assert_eq!(status, 200);
+
+    let (status, body) = server.get("/dtolnay.json");
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""added_by":"jane","added_at":"#));
+    assert!(body.contains(r#""tags":["question"]"#));
+    let jane = body.find("jane").unwrap();
+    let mark = body.find("Mark-Simulacrum").unwrap();
+    assert!(jane < mark);
+
+    let (status, body) = server.get("/dtolnay");
+    assert_eq!(status, 200);
+    assert!(body.starts_with("<!DOCTYPE html>"));
+    assert!(body.contains("Question about &quot;attributes&quot;"));
+
+    let (status, body) = server.get("/dtolnay.md");
+    assert_eq!(status, 200);
+    assert!(body.starts_with("**@dtolnay's queue** has 2 items:"));
+
+    let (status, body) = server.get("/");
+    assert_eq!(status, 200);
+    assert!(body.contains("<a href=\"/dtolnay\">dtolnay</a> (2)"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the triage-server binary")]
+fn test_errors() {
+    let server = Server::start("errors");
+
+    assert_eq!(
+        server.command("jane", "@dtolnay move 3"),
+        (400, "`move` needs a position to move to\n".to_owned()),
+    );
+    assert_eq!(
+        server.command("jane", "@dtolnay remove 1"),
+        (
+            400,
+            "dtolnay's list has 0 items, there is no position 1\n".to_owned()
+        ),
+    );
+    assert_eq!(
+        server.request("POST", "/command", &[], "@dtolnay remove 1"),
+        (400, "missing X-Editor header\n".to_owned()),
+    );
+    assert_eq!(
+        server.get("/nobody.json").1,
+        "{\"user\":\"nobody\",\"items\":[]}\n"
+    );
+    assert_eq!(server.get("/../etc/passwd").0, 404);
+    assert_eq!(server.get("/dtolnay.exe").0, 404);
+    assert_eq!(server.request("DELETE", "/dtolnay", &[], "").0, 405);
+    assert_eq!(server.request("POST", "/nope/nope", &[], "").0, 404);
+    assert_eq!(server.request("POST", "/dtolnay.exe", &[], "").0, 404);
+
+    let long = format!("X-Padding: {}", "x".repeat(10_000));
+    assert_eq!(server.request("GET", "/dtolnay", &[&long], "").0, 400);
+    let many: Vec<String> = (0..200).map(|i| format!("X-Header-{i}: {i}")).collect();
+    let many: Vec<&str> = many.iter().map(String::as_str).collect();
+    assert_eq!(server.request("GET", "/dtolnay", &many, "").0, 400);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the triage-server binary")]
+fn test_index_escaping() {
+    let server = Server::start("index-escaping");
+
+    // The log may be written by anything, not only by this server's command
+    // parser, so the user name is not necessarily a valid one.
+    let store = Store::open(&server.store).unwrap();
+    store
+        .apply(Edit {
+            at: 1_582_156_800,
+            editor: "jane".to_owned(),
+            user: "<b>\"a&b\" c/d</b>".to_owned(),
+            change: Change::Add {
+                index: None,
+                url: "https://github.com/dtolnay/syn/issues/700".to_owned(),
+                description: String::new(),
+            },
+        })
+        .unwrap();
+
+    let (status, body) = server.get("/");
+    assert_eq!(status, 200);
+    assert!(body.contains(concat!(
+        "<a href=\"/%3Cb%3E%22a%26b%22%20c%2Fd%3C%2Fb%3E\">",
+        "&lt;b&gt;&quot;a&amp;b&quot; c/d&lt;/b&gt;</a> (1)",
+    )));
+}
@@ -0,0 +1,176 @@
+// Concurrent edits to the triage notifications file store, each thread going
+// through its own Store handle as separate processes would.
+
+use dtolnay::triage_scale::notifications::{Change, Edit};
+use dtolnay::triage_scale::store::{Error, Store};
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+
+fn store(name: &str) -> Store {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("triage-store");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = fs::remove_file(&path);
+    Store::open(path).unwrap()
+}
+
+fn add(editor: &str, user: &str, index: Option<usize>, url: &str) -> Edit {
+    Edit {
+        at: 1_582_156_800,
+        editor: editor.to_owned(),
+        user: user.to_owned(),
+        change: Change::Add {
+            index,
+            url: url.to_owned(),
+            description: format!("added by {editor}"),
+        },
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "locks files")]
+fn test_concurrent_appends() {
+    const THREADS: usize = 8;
+    const EDITS: usize = 25;
+
+    let store = store("concurrent-appends");
+    thread::scope(|scope| {
+        for t in 0..THREADS {
+            let store = Store::open(store.path()).unwrap();
+            scope.spawn(move || {
+                let editor = format!("editor{t}");
+                for i in 0..EDITS {
+                    let url = format!("https://example.com/{t}/{i}");
+                    let index = (i % 2 == 0).then_some(0);
+                    store.apply(add(&editor, "dtolnay", index, &url)).unwrap();
+                }
+            });
+        }
+    });
+
+    let notifications = store.load().unwrap();
+    let list = notifications.list("dtolnay");
+    assert_eq!(list.len(), THREADS * EDITS);
+    assert_eq!(notifications.log().len(), THREADS * EDITS);
+    for t in 0..THREADS {
+        // Each editor's appended items are in the order they were added.
+        let appended: Vec<&str> = list
+            .iter()
+            .filter(|item| item.added_by == format!("editor{t}"))
+            .map(|item| item.url.as_str())
+            .filter(|url| url.rsplit('/').next().unwrap().parse::<usize>().unwrap() % 2 == 1)
+            .collect();
+        let expected: Vec<String> = (1..EDITS)
+            .step_by(2)
+            .map(|i| format!("https://example.com/{t}/{i}"))
+            .collect();
+        assert_eq!(appended, expected);
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "locks files")]
+fn test_concurrent_removals() {
+    const ITEMS: usize = 40;
+
+    let store = store("concurrent-removals");
+    for i in 0..ITEMS {
+        let url = format!("https://example.com/{i}");
+        store.apply(add("jane", "dtolnay", None, &url)).unwrap();
+    }
+
+    // Every thread keeps removing the first item until the list is empty. Each
+    // removal is checked against the list as left by all the others, so no
+    // two threads remove the same item and none removes past the end.
+    let removed: usize = thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let store = Store::open(store.path()).unwrap();
+                scope.spawn(move || {
+                    let mut removed = 0;
+                    loop {
+                        let edit = Edit {
+                            at: 1_582_160_400,
+                            editor: format!("triager{t}"),
+                            user: "dtolnay".to_owned(),
+                            change: Change::Remove { index: 0 },
+                        };
+                        match store.apply(edit) {
+                            Ok(_) => removed += 1,
+                            Err(Error::Edit(err)) => {
+                                assert_eq!(err.len, 0);
+                                return removed;
+                            }
+                            Err(err) => panic!("{err}"),
+                        }
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().map(|t| t.join().unwrap()).sum()
+    });
+
+    assert_eq!(removed, ITEMS);
+    let notifications = store.load().unwrap();
+    assert!(notifications.list("dtolnay").is_empty());
+    assert_eq!(notifications.log().len(), 2 * ITEMS);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "locks files")]
+fn test_escaping_and_interrupted_append() {
+    let store = store("escaping");
+    let url = "https://example.com/\ttab\\backslash\nnewline";
+    store.apply(add("jane", "dtolnay", None, url)).unwrap();
+
+    // Simulate a crash partway through writing a second edit.
+    let mut contents = fs::read_to_string(store.path()).unwrap();
+    contents.push_str("1582160400\tjane\tdtol");
+    fs::write(store.path(), contents).unwrap();
+
+    let notifications = store.load().unwrap();
+    assert_eq!(notifications.list("dtolnay")[0].url, url);
+
+    store
+        .apply(add("jane", "dtolnay", None, "https://example.com/2"))
+        .unwrap();
+    let notifications = store.load().unwrap();
+    assert_eq!(notifications.list("dtolnay").len(), 2);
+    assert_eq!(fs::read_to_string(store.path()).unwrap().lines().count(), 2);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "locks files")]
+fn test_tags() {
+    let store = store("tags");
+    store
+        .apply(add("jane", "dtolnay", None, "https://example.com/1"))
+        .unwrap();
+    let tag = Edit {
+        at: 1_582_160_400,
+        editor: "jane".to_owned(),
+        user: "dtolnay".to_owned(),
+        change: Change::Tag {
+            index: 0,
+            tag: "needs\tescape".to_owned(),
+        },
+    };
+    store.apply(tag).unwrap();
+
+    let notifications = store.load().unwrap();
+    assert_eq!(notifications.list("dtolnay")[0].tags, ["needs\tescape"]);
+    assert_eq!(fs::read_to_string(store.path()).unwrap().lines().count(), 2);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "locks files")]
+fn test_corrupt() {
+    let store = store("corrupt");
+    fs::write(store.path(), "1582160400\tjane\tdtolnay\tremove\t0\n").unwrap();
+    let err = store.load().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1: dtolnay's list has 0 items, there is no position 1",
+    );
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const SRC: &str = r#"
+mod ffi {
+    pub unsafe fn frob(_arg: i32) {}
+}
+
+extern "C" {
+    fn cxx_string_view_len(view: *const u8) -> usize;
+}
+
+pub fn frob(arg: i32) {
+    assert!(arg != 10);
+    unsafe { ffi::frob(arg) }
+}
+
+pub fn len(view: *const u8) -> usize {
+    // Not an unsafe block: "unsafe { }"
+    unsafe {
+        cxx_string_view_len(view)
+    }
+}
+
+pub fn first(slice: &[u8]) -> u8 {
+    unsafe { *slice.get_unchecked(0) }
+}
+
+unsafe impl Send for Counter {}
+"#;
+
+#[test]
+#[cfg_attr(miri, ignore = "spawns the unsafe-census binary")]
+fn test_census() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("unsafe-census");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("lib.rs"), SRC).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_unsafe-census"))
+        .args(["--verbose", "--ffi", "C++=cxx_*", "--ffi", "C=ffi"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let lib = dir.join("lib.rs");
+    let lib = lib.display();
+    let expected = format!(
+        "\
+{lib}:12: FFI to C
+{lib}:17: FFI to C++
+{lib}:23: would exist even if the whole codebase were Rust
+
+19 lines of Rust code, 68.4% safe code.
+
+- 1 unsafe block — FFI to C++
+- 1 — FFI to C
+- 1 — would exist even if the whole codebase were Rust
+
+1 unsafe fn, 1 unsafe impl.
+",
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}